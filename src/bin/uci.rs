@@ -0,0 +1,312 @@
+//! Plays chess over the UCI protocol on stdin and stdout,
+//! so the built-in engine can be used by other chess programs and tournament managers.
+
+use std::io::{self, BufRead};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+
+use chess::game::engine::{uci_score, Engine, SearchInfo, SearchLimits};
+use chess::game::history::History;
+use chess::game::moves::Move;
+use chess::game::notation::{from_uci, to_uci, to_uci_chess960};
+use chess::game::player::Player;
+use chess::game::position::Position;
+use chess::game::uci::Score;
+use chess::game::variant::VariantKind;
+
+const DEFAULT_HASH: usize = 16;
+const MAX_HASH: usize = 1024;
+const MAX_SKILL: i32 = 20;
+
+/// Time kept back when playing on the clock, for talking to the program running the engine.
+const MOVE_OVERHEAD: u64 = 50;
+
+struct UciServer {
+    /// The engine, while it is not searching.
+    engine: Option<Engine>,
+    /// The stop flag and thread of the running search, which hands the engine back.
+    search: Option<(Arc<AtomicBool>, JoinHandle<Engine>)>,
+    history: History,
+    skill: i32,
+    /// Writes castling as the king moving onto its rook, for Chess960.
+    chess960: bool,
+    /// The rules the positions are played with.
+    variant: VariantKind,
+}
+
+impl UciServer {
+    fn new() -> UciServer {
+        UciServer {
+            engine: Some(Engine::new(DEFAULT_HASH)),
+            search: None,
+            history: History::default(),
+            skill: MAX_SKILL,
+            chess960: false,
+            variant: VariantKind::Standard,
+        }
+    }
+
+    /// Stops the running search, which still answers with its best move, and takes the engine back.
+    fn finish_search(&mut self) -> &mut Engine {
+        if let Some((stop, handle)) = self.search.take() {
+            stop.store(true, Ordering::Relaxed);
+            self.engine = Some(handle.join().unwrap());
+        }
+
+        self.engine.as_mut().unwrap()
+    }
+
+    /// Handles one command, returns false once the engine should quit.
+    fn handle(&mut self, line: &str) -> bool {
+        let words: Vec<&str> = line.split_whitespace().collect();
+
+        match words.first() {
+            Some(&"uci") => {
+                println!("id name Chess");
+                println!("id author atomflunder");
+                println!(
+                    "option name Hash type spin default {} min 1 max {}",
+                    DEFAULT_HASH, MAX_HASH
+                );
+                println!(
+                    "option name Skill Level type spin default {} min 0 max {}",
+                    MAX_SKILL, MAX_SKILL
+                );
+                println!("option name UCI_Chess960 type check default false");
+
+                let variants: Vec<String> = VariantKind::ALL
+                    .iter()
+                    .map(|variant| format!("var {}", variant.uci_name()))
+                    .collect();
+                println!(
+                    "option name UCI_Variant type combo default chess {}",
+                    variants.join(" ")
+                );
+                println!("uciok");
+            }
+            Some(&"isready") => println!("readyok"),
+            Some(&"ucinewgame") => {
+                self.finish_search().new_game();
+                self.history = History::default();
+            }
+            Some(&"position") => {
+                self.finish_search();
+                self.set_position(&words[1..]);
+            }
+            Some(&"go") => {
+                self.finish_search();
+                self.go(&words[1..]);
+            }
+            Some(&"stop") => {
+                self.finish_search();
+            }
+            Some(&"setoption") => {
+                self.finish_search();
+                self.set_option(&words[1..]);
+            }
+            Some(&"quit") => {
+                self.finish_search();
+                return false;
+            }
+            // Unknown commands are ignored, like the protocol says.
+            _ => (),
+        }
+
+        true
+    }
+
+    /// Reads "startpos" or "fen <fen>", followed by the moves played since.
+    fn set_position(&mut self, words: &[&str]) {
+        let moves_index = words.iter().position(|w| *w == "moves");
+        let (setup, moves) = match moves_index {
+            Some(index) => (&words[..index], &words[index + 1..]),
+            None => (words, &[][..]),
+        };
+
+        let start = match setup.first() {
+            Some(&"startpos") => self
+                .variant
+                .rules()
+                .start_position(Position::new().with_variant(self.variant)),
+            // A FEN with the checks left or the pockets keeps its variant, whatever variant was set.
+            Some(&"fen") => match Position::from_variant_fen(&setup[1..].join(" "), self.variant) {
+                Ok(position) => position,
+                Err(e) => {
+                    println!("info string invalid fen: {}", e);
+                    return;
+                }
+            },
+            _ => {
+                println!("info string expected startpos or fen");
+                return;
+            }
+        };
+
+        let mut history = History::new(start);
+
+        for uci in moves {
+            match from_uci(history.current(), uci) {
+                Some(mv) => {
+                    let position = history.current().apply_move(mv).unwrap();
+                    history.push(mv, position);
+                }
+                None => {
+                    println!("info string illegal move {}", uci);
+                    break;
+                }
+            }
+        }
+
+        self.history = history;
+    }
+
+    /// Starts searching on its own thread, so "stop" and "isready" can still be answered.
+    fn go(&mut self, words: &[&str]) {
+        let mut limits = SearchLimits {
+            // Lower skill levels add some randomness and do not look as deep.
+            randomness: (MAX_SKILL - self.skill) * 10,
+            depth: if self.skill < MAX_SKILL {
+                Some(1 + self.skill as u32 / 4)
+            } else {
+                None
+            },
+            movetime: None,
+        };
+
+        let mut infinite = false;
+        let (mut time, mut increment, mut moves_to_go) = (None, 0, None);
+        let turn = self.history.current().turn;
+
+        let mut words = words.iter();
+
+        while let Some(word) = words.next() {
+            let mut value = || words.next().and_then(|w| w.parse::<u64>().ok());
+
+            match *word {
+                // The skill level can make the depth even lower.
+                "depth" => {
+                    if let Some(depth) = value() {
+                        limits.depth =
+                            Some(limits.depth.map_or(depth as u32, |d| d.min(depth as u32)));
+                    }
+                }
+                "movetime" => limits.movetime = value().map(Duration::from_millis),
+                "wtime" if turn == Player::White => time = value(),
+                "btime" if turn == Player::Black => time = value(),
+                "winc" if turn == Player::White => increment = value().unwrap_or(0),
+                "binc" if turn == Player::Black => increment = value().unwrap_or(0),
+                "movestogo" => moves_to_go = value(),
+                "infinite" => infinite = true,
+                _ => (),
+            }
+        }
+
+        // Using a part of the time left on the clock, but never all of it.
+        if let Some(time) = time {
+            let budget = time / moves_to_go.unwrap_or(30).max(1) + increment * 3 / 4;
+            let budget = budget.min(time.saturating_sub(MOVE_OVERHEAD)).max(10);
+
+            limits.movetime = Some(Duration::from_millis(budget));
+        }
+
+        let mut engine = self.engine.take().unwrap();
+        let stop = engine.stop.clone();
+        let history = self.history.clone();
+        let chess960 = self.chess960;
+
+        stop.store(false, Ordering::Relaxed);
+
+        let handle = thread::spawn(move || {
+            let result = engine.search(&history, limits, |info| print_info(info, chess960));
+
+            // An infinite search only answers once it is told to stop.
+            while infinite && !engine.stop.load(Ordering::Relaxed) {
+                thread::sleep(Duration::from_millis(5));
+            }
+
+            match result.best_move {
+                Some(mv) => println!("bestmove {}", write_move(mv, chess960)),
+                // The null move, there is no legal move to make.
+                None => println!("bestmove 0000"),
+            }
+
+            engine
+        });
+
+        self.search = Some((stop, handle));
+    }
+
+    /// Reads "name <name> value <value>".
+    fn set_option(&mut self, words: &[&str]) {
+        let value_index = words
+            .iter()
+            .position(|w| *w == "value")
+            .unwrap_or(words.len());
+
+        let name = words[..value_index]
+            .iter()
+            .filter(|w| **w != "name")
+            .copied()
+            .collect::<Vec<_>>()
+            .join(" ");
+        let text = words.get(value_index + 1).copied().unwrap_or("");
+        let value = text.parse::<i64>().ok();
+
+        match (name.to_ascii_lowercase().as_str(), value) {
+            ("hash", Some(megabytes)) => {
+                *self.finish_search() = Engine::new(megabytes.clamp(1, MAX_HASH as i64) as usize);
+            }
+            ("skill level", Some(skill)) => {
+                self.skill = skill.clamp(0, MAX_SKILL as i64) as i32;
+            }
+            ("uci_chess960", _) => self.chess960 = text.eq_ignore_ascii_case("true"),
+            ("uci_variant", _) => match VariantKind::parse(text) {
+                Some(variant) => self.variant = variant,
+                None => println!("info string unknown variant {}", text),
+            },
+            _ => println!("info string unknown option {}", name),
+        }
+    }
+}
+
+fn write_move(mv: Move, chess960: bool) -> String {
+    if chess960 {
+        to_uci_chess960(mv)
+    } else {
+        to_uci(mv)
+    }
+}
+
+fn print_info(info: &SearchInfo, chess960: bool) {
+    let score = match uci_score(info.score) {
+        Score::Centipawns(cp) => format!("cp {}", cp),
+        Score::Mate(moves) => format!("mate {}", moves),
+    };
+
+    let milliseconds = info.time.as_millis() as u64;
+    let pv: Vec<String> = info.pv.iter().map(|mv| write_move(*mv, chess960)).collect();
+
+    println!(
+        "info depth {} score {} nodes {} nps {} time {} pv {}",
+        info.depth,
+        score,
+        info.nodes,
+        info.nodes * 1000 / milliseconds.max(1),
+        milliseconds,
+        pv.join(" ")
+    );
+}
+
+fn main() {
+    let mut server = UciServer::new();
+
+    for line in io::stdin().lock().lines().map_while(Result::ok) {
+        if !server.handle(&line) {
+            break;
+        }
+    }
+
+    server.finish_search();
+}
@@ -0,0 +1,649 @@
+use std::sync::OnceLock;
+
+use super::board::Chessboard;
+use super::moves::{Move, MoveKind};
+use super::pieces::PieceKind;
+use super::player::Player;
+use super::position::Position;
+
+/// A set of squares, one bit per square.
+/// Bit `row * 8 + column` stands for the square (row, column), so bit 0 is a8 and bit 63 is h1.
+pub type Bitboard = u64;
+
+const WHITE: usize = 0;
+const BLACK: usize = 1;
+
+/// The eight directions a sliding piece can move in, as (row, column) steps.
+/// The first four are the rook directions, the last four the bishop directions,
+/// and every direction is followed or preceded by its opposite.
+const DIRECTIONS: [(isize, isize); 8] = [
+    (-1, 0),
+    (1, 0),
+    (0, -1),
+    (0, 1),
+    (-1, -1),
+    (1, 1),
+    (-1, 1),
+    (1, -1),
+];
+
+/// Gets the bit number of a square.
+pub fn square_index(square: (usize, usize)) -> usize {
+    square.0 * 8 + square.1
+}
+
+/// Gets the square of a bit number.
+pub fn square_at(index: usize) -> (usize, usize) {
+    (index / 8, index % 8)
+}
+
+/// Gets the bitboard with only the given square set.
+pub fn bit(square: (usize, usize)) -> Bitboard {
+    1 << square_index(square)
+}
+
+/// Goes through the set squares of a bitboard, as bit numbers.
+pub fn squares(mut bitboard: Bitboard) -> impl Iterator<Item = usize> {
+    std::iter::from_fn(move || {
+        if bitboard == 0 {
+            return None;
+        }
+
+        let index = bitboard.trailing_zeros() as usize;
+        bitboard &= bitboard - 1;
+
+        Some(index)
+    })
+}
+
+fn color_index(player: Player) -> usize {
+    if player == Player::Black {
+        BLACK
+    } else {
+        WHITE
+    }
+}
+
+/// Everything about the board that never changes, worked out once on first use.
+struct Tables {
+    knight: [Bitboard; 64],
+    king: [Bitboard; 64],
+    /// The squares a pawn of either player attacks.
+    pawn: [[Bitboard; 64]; 2],
+    /// The squares from a square to the edge of the board, for every direction.
+    rays: [[Bitboard; 64]; 8],
+    /// The squares strictly between two squares on the same line.
+    between: Box<[[Bitboard; 64]]>,
+    /// The whole line through two squares, from edge to edge.
+    line: Box<[[Bitboard; 64]]>,
+}
+
+/// Gets the square one step away in a direction, if it is still on the board.
+fn offset(index: usize, step: (isize, isize)) -> Option<usize> {
+    let (row, column) = square_at(index);
+    let row = row as isize + step.0;
+    let column = column as isize + step.1;
+
+    if (0..8).contains(&row) && (0..8).contains(&column) {
+        Some(row as usize * 8 + column as usize)
+    } else {
+        None
+    }
+}
+
+fn steps(index: usize, steps: &[(isize, isize)]) -> Bitboard {
+    steps
+        .iter()
+        .filter_map(|step| offset(index, *step))
+        .fold(0, |bitboard, to| bitboard | 1 << to)
+}
+
+impl Tables {
+    fn new() -> Tables {
+        let mut tables = Tables {
+            knight: [0; 64],
+            king: [0; 64],
+            pawn: [[0; 64]; 2],
+            rays: [[0; 64]; 8],
+            between: vec![[0; 64]; 64].into_boxed_slice(),
+            line: vec![[0; 64]; 64].into_boxed_slice(),
+        };
+
+        for index in 0..64 {
+            tables.knight[index] = steps(
+                index,
+                &[
+                    (-2, -1),
+                    (-2, 1),
+                    (-1, -2),
+                    (-1, 2),
+                    (1, -2),
+                    (1, 2),
+                    (2, -1),
+                    (2, 1),
+                ],
+            );
+            tables.king[index] = steps(index, &DIRECTIONS);
+            // White pawns move up the board, towards row 0.
+            tables.pawn[WHITE][index] = steps(index, &[(-1, -1), (-1, 1)]);
+            tables.pawn[BLACK][index] = steps(index, &[(1, -1), (1, 1)]);
+
+            for (direction, step) in DIRECTIONS.iter().enumerate() {
+                let mut square = index;
+
+                while let Some(next) = offset(square, *step) {
+                    tables.rays[direction][index] |= 1 << next;
+                    square = next;
+                }
+            }
+        }
+
+        for from in 0..64 {
+            for direction in 0..8 {
+                // The directions come in pairs, the opposite of one is right next to it.
+                let opposite = direction ^ 1;
+
+                for to in squares(tables.rays[direction][from]) {
+                    tables.between[from][to] =
+                        tables.rays[direction][from] & !tables.rays[direction][to] & !(1 << to);
+                    tables.line[from][to] =
+                        tables.rays[direction][from] | tables.rays[opposite][from] | 1 << from;
+                }
+            }
+        }
+
+        tables
+    }
+}
+
+fn tables() -> &'static Tables {
+    static TABLES: OnceLock<Tables> = OnceLock::new();
+
+    TABLES.get_or_init(Tables::new)
+}
+
+/// Gets the squares a sliding piece reaches in one direction, stopping at the first piece in the way.
+fn ray_attacks(direction: usize, index: usize, occupied: Bitboard) -> Bitboard {
+    let rays = &tables().rays[direction];
+    let attacks = rays[index];
+    let blockers = attacks & occupied;
+
+    if blockers == 0 {
+        return attacks;
+    }
+
+    // Up and left are towards bit 0, so the nearest blocker is the highest bit there.
+    let (row_step, column_step) = DIRECTIONS[direction];
+    let blocker = if row_step < 0 || (row_step == 0 && column_step < 0) {
+        63 - blockers.leading_zeros() as usize
+    } else {
+        blockers.trailing_zeros() as usize
+    };
+
+    attacks & !rays[blocker]
+}
+
+pub fn knight_attacks(index: usize) -> Bitboard {
+    tables().knight[index]
+}
+
+pub fn king_attacks(index: usize) -> Bitboard {
+    tables().king[index]
+}
+
+pub fn pawn_attacks(player: Player, index: usize) -> Bitboard {
+    tables().pawn[color_index(player)][index]
+}
+
+pub fn rook_attacks(index: usize, occupied: Bitboard) -> Bitboard {
+    (0..4).fold(0, |attacks, direction| {
+        attacks | ray_attacks(direction, index, occupied)
+    })
+}
+
+pub fn bishop_attacks(index: usize, occupied: Bitboard) -> Bitboard {
+    (4..8).fold(0, |attacks, direction| {
+        attacks | ray_attacks(direction, index, occupied)
+    })
+}
+
+pub fn queen_attacks(index: usize, occupied: Bitboard) -> Bitboard {
+    rook_attacks(index, occupied) | bishop_attacks(index, occupied)
+}
+
+/// Gets the squares strictly between two squares, or nothing if they are not on one line.
+pub fn between(from: usize, to: usize) -> Bitboard {
+    tables().between[from][to]
+}
+
+/// Gets the whole line through two squares, or nothing if they are not on one line.
+pub fn line(from: usize, to: usize) -> Bitboard {
+    tables().line[from][to]
+}
+
+/// The pieces on a chessboard as bitboards, one for every player and kind of piece.
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Default)]
+pub struct Bitboards {
+    /// White and Black, each in the order of `PieceKind`.
+    pub pieces: [[Bitboard; 6]; 2],
+    /// All pieces of White and Black.
+    pub colors: [Bitboard; 2],
+    pub occupied: Bitboard,
+}
+
+impl Bitboards {
+    pub fn from_board(board: &Chessboard) -> Bitboards {
+        let mut bitboards = Bitboards::default();
+
+        for (row, pieces) in board.board.iter().enumerate() {
+            for (column, piece) in pieces.iter().enumerate() {
+                if let Some(kind) = PieceKind::from_char(*piece) {
+                    let color = if piece.is_uppercase() { WHITE } else { BLACK };
+                    let square = bit((row, column));
+
+                    bitboards.pieces[color][kind as usize] |= square;
+                    bitboards.colors[color] |= square;
+                    bitboards.occupied |= square;
+                }
+            }
+        }
+
+        bitboards
+    }
+
+    /// Gets the pieces of one kind of a player.
+    pub fn piece(&self, player: Player, kind: PieceKind) -> Bitboard {
+        self.pieces[color_index(player)][kind as usize]
+    }
+
+    /// Gets the pieces of both players that attack a square, with the given pieces in the way.
+    pub fn attackers_to(&self, index: usize, occupied: Bitboard) -> Bitboard {
+        let both =
+            |kind: PieceKind| self.pieces[WHITE][kind as usize] | self.pieces[BLACK][kind as usize];
+        let queens = both(PieceKind::Queen);
+
+        // A white pawn attacks the square if a black pawn on the square would attack the white pawn.
+        (pawn_attacks(Player::Black, index) & self.pieces[WHITE][PieceKind::Pawn as usize])
+            | (pawn_attacks(Player::White, index) & self.pieces[BLACK][PieceKind::Pawn as usize])
+            | (knight_attacks(index) & both(PieceKind::Knight))
+            | (king_attacks(index) & both(PieceKind::King))
+            | (bishop_attacks(index, occupied) & (both(PieceKind::Bishop) | queens))
+            | (rook_attacks(index, occupied) & (both(PieceKind::Rook) | queens))
+    }
+
+    /// Returns true if any piece of the player attacks the square.
+    pub fn is_attacked(&self, index: usize, by: Player) -> bool {
+        self.attackers_to(index, self.occupied) & self.colors[color_index(by)] != 0
+    }
+
+    /// Gets every square the player attacks, whether there is a piece on it or not.
+    pub fn attacks(&self, player: Player) -> Bitboard {
+        let mut attacks = 0;
+
+        for kind in [
+            PieceKind::Pawn,
+            PieceKind::Knight,
+            PieceKind::Bishop,
+            PieceKind::Rook,
+            PieceKind::Queen,
+            PieceKind::King,
+        ] {
+            for index in squares(self.piece(player, kind)) {
+                attacks |= match kind {
+                    PieceKind::Pawn => pawn_attacks(player, index),
+                    PieceKind::Knight => knight_attacks(index),
+                    PieceKind::Bishop => bishop_attacks(index, self.occupied),
+                    PieceKind::Rook => rook_attacks(index, self.occupied),
+                    PieceKind::Queen => queen_attacks(index, self.occupied),
+                    PieceKind::King => king_attacks(index),
+                };
+            }
+        }
+
+        attacks
+    }
+
+    /// Gets the square of the king of a player, if there is one.
+    fn king_square(&self, player: Player) -> Option<usize> {
+        let king = self.piece(player, PieceKind::King);
+
+        (king != 0).then(|| king.trailing_zeros() as usize)
+    }
+
+    /// Gets the pieces giving check to the king of the player.
+    pub fn checkers(&self, player: Player) -> Bitboard {
+        match self.king_square(player) {
+            Some(king) => {
+                self.attackers_to(king, self.occupied) & self.colors[color_index(player.opponent())]
+            }
+            None => 0,
+        }
+    }
+
+    /// Returns true if the king of the player is attacked.
+    pub fn in_check(&self, player: Player) -> bool {
+        self.checkers(player) != 0
+    }
+
+    /// Gets the pieces of the player that cannot leave the line between their king and an enemy slider.
+    pub fn pinned(&self, player: Player) -> Bitboard {
+        let king = match self.king_square(player) {
+            Some(king) => king,
+            None => return 0,
+        };
+
+        let enemy = player.opponent();
+        let queens = self.piece(enemy, PieceKind::Queen);
+
+        // The sliders that would attack the king on an empty board.
+        let snipers = (rook_attacks(king, 0) & (self.piece(enemy, PieceKind::Rook) | queens))
+            | (bishop_attacks(king, 0) & (self.piece(enemy, PieceKind::Bishop) | queens));
+
+        let mut pinned = 0;
+
+        for sniper in squares(snipers) {
+            let blockers = between(king, sniper) & self.occupied;
+
+            if blockers.count_ones() == 1 {
+                pinned |= blockers & self.colors[color_index(player)];
+            }
+        }
+
+        pinned
+    }
+}
+
+/// Adds a move, once for every promotion piece if a pawn reaches the end of the board.
+fn push_move(moves: &mut Vec<Move>, mv: Move, is_pawn: bool) {
+    if is_pawn && (mv.to.0 == 0 || mv.to.0 == 7) {
+        for promotion in PieceKind::PROMOTIONS {
+            moves.push(mv.with_promotion(promotion));
+        }
+    } else {
+        moves.push(mv);
+    }
+}
+
+/// Gets every legal move of the player on turn.
+/// Instead of trying out every move, checks and pins decide up front where a piece may go.
+pub fn legal_moves(position: &Position) -> Vec<Move> {
+    let bitboards = Bitboards::from_board(&position.board);
+    let player = position.turn;
+    let them = color_index(player.opponent());
+    let own = bitboards.colors[color_index(player)];
+    let occupied = bitboards.occupied;
+
+    let mut moves = Vec::with_capacity(64);
+
+    let king = bitboards.king_square(player);
+    let checkers = bitboards.checkers(player);
+    let pinned = bitboards.pinned(player);
+
+    if let Some(king) = king {
+        // The king itself is left out, so it cannot hide behind itself from a slider.
+        let without_king = occupied & !(1 << king);
+
+        for to in squares(king_attacks(king) & !own) {
+            if bitboards.attackers_to(to, without_king) & bitboards.colors[them] == 0 {
+                moves.push(Move::new(square_at(king), square_at(to)));
+            }
+        }
+    }
+
+    // In double check only the king can move.
+    if checkers.count_ones() > 1 {
+        return moves;
+    }
+
+    // In check, the other pieces have to capture the checking piece or step in between.
+    let targets = match (king, checkers) {
+        (Some(king), checker) if checker != 0 => {
+            checker | between(king, checker.trailing_zeros() as usize)
+        }
+        _ => !0,
+    };
+
+    // A pinned piece can only move along the line to its king.
+    let allowed = |from: usize| match king {
+        Some(king) if pinned & 1 << from != 0 => targets & line(king, from),
+        _ => targets,
+    };
+
+    add_piece_moves(position, &bitboards, allowed, &mut moves);
+
+    if let Some(column) = position.en_passant {
+        add_en_passant(position, &bitboards, king, column, &mut moves);
+    }
+
+    if let Some(king) = king {
+        if checkers == 0 {
+            add_castles(position, king, &mut moves, |board| {
+                !Bitboards::from_board(board).in_check(player)
+            });
+        }
+    }
+
+    moves
+}
+
+/// Gets every move the pieces of the player on turn can make, without looking at the safety of the own king.
+/// Castling is only added if the king is safe on every square it passes, by the given check,
+/// which gets the board with the king on one of those squares and the rook lifted off.
+pub fn pseudo_legal_moves(
+    position: &Position,
+    castle_is_safe: impl Fn(&Chessboard) -> bool,
+) -> Vec<Move> {
+    let bitboards = Bitboards::from_board(&position.board);
+    let player = position.turn;
+    let own = bitboards.colors[color_index(player)];
+
+    let mut moves = Vec::with_capacity(64);
+
+    // There can be more than one king when the king is not royal.
+    for king in squares(bitboards.piece(player, PieceKind::King)) {
+        for to in squares(king_attacks(king) & !own) {
+            moves.push(Move::new(square_at(king), square_at(to)));
+        }
+    }
+
+    add_piece_moves(position, &bitboards, |_| !0, &mut moves);
+
+    if let Some(column) = position.en_passant {
+        add_en_passant(position, &bitboards, None, column, &mut moves);
+    }
+
+    if let Some(king) = bitboards.king_square(player) {
+        add_castles(position, king, &mut moves, castle_is_safe);
+    }
+
+    moves
+}
+
+/// Adds the moves of the pawns and the pieces other than the king, to the squares allowed for the piece
+/// on the given square. Castling and en passant are left to their own functions.
+fn add_piece_moves(
+    position: &Position,
+    bitboards: &Bitboards,
+    allowed: impl Fn(usize) -> Bitboard,
+    moves: &mut Vec<Move>,
+) {
+    let player = position.turn;
+    let us = color_index(player);
+    let them = us ^ 1;
+    let own = bitboards.colors[us];
+    let occupied = bitboards.occupied;
+
+    for kind in [
+        PieceKind::Knight,
+        PieceKind::Bishop,
+        PieceKind::Rook,
+        PieceKind::Queen,
+    ] {
+        for from in squares(bitboards.pieces[us][kind as usize]) {
+            let attacks = match kind {
+                PieceKind::Knight => knight_attacks(from),
+                PieceKind::Bishop => bishop_attacks(from, occupied),
+                PieceKind::Rook => rook_attacks(from, occupied),
+                _ => queen_attacks(from, occupied),
+            };
+
+            for to in squares(attacks & !own & allowed(from)) {
+                moves.push(Move::new(square_at(from), square_at(to)));
+            }
+        }
+    }
+
+    let (forward, start_row): (isize, usize) = if player == Player::White {
+        (-1, 6)
+    } else {
+        (1, 1)
+    };
+
+    for from in squares(bitboards.pieces[us][PieceKind::Pawn as usize]) {
+        let allowed = allowed(from);
+        let (row, column) = square_at(from);
+
+        let push = row
+            .checked_add_signed(forward)
+            .filter(|row| *row < 8)
+            .map(|row| (row, column));
+
+        if let Some(push) = push.filter(|square| occupied & bit(*square) == 0) {
+            if allowed & bit(push) != 0 {
+                push_move(moves, Move::new((row, column), push), true);
+            }
+
+            // Only looked at for a pawn on its starting row, where this stays on the board.
+            let double = ((push.0 as isize + forward) as usize, column);
+
+            if row == start_row && occupied & bit(double) == 0 && allowed & bit(double) != 0 {
+                moves.push(Move {
+                    kind: MoveKind::DoublePush,
+                    ..Move::new((row, column), double)
+                });
+            }
+        }
+
+        for to in squares(pawn_attacks(player, from) & bitboards.colors[them] & allowed) {
+            push_move(moves, Move::new(square_at(from), square_at(to)), true);
+        }
+    }
+}
+
+/// Adds the en passant captures onto the given column.
+/// Without a king to keep safe, every capture is added.
+fn add_en_passant(
+    position: &Position,
+    bitboards: &Bitboards,
+    king: Option<usize>,
+    column: usize,
+    moves: &mut Vec<Move>,
+) {
+    let player = position.turn;
+    let (pawn_row, target_row, enemy_pawn) = if player == Player::White {
+        (3, 2, 'p')
+    } else {
+        (4, 5, 'P')
+    };
+
+    // The pawn that moved two squares has to be there, with the square behind it empty.
+    if position.piece_at((pawn_row, column)) != enemy_pawn
+        || position.piece_at((target_row, column)) != ' '
+    {
+        return;
+    }
+
+    let captured = square_index((pawn_row, column));
+    let target = square_index((target_row, column));
+    let us = color_index(player);
+
+    // Our pawns that could capture onto the target are where an enemy pawn there would attack.
+    let capturers =
+        pawn_attacks(player.opponent(), target) & bitboards.pieces[us][PieceKind::Pawn as usize];
+
+    for from in squares(capturers) {
+        // Two pawns leave the row at once, so the pin lookup is not enough here.
+        // Trying it out on the bitboards handles every case, including the pawn that gave check.
+        if let Some(king) = king {
+            let occupied = (bitboards.occupied & !(1 << from) & !(1 << captured)) | 1 << target;
+            let attackers = bitboards.attackers_to(king, occupied)
+                & bitboards.colors[us ^ 1]
+                & !(1 << captured);
+
+            if attackers != 0 {
+                continue;
+            }
+        }
+
+        moves.push(Move {
+            kind: MoveKind::EnPassant,
+            ..Move::new(square_at(from), (target_row, column))
+        });
+    }
+}
+
+/// Adds the castling moves where the king is safe on every square it passes, by the given check.
+/// In Chess960 the king and rook can start anywhere on the back rank, so every square either of them
+/// passes over has to be empty, apart from the squares of the two of them.
+fn add_castles(
+    position: &Position,
+    king: usize,
+    moves: &mut Vec<Move>,
+    is_safe: impl Fn(&Chessboard) -> bool,
+) {
+    let player = position.turn;
+    let (row, rook) = if player == Player::White {
+        (7, 'R')
+    } else {
+        (0, 'r')
+    };
+    let (king_row, king_column) = square_at(king);
+
+    if king_row != row {
+        return;
+    }
+
+    let king_piece = position.piece_at((row, king_column));
+    let span = |a: usize, b: usize| a.min(b)..=a.max(b);
+
+    for rook_column in position.castling_rooks(player).into_iter().flatten() {
+        if position.piece_at((row, rook_column)) != rook {
+            continue;
+        }
+
+        let mv = Move {
+            kind: MoveKind::Castle,
+            ..Move::new((row, king_column), (row, rook_column))
+        };
+        let (king_to, rook_to) = mv.castle_columns();
+
+        let is_free = |column: &usize| {
+            *column == king_column
+                || *column == rook_column
+                || position.piece_at((row, *column)) == ' '
+        };
+
+        if !span(king_column, king_to)
+            .chain(span(rook_column, rook_to))
+            .all(|column| is_free(&column))
+        {
+            continue;
+        }
+
+        // The king may not start on, pass through or land on an attacked square.
+        // The rook moves away too, so it cannot shield the king from an attack along the back rank.
+        let is_safe_on = |column: usize| {
+            let mut board = position.board;
+
+            board.board[row][king_column] = ' ';
+            board.board[row][rook_column] = ' ';
+            board.board[row][column] = king_piece;
+
+            is_safe(&board)
+        };
+
+        if span(king_column, king_to).all(is_safe_on) {
+            moves.push(mv);
+        }
+    }
+}
@@ -1,6 +1,6 @@
 use std::fmt;
 
-#[derive(Clone, Copy)]
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
 pub struct Chessboard {
     pub board: [[char; 8]; 8],
 }
@@ -11,10 +11,16 @@ impl fmt::Display for Chessboard {
             for y in x {
                 write!(f, "{}", y)?;
             }
-            write!(f, "\n")?;
+            writeln!(f)?;
         }
 
-        write!(f, "\n")
+        writeln!(f)
+    }
+}
+
+impl Default for Chessboard {
+    fn default() -> Self {
+        Self::new()
     }
 }
 
@@ -33,4 +39,56 @@ impl Chessboard {
             ],
         }
     }
+
+    /// One of the 960 starting positions of Chess960, by its number from 0 to 959.
+    /// The numbers are the ones from Reinhard Scharnagl, 518 is the standard starting position.
+    pub fn chess960(index: usize) -> Chessboard {
+        let mut back_rank = [' '; 8];
+        let mut n = index % 960;
+
+        // The bishops go on opposite colours, first the one on the light squares.
+        back_rank[n % 4 * 2 + 1] = 'b';
+        n /= 4;
+        back_rank[n % 4 * 2] = 'b';
+        n /= 4;
+
+        // The other pieces fill the free squares from left to right.
+        let mut place = |piece: char, nth: usize| {
+            let column = (0..8).filter(|c| back_rank[*c] == ' ').nth(nth).unwrap();
+            back_rank[column] = piece;
+        };
+
+        place('q', n % 6);
+        n /= 6;
+
+        // The second knight is placed after the first one is, so it skips over it.
+        let (first, second) = [
+            (0, 1),
+            (0, 2),
+            (0, 3),
+            (0, 4),
+            (1, 2),
+            (1, 3),
+            (1, 4),
+            (2, 3),
+            (2, 4),
+            (3, 4),
+        ][n];
+        place('n', first);
+        place('n', second - 1);
+
+        // The king always ends up between the rooks.
+        place('r', 0);
+        place('k', 0);
+        place('r', 0);
+
+        let mut board = Chessboard::new();
+
+        for (column, piece) in back_rank.into_iter().enumerate() {
+            board.board[0][column] = piece;
+            board.board[7][column] = piece.to_ascii_uppercase();
+        }
+
+        board
+    }
 }
@@ -1,215 +1,237 @@
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+use super::bitboard::{king_attacks, Bitboards};
 use super::board::Chessboard;
-use super::game::MainGame;
-use super::pieces::Piece;
+use super::history::History;
+use super::pieces::{Piece, PieceKind};
 use super::player::Player;
-
-use ggez::Context;
+use super::position::Position;
+use super::result::GameResult;
 
 /// Returns true if the given player is in check.
-pub fn check(
-    board: Chessboard,
-    player: Player,
-    en_passant: Option<usize>,
-    castling: (bool, bool, bool, bool),
-) -> bool {
-    let mut white_king_pos: (usize, usize) = (0, 0);
-    let mut black_king_pos: (usize, usize) = (0, 0);
-
-    for (x, i) in board.board.iter().enumerate() {
-        for (y, j) in i.iter().enumerate() {
-            if j == &'k' {
-                black_king_pos = (x, y);
-            } else if j == &'K' {
-                white_king_pos = (x, y);
-            }
-        }
-    }
+pub fn check(board: Chessboard, player: Player) -> bool {
+    Bitboards::from_board(&board).in_check(player)
+}
 
-    if player == Player::White {
-        for (x, i) in board.board.iter().enumerate() {
-            for (y, j) in i.iter().enumerate() {
-                if Piece::get_player(*j) == Player::Black {
-                    if Piece::get_possible_moves(*j, (x, y), board, en_passant, castling)
-                        .contains(&white_king_pos)
-                    {
-                        return true;
-                    }
-                }
-            }
-        }
-    } else if player == Player::Black {
-        for (x, i) in board.board.iter().enumerate() {
-            for (y, j) in i.iter().enumerate() {
-                if Piece::get_player(*j) == Player::White {
-                    if Piece::get_possible_moves(*j, (x, y), board, en_passant, castling)
-                        .contains(&black_king_pos)
-                    {
-                        return true;
-                    }
-                }
-            }
-        }
+/// Returns true if the given player is in check by the rules of Atomic.
+/// Kings next to each other cannot give check, taking one would blow up the other as well.
+pub fn atomic_check(board: Chessboard, player: Player) -> bool {
+    let bitboards = Bitboards::from_board(&board);
+    let king = bitboards.piece(player, PieceKind::King);
+    let enemy_king = bitboards.piece(player.opponent(), PieceKind::King);
+
+    if king == 0 || enemy_king == 0 {
+        return false;
     }
 
-    return false;
+    king_attacks(king.trailing_zeros() as usize) & enemy_king == 0 && bitboards.in_check(player)
 }
 
 /// Returns the player that is in checkmate, or None if no one is in checkmate.
-pub fn player_checkmate(
-    board: Chessboard,
-    en_passant: Option<usize>,
-    castling: (bool, bool, bool, bool),
-    game: MainGame,
-    ctx: &Context,
-) -> Option<Player> {
-    if check(board, Player::White, en_passant, castling) == true {
-        let all_pieces = Piece::get_all_player_pieces(Player::White, board);
-
-        for piece in all_pieces {
-            let moves = Piece::get_possible_moves(piece.0, piece.1, board, en_passant, castling);
-
-            for mv in moves.clone() {
-                let mut temp_game = game.clone();
-
-                temp_game.make_move(ctx, piece.0, piece.1, moves.clone(), mv.0, mv.1);
-
-                if !check(
-                    temp_game.board,
-                    Player::White,
-                    temp_game.en_passant,
-                    temp_game.castling,
-                ) {
-                    return None;
-                }
-            }
-        }
+pub fn player_checkmate(position: &Position) -> Option<Player> {
+    if position.in_check() && position.legal_moves().is_empty() {
+        return Some(position.turn);
+    }
 
-        return Some(Player::White);
-    } else if check(board, Player::Black, en_passant, castling) == true {
-        let all_pieces = Piece::get_all_player_pieces(Player::Black, board);
+    None
+}
 
-        for piece in all_pieces {
-            let moves = Piece::get_possible_moves(piece.0, piece.1, board, en_passant, castling);
+/// The rules that end a game in a draw.
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Serialize, Deserialize)]
+pub enum DrawRule {
+    Stalemate,
+    InsufficientMaterial,
+    /// The same position came up three times, a player can claim the draw.
+    ThreefoldRepetition,
+    /// The same position came up five times, the game ends right away.
+    FivefoldRepetition,
+    /// No capture or pawn move in the last fifty moves, a player can claim the draw.
+    FiftyMoveRule,
+    /// No capture or pawn move in the last seventy-five moves, the game ends right away.
+    SeventyFiveMoveRule,
+    /// A player ran out of time, but the other one could not have checkmated.
+    TimeoutVsInsufficientMaterial,
+    /// The players agreed to a draw.
+    Agreement,
+}
 
-            for mv in moves.clone() {
-                let mut temp_game = game.clone();
+impl fmt::Display for DrawRule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let rule = match self {
+            DrawRule::Stalemate => "stalemate",
+            DrawRule::InsufficientMaterial => "insufficient material",
+            DrawRule::ThreefoldRepetition => "threefold repetition",
+            DrawRule::FivefoldRepetition => "fivefold repetition",
+            DrawRule::FiftyMoveRule => "the fifty-move rule",
+            DrawRule::SeventyFiveMoveRule => "the seventy-five-move rule",
+            DrawRule::TimeoutVsInsufficientMaterial => "timeout vs insufficient material",
+            DrawRule::Agreement => "agreement",
+        };
+
+        write!(f, "{}", rule)
+    }
+}
 
-                temp_game.make_move(ctx, piece.0, piece.1, moves.clone(), mv.0, mv.1);
+impl DrawRule {
+    /// Returns true if a player has to claim the draw, instead of the game ending by itself.
+    pub fn is_claimable(&self) -> bool {
+        matches!(
+            self,
+            DrawRule::ThreefoldRepetition | DrawRule::FiftyMoveRule
+        )
+    }
+}
 
-                if !check(
-                    temp_game.board,
-                    Player::Black,
-                    temp_game.en_passant,
-                    temp_game.castling,
-                ) {
-                    return None;
+/// Returns true if neither player can ever checkmate, whatever moves are played.
+/// That is the case when only the kings and one knight or bishop are left,
+/// or when besides the kings there are only bishops, all on squares of the same color.
+/// Two knights could still mate if the other player helps, so they are not enough for a draw.
+pub fn insufficient_material(position: &Position) -> bool {
+    let mut minor_pieces = 0;
+    let mut knights = 0;
+    // Whether there is a bishop on the light squares and on the dark squares.
+    let mut bishop_colors = [false; 2];
+
+    for (row, pieces) in position.board.board.iter().enumerate() {
+        for (column, piece) in pieces.iter().enumerate() {
+            match piece.to_ascii_lowercase() {
+                ' ' | 'k' => (),
+                'n' => {
+                    knights += 1;
+                    minor_pieces += 1;
                 }
+                'b' => {
+                    bishop_colors[(row + column) % 2] = true;
+                    minor_pieces += 1;
+                }
+                // Pawns, rooks and queens can always mate.
+                _ => return false,
             }
         }
-
-        return Some(Player::Black);
-    } else {
-        return None;
     }
+
+    minor_pieces <= 1 || (knights == 0 && !(bishop_colors[0] && bishop_colors[1]))
 }
 
-/// Returns true if the game is tied.
-pub fn is_tie(
-    board: Chessboard,
-    en_passant: Option<usize>,
-    castling: (bool, bool, bool, bool),
-    game: MainGame,
-    ctx: &Context,
-) -> bool {
-    let black_pieces = Piece::get_all_player_pieces(Player::Black, board);
-    let white_pieces = Piece::get_all_player_pieces(Player::White, board);
-
-    if black_pieces.len() == 1 && white_pieces.len() == 1 {
-        return true;
-    }
+/// Returns true if the player has the pieces to checkmate, if the other player helps.
+/// Decides who wins when the other player runs out of time.
+/// A lone knight needs an enemy piece other than a queen to block the king in,
+/// and bishops on one color need an enemy knight or pawn, or a bishop on the other color.
+pub fn can_checkmate(position: &Position, player: Player) -> bool {
+    let mut knights = 0;
+    let mut bishops = 0;
+    let mut bishop_colors = [false; 2];
+    let mut enemy_pieces = Vec::new();
+
+    for (row, pieces) in position.board.board.iter().enumerate() {
+        for (column, piece) in pieces.iter().enumerate() {
+            if *piece == ' ' {
+                continue;
+            }
 
-    // You cannot checkmate the other king with just a knight or bishop.
-    if black_pieces.len() == 2 && white_pieces.len() == 1 {
-        // One of the black pieces will be the King,
-        // so we only need to check if the other is either a bishop or a knight.
-        if black_pieces[0].0 == 'b'
-            || black_pieces[1].0 == 'b'
-            || black_pieces[0].0 == 'n'
-            || black_pieces[1].0 == 'n'
-        {
-            return true;
+            let kind = piece.to_ascii_lowercase();
+
+            if kind == 'b' {
+                bishop_colors[(row + column) % 2] = true;
+            }
+
+            if Piece::get_player(*piece) != player {
+                enemy_pieces.push(kind);
+                continue;
+            }
+
+            match kind {
+                'k' => (),
+                'n' => knights += 1,
+                'b' => bishops += 1,
+                // Pawns, rooks and queens can always mate.
+                _ => return true,
+            }
         }
     }
 
-    if black_pieces.len() == 1 && white_pieces.len() == 2 {
-        if white_pieces[0].0 == 'B'
-            || white_pieces[1].0 == 'B'
-            || white_pieces[0].0 == 'N'
-            || white_pieces[1].0 == 'N'
-        {
-            return true;
+    match (knights, bishops) {
+        (0, 0) => false,
+        (1, 0) => enemy_pieces.iter().any(|kind| !matches!(kind, 'k' | 'q')),
+        (0, _) => {
+            (bishop_colors[0] && bishop_colors[1])
+                || enemy_pieces.iter().any(|kind| matches!(kind, 'n' | 'p'))
         }
+        _ => true,
     }
+}
 
-    // Checking for stalemates.
-    // If the current player has no legal moves left but is not in check, it is a stalemate and a tie.
-    if game.turn == Player::White && check(board, Player::White, en_passant, castling) == false {
-        let all_pieces = Piece::get_all_player_pieces(Player::White, board);
+/// Returns true if the current player has no legal moves left but is not in check.
+fn is_stalemate(position: &Position) -> bool {
+    !position.in_check() && position.legal_moves().is_empty()
+}
 
-        for piece in all_pieces {
-            let mut moves =
-                Piece::get_possible_moves(piece.0, piece.1, board, en_passant, castling);
+/// Returns true if the game is tied by the position alone, by stalemate or the pieces left on the board.
+pub fn is_tie(position: &Position) -> bool {
+    variant_winner(position).is_none()
+        && (position.variant.rules().insufficient_material(position) || is_stalemate(position))
+}
 
-            for mv in moves.clone() {
-                let mut temp_game = game.clone();
+/// Returns the player who won by the rules of the variant played, like King of the Hill.
+pub fn variant_winner(position: &Position) -> Option<Player> {
+    position.variant.rules().winner(position)
+}
 
-                temp_game.make_move(ctx, piece.0, piece.1, moves.clone(), mv.0, mv.1);
+/// Gets the rule that ends the game in a draw after the last move, without anyone asking for it.
+pub fn automatic_draw(history: &History) -> Option<DrawRule> {
+    let position = history.current();
 
-                if check(
-                    temp_game.board,
-                    Player::White,
-                    temp_game.en_passant,
-                    temp_game.castling,
-                ) {
-                    moves.retain(|m| m != &mv);
-                }
-            }
+    // A checkmate or a win by the variant on the last move still counts.
+    if player_checkmate(position).is_some() || variant_winner(position).is_some() {
+        return None;
+    }
 
-            if !moves.is_empty() {
-                return false;
-            }
-        }
-        return true;
-    } else if game.turn == Player::Black
-        && check(board, Player::Black, en_passant, castling) == false
-    {
-        let all_pieces = Piece::get_all_player_pieces(Player::Black, board);
-
-        for piece in all_pieces {
-            let mut moves =
-                Piece::get_possible_moves(piece.0, piece.1, board, en_passant, castling);
-
-            for mv in moves.clone() {
-                let mut temp_game = game.clone();
-
-                temp_game.make_move(ctx, piece.0, piece.1, moves.clone(), mv.0, mv.1);
-
-                if check(
-                    temp_game.board,
-                    Player::Black,
-                    temp_game.en_passant,
-                    temp_game.castling,
-                ) {
-                    moves.retain(|m| m != &mv);
-                }
-            }
+    if is_stalemate(position) {
+        Some(DrawRule::Stalemate)
+    } else if position.variant.rules().insufficient_material(position) {
+        Some(DrawRule::InsufficientMaterial)
+    } else if history.repetitions() >= 5 {
+        Some(DrawRule::FivefoldRepetition)
+    } else if position.halfmove_clock >= 150 {
+        Some(DrawRule::SeventyFiveMoveRule)
+    } else {
+        None
+    }
+}
 
-            if !moves.is_empty() {
-                return false;
-            }
-        }
+/// Gets the result of the game if it ended by itself after the last move,
+/// by the rules of the variant, checkmate or a draw.
+pub fn game_result(history: &History) -> Option<GameResult> {
+    let position = history.current();
 
-        return true;
+    if let Some(winner) = variant_winner(position) {
+        return Some(GameResult::Variant {
+            winner,
+            variant: position.variant,
+        });
+    }
+
+    match player_checkmate(position) {
+        Some(loser) => Some(GameResult::Checkmate {
+            winner: loser.opponent(),
+        }),
+        None => automatic_draw(history).map(GameResult::Draw),
+    }
+}
+
+/// Gets the rule the player on turn can claim a draw by, if the game did not end already.
+pub fn claimable_draw(history: &History) -> Option<DrawRule> {
+    let position = history.current();
+
+    if game_result(history).is_some() {
+        None
+    } else if history.repetitions() >= 3 {
+        Some(DrawRule::ThreefoldRepetition)
+    } else if position.halfmove_clock >= 100 {
+        Some(DrawRule::FiftyMoveRule)
+    } else {
+        None
     }
-    return false;
 }
@@ -0,0 +1,253 @@
+use std::fmt;
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+
+use super::player::Player;
+
+/// How the extra time of a time control is given.
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Serialize, Deserialize)]
+pub enum TimeBonus {
+    /// The extra time gets added after every move.
+    Fischer,
+    /// The time used for a move gets added back after it, but never more than the extra time.
+    Bronstein,
+    /// The clock only starts counting down once the extra time of the move has passed.
+    /// This is what US Chess calls a delay.
+    Delay,
+}
+
+impl TimeBonus {
+    /// Reads "Fischer", "Bronstein" or "Delay", ignoring case.
+    pub fn parse(text: &str) -> Option<TimeBonus> {
+        match text.to_ascii_uppercase().as_str() {
+            "FISCHER" | "INCREMENT" => Some(TimeBonus::Fischer),
+            "BRONSTEIN" => Some(TimeBonus::Bronstein),
+            "DELAY" | "US" | "SIMPLE" => Some(TimeBonus::Delay),
+            _ => None,
+        }
+    }
+}
+
+/// One part of a time control, like "40 moves in 90 minutes".
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Serialize, Deserialize)]
+pub struct Stage {
+    /// The number of moves to make in this stage, or None if it lasts for the rest of the game.
+    pub moves: Option<u32>,
+    pub time: Duration,
+    /// The increment or delay of every move.
+    pub bonus: Duration,
+}
+
+/// The time each player gets for the game.
+#[derive(Debug, PartialEq, Eq, Clone, Serialize, Deserialize)]
+pub struct TimeControl {
+    /// The stages are played one after the other, the time of the next one is added once a stage is over.
+    /// If the last stage has a number of moves, it starts over after it.
+    pub stages: Vec<Stage>,
+    pub bonus: TimeBonus,
+}
+
+/// Reads a number of seconds, which may have a fraction.
+fn parse_seconds(text: &str) -> Option<Duration> {
+    let seconds: f64 = text.parse().ok()?;
+
+    (seconds.is_finite() && seconds >= 0.0).then(|| Duration::from_secs_f64(seconds))
+}
+
+fn write_seconds(f: &mut fmt::Formatter<'_>, duration: Duration) -> fmt::Result {
+    if duration.subsec_millis() == 0 {
+        write!(f, "{}", duration.as_secs())
+    } else {
+        write!(f, "{}", duration.as_secs_f64())
+    }
+}
+
+impl TimeControl {
+    /// Reads a time control in the format of the PGN TimeControl tag, with all times in seconds.
+    /// Stages are separated by colons, "40/5400+30:1800+30" being 40 moves in 90 minutes
+    /// and then 30 minutes for the rest of the game, with 30 seconds extra for every move.
+    pub fn parse(text: &str, bonus: TimeBonus) -> Option<TimeControl> {
+        let mut stages = Vec::new();
+
+        for field in text.trim().split(':') {
+            let (moves, rest) = match field.split_once('/') {
+                Some((moves, rest)) => (Some(moves.parse().ok().filter(|m| *m > 0)?), rest),
+                None => (None, field),
+            };
+
+            let (time, extra) = match rest.split_once('+') {
+                Some((time, extra)) => (parse_seconds(time)?, parse_seconds(extra)?),
+                None => (parse_seconds(rest)?, Duration::ZERO),
+            };
+
+            stages.push(Stage {
+                moves,
+                time,
+                bonus: extra,
+            });
+        }
+
+        // Only the last stage may last for the rest of the game.
+        if stages[..stages.len() - 1].iter().any(|s| s.moves.is_none()) {
+            return None;
+        }
+
+        Some(TimeControl { stages, bonus })
+    }
+}
+
+impl fmt::Display for TimeControl {
+    /// Writes the time control like the PGN TimeControl tag.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, stage) in self.stages.iter().enumerate() {
+            if i > 0 {
+                write!(f, ":")?;
+            }
+
+            if let Some(moves) = stage.moves {
+                write!(f, "{}/", moves)?;
+            }
+
+            write_seconds(f, stage.time)?;
+
+            if !stage.bonus.is_zero() {
+                write!(f, "+")?;
+                write_seconds(f, stage.bonus)?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Formats the time left on a clock, like "1:30:00", "4:59" or "0:09.5".
+/// Tenths of seconds are only shown in the last ten seconds.
+pub fn format_time(time: Duration) -> String {
+    let seconds = time.as_secs();
+
+    if seconds >= 3600 {
+        format!(
+            "{}:{:02}:{:02}",
+            seconds / 3600,
+            seconds / 60 % 60,
+            seconds % 60
+        )
+    } else if seconds >= 10 {
+        format!("{}:{:02}", seconds / 60, seconds % 60)
+    } else {
+        format!("0:{:02}.{}", seconds, time.subsec_millis() / 100)
+    }
+}
+
+fn index(player: Player) -> usize {
+    usize::from(player == Player::Black)
+}
+
+/// The clocks of both players.
+#[derive(Debug, PartialEq, Eq, Clone, Serialize, Deserialize)]
+pub struct Clock {
+    pub control: TimeControl,
+    /// The time left for White and Black.
+    pub remaining: [Duration; 2],
+    /// The player whose clock is counting down, if any.
+    pub running: Option<Player>,
+    /// The moves made by White and Black, for knowing the stage of the time control.
+    moves: [u32; 2],
+    /// The stage White and Black are in.
+    stages: [usize; 2],
+    /// The time used for the move being thought about.
+    used: Duration,
+}
+
+impl Clock {
+    /// Both clocks with the time of the first stage, not running yet.
+    pub fn new(control: TimeControl) -> Clock {
+        let time = control.stages[0].time;
+
+        Clock {
+            control,
+            remaining: [time; 2],
+            running: None,
+            moves: [0; 2],
+            stages: [0; 2],
+            used: Duration::ZERO,
+        }
+    }
+
+    pub fn remaining(&self, player: Player) -> Duration {
+        self.remaining[index(player)]
+    }
+
+    fn stage(&self, player: Player) -> &Stage {
+        &self.control.stages[self.stages[index(player)]]
+    }
+
+    /// Lets time pass on the running clock.
+    pub fn tick(&mut self, elapsed: Duration) {
+        let player = match self.running {
+            Some(player) => player,
+            None => return,
+        };
+
+        let before = self.used;
+        self.used += elapsed;
+
+        let spent = if self.control.bonus == TimeBonus::Delay {
+            // The time within the delay is free.
+            let delay = self.stage(player).bonus;
+
+            self.used.saturating_sub(delay) - before.saturating_sub(delay)
+        } else {
+            elapsed
+        };
+
+        let remaining = &mut self.remaining[index(player)];
+        *remaining = remaining.saturating_sub(spent);
+    }
+
+    /// Stops the clock of the player who just moved and starts the one of the other player.
+    /// Adds the increment of the move and the time of the next stage, if the player reached it.
+    pub fn press(&mut self, player: Player) {
+        let i = index(player);
+        let stage = *self.stage(player);
+
+        match self.control.bonus {
+            TimeBonus::Fischer => self.remaining[i] += stage.bonus,
+            TimeBonus::Bronstein => self.remaining[i] += self.used.min(stage.bonus),
+            TimeBonus::Delay => (),
+        }
+
+        self.moves[i] += 1;
+
+        if let Some(moves) = stage.moves {
+            if self.moves[i] == moves {
+                self.moves[i] = 0;
+                // After the last stage it starts over.
+                if self.stages[i] + 1 < self.control.stages.len() {
+                    self.stages[i] += 1;
+                }
+                self.remaining[i] += self.stage(player).time;
+            }
+        }
+
+        self.resume(player.opponent());
+    }
+
+    /// Starts the clock of the player, without anything being added to the other one.
+    pub fn resume(&mut self, player: Player) {
+        self.running = Some(player);
+        self.used = Duration::ZERO;
+    }
+
+    pub fn stop(&mut self) {
+        self.running = None;
+    }
+
+    /// Gets the player who ran out of time, if any.
+    pub fn flagged(&self) -> Option<Player> {
+        [Player::White, Player::Black]
+            .into_iter()
+            .find(|player| self.remaining(*player).is_zero())
+    }
+}
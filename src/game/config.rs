@@ -61,6 +61,10 @@ pub struct Config {
     #[serde(default = "knight_upgrade_button_default")]
     pub knight_upgrade_button: String,
 
+    /// Pawns can only turn into kings in Antichess.
+    #[serde(default = "king_upgrade_button_default")]
+    pub king_upgrade_button: String,
+
     #[serde(default = "draw_clear_button_default")]
     pub draw_clear_button: String,
 
@@ -72,94 +76,360 @@ pub struct Config {
 
     #[serde(default = "volume_default")]
     pub volume: f32,
+
+    #[serde(default = "copy_fen_button_default")]
+    pub copy_fen_button: String,
+
+    #[serde(default = "paste_fen_button_default")]
+    pub paste_fen_button: String,
+
+    #[serde(default = "load_fen_button_default")]
+    pub load_fen_button: String,
+
+    #[serde(default = "fen_file_default")]
+    pub fen_file: String,
+
+    #[serde(default = "export_pgn_button_default")]
+    pub export_pgn_button: String,
+
+    #[serde(default = "pgn_folder_default")]
+    pub pgn_folder: String,
+
+    #[serde(default = "white_player_name_default")]
+    pub white_player_name: String,
+
+    #[serde(default = "black_player_name_default")]
+    pub black_player_name: String,
+
+    #[serde(default = "load_pgn_button_default")]
+    pub load_pgn_button: String,
+
+    #[serde(default = "pgn_file_default")]
+    pub pgn_file: String,
+
+    #[serde(default = "previous_move_button_default")]
+    pub previous_move_button: String,
+
+    #[serde(default = "next_move_button_default")]
+    pub next_move_button: String,
+
+    #[serde(default = "previous_game_button_default")]
+    pub previous_game_button: String,
+
+    #[serde(default = "next_game_button_default")]
+    pub next_game_button: String,
+
+    #[serde(default = "history_panel_width_default")]
+    pub history_panel_width: f32,
+
+    #[serde(default = "undo_button_default")]
+    pub undo_button: String,
+
+    #[serde(default = "redo_button_default")]
+    pub redo_button: String,
+
+    #[serde(default = "flip_board_button_default")]
+    pub flip_board_button: String,
+
+    /// The player at the bottom of the board, "White", "Black" or "Auto" to turn it to the player on turn.
+    #[serde(default = "board_orientation_default")]
+    pub board_orientation: String,
+
+    /// The program of a UCI engine to play against, left empty to play without one.
+    #[serde(default = "engine_path_default")]
+    pub engine_path: String,
+
+    /// The player the engine moves for, "White", "Black", "Both" or "None".
+    #[serde(default = "engine_player_default")]
+    pub engine_player: String,
+
+    /// How long the engine thinks about a move, in milliseconds.
+    #[serde(default = "engine_movetime_default")]
+    pub engine_movetime: u64,
+
+    /// The player the built-in engine moves for, "White", "Black", "Both" or "None".
+    #[serde(default = "ai_player_default")]
+    pub ai_player: String,
+
+    /// How well the built-in engine plays, from 1 to 5.
+    #[serde(default = "ai_level_default")]
+    pub ai_level: u8,
+
+    #[serde(default = "claim_draw_button_default")]
+    pub claim_draw_button: String,
+
+    /// The time each player gets in the format of the PGN TimeControl tag, in seconds,
+    /// like "300+2" or "40/5400+30:1800+30", left empty to play without clocks.
+    #[serde(default = "time_control_default")]
+    pub time_control: String,
+
+    /// How the time after the plus is given, "Fischer", "Bronstein" or "Delay".
+    #[serde(default = "time_bonus_default")]
+    pub time_bonus: String,
+
+    /// Resigns the game for the player on turn, or for the player against the engine while it thinks.
+    #[serde(default = "resign_button_default")]
+    pub resign_button: String,
+
+    /// Offers a draw, which stands until the other player accepts, declines or makes a move.
+    #[serde(default = "offer_draw_button_default")]
+    pub offer_draw_button: String,
+
+    #[serde(default = "accept_draw_button_default")]
+    pub accept_draw_button: String,
+
+    #[serde(default = "decline_draw_button_default")]
+    pub decline_draw_button: String,
+
+    /// The game is saved here after every move, and offered to be resumed when starting the next time.
+    #[serde(default = "save_file_default")]
+    pub save_file: String,
+
+    /// Starts new games from one of the 960 starting positions of Chess960, also called Fischer Random.
+    #[serde(default = "chess960_default")]
+    pub chess960: bool,
+
+    /// The number of the Chess960 starting position from 0 to 959, 518 being the standard one.
+    /// Left as null for a random one every game.
+    #[serde(default = "chess960_position_default")]
+    pub chess960_position: Option<usize>,
+
+    /// The rules new games are played with: "Standard", "King of the Hill", "Three-check", "Atomic", "Crazyhouse" or "Antichess".
+    /// Works together with Chess960.
+    #[serde(default = "variant_default")]
+    pub variant: String,
 }
 
 fn light_sqaure_color_default() -> (u8, u8, u8) {
-    return (232, 206, 162);
+    (232, 206, 162)
 }
 
 fn dark_sqaure_color_default() -> (u8, u8, u8) {
-    return (153, 100, 33);
+    (153, 100, 33)
 }
 
 fn selected_piece_color_default() -> (u8, u8, u8) {
-    return (0, 128, 128);
+    (0, 128, 128)
 }
 
 fn possible_moves_color_default() -> (u8, u8, u8) {
-    return (0, 255, 255);
+    (0, 255, 255)
 }
 
 fn possible_captures_color_default() -> (u8, u8, u8) {
-    return (255, 0, 255);
+    (255, 0, 255)
 }
 
 fn last_move_start_color_default() -> (u8, u8, u8) {
-    return (0, 150, 30);
+    (0, 150, 30)
 }
 
 fn last_move_end_color_default() -> (u8, u8, u8) {
-    return (0, 250, 50);
+    (0, 250, 50)
 }
 
 fn check_color_default() -> (u8, u8, u8) {
-    return (255, 0, 0);
+    (255, 0, 0)
 }
 
 fn checkmate_color_default() -> (u8, u8, u8) {
-    return (169, 0, 0);
+    (169, 0, 0)
 }
 
 fn tie_color_default() -> (u8, u8, u8) {
-    return (0, 0, 169);
+    (0, 0, 169)
 }
 
 fn font_color_default() -> (u8, u8, u8) {
-    return (60, 60, 60);
+    (60, 60, 60)
 }
 
 fn draw_color_default() -> (u8, u8, u8) {
-    return (255, 211, 69);
+    (255, 211, 69)
 }
 
 fn font_size_default() -> f32 {
-    return 12.0;
+    12.0
 }
 
 fn draw_thickness_default() -> f32 {
-    return 5.0;
+    5.0
 }
 
 fn queen_upgrade_button_default() -> String {
-    return "Q".to_string();
+    "Q".to_string()
 }
 
 fn rook_upgrade_button_default() -> String {
-    return "R".to_string();
+    "R".to_string()
 }
 
 fn bishop_upgrade_button_default() -> String {
-    return "B".to_string();
+    "B".to_string()
 }
 
 fn knight_upgrade_button_default() -> String {
-    return "N".to_string();
+    "N".to_string()
+}
+
+fn king_upgrade_button_default() -> String {
+    "K".to_string()
 }
 
 fn draw_clear_button_default() -> String {
-    return "Space".to_string();
+    "Space".to_string()
 }
 
 fn window_size_horizontal_default() -> f32 {
-    return 480f32;
+    480f32
 }
 
 fn window_size_vertical_default() -> f32 {
-    return 480f32;
+    480f32
 }
 
 fn volume_default() -> f32 {
-    return 0.5f32;
+    0.5f32
+}
+
+fn copy_fen_button_default() -> String {
+    "C".to_string()
+}
+
+fn paste_fen_button_default() -> String {
+    "V".to_string()
+}
+
+fn load_fen_button_default() -> String {
+    "L".to_string()
+}
+
+fn fen_file_default() -> String {
+    "./position.fen".to_string()
+}
+
+fn export_pgn_button_default() -> String {
+    "E".to_string()
+}
+
+fn pgn_folder_default() -> String {
+    "./games".to_string()
+}
+
+fn white_player_name_default() -> String {
+    "?".to_string()
+}
+
+fn black_player_name_default() -> String {
+    "?".to_string()
+}
+
+fn load_pgn_button_default() -> String {
+    "O".to_string()
+}
+
+fn pgn_file_default() -> String {
+    "./import.pgn".to_string()
+}
+
+fn previous_move_button_default() -> String {
+    "Left".to_string()
+}
+
+fn next_move_button_default() -> String {
+    "Right".to_string()
+}
+
+fn previous_game_button_default() -> String {
+    "Up".to_string()
+}
+
+fn next_game_button_default() -> String {
+    "Down".to_string()
+}
+
+fn history_panel_width_default() -> f32 {
+    160.0
+}
+
+fn undo_button_default() -> String {
+    "Z".to_string()
+}
+
+fn redo_button_default() -> String {
+    "Y".to_string()
+}
+
+fn flip_board_button_default() -> String {
+    "F".to_string()
+}
+
+fn board_orientation_default() -> String {
+    "White".to_string()
+}
+
+fn engine_path_default() -> String {
+    "".to_string()
+}
+
+fn engine_player_default() -> String {
+    "None".to_string()
+}
+
+fn engine_movetime_default() -> u64 {
+    1000
+}
+
+fn ai_player_default() -> String {
+    "None".to_string()
+}
+
+fn ai_level_default() -> u8 {
+    3
+}
+
+fn claim_draw_button_default() -> String {
+    "D".to_string()
+}
+
+fn time_control_default() -> String {
+    "".to_string()
+}
+
+fn time_bonus_default() -> String {
+    "Fischer".to_string()
+}
+
+fn resign_button_default() -> String {
+    "G".to_string()
+}
+
+fn offer_draw_button_default() -> String {
+    "H".to_string()
+}
+
+fn accept_draw_button_default() -> String {
+    "A".to_string()
+}
+
+fn decline_draw_button_default() -> String {
+    "X".to_string()
+}
+
+fn save_file_default() -> String {
+    "./saved_game.json".to_string()
+}
+
+fn chess960_default() -> bool {
+    false
+}
+
+fn chess960_position_default() -> Option<usize> {
+    None
+}
+
+fn variant_default() -> String {
+    "Standard".to_string()
 }
 
 impl Default for Config {
@@ -183,10 +453,46 @@ impl Default for Config {
             rook_upgrade_button: rook_upgrade_button_default(),
             bishop_upgrade_button: bishop_upgrade_button_default(),
             knight_upgrade_button: knight_upgrade_button_default(),
+            king_upgrade_button: king_upgrade_button_default(),
             draw_clear_button: draw_clear_button_default(),
             window_size_horizontal: window_size_horizontal_default(),
             window_size_vertical: window_size_vertical_default(),
             volume: volume_default(),
+            copy_fen_button: copy_fen_button_default(),
+            paste_fen_button: paste_fen_button_default(),
+            load_fen_button: load_fen_button_default(),
+            fen_file: fen_file_default(),
+            export_pgn_button: export_pgn_button_default(),
+            pgn_folder: pgn_folder_default(),
+            white_player_name: white_player_name_default(),
+            black_player_name: black_player_name_default(),
+            load_pgn_button: load_pgn_button_default(),
+            pgn_file: pgn_file_default(),
+            previous_move_button: previous_move_button_default(),
+            next_move_button: next_move_button_default(),
+            previous_game_button: previous_game_button_default(),
+            next_game_button: next_game_button_default(),
+            history_panel_width: history_panel_width_default(),
+            undo_button: undo_button_default(),
+            redo_button: redo_button_default(),
+            flip_board_button: flip_board_button_default(),
+            board_orientation: board_orientation_default(),
+            engine_path: engine_path_default(),
+            engine_player: engine_player_default(),
+            engine_movetime: engine_movetime_default(),
+            ai_player: ai_player_default(),
+            ai_level: ai_level_default(),
+            claim_draw_button: claim_draw_button_default(),
+            time_control: time_control_default(),
+            time_bonus: time_bonus_default(),
+            resign_button: resign_button_default(),
+            offer_draw_button: offer_draw_button_default(),
+            accept_draw_button: accept_draw_button_default(),
+            decline_draw_button: decline_draw_button_default(),
+            save_file: save_file_default(),
+            chess960: chess960_default(),
+            chess960_position: chess960_position_default(),
+            variant: variant_default(),
         }
     }
 }
@@ -205,7 +511,7 @@ pub fn load_config() -> Config {
 
     let c: Config = serde_json::from_str(&json_file).unwrap();
 
-    return Config {
+    Config {
         light_square_color: c.light_square_color,
         dark_square_color: c.dark_square_color,
         selected_piece_color: c.selected_piece_color,
@@ -224,11 +530,47 @@ pub fn load_config() -> Config {
         rook_upgrade_button: c.rook_upgrade_button,
         bishop_upgrade_button: c.bishop_upgrade_button,
         knight_upgrade_button: c.knight_upgrade_button,
+        king_upgrade_button: c.king_upgrade_button,
         draw_clear_button: c.draw_clear_button,
         window_size_horizontal: c.window_size_horizontal,
         window_size_vertical: c.window_size_vertical,
         volume: c.volume,
-    };
+        copy_fen_button: c.copy_fen_button,
+        paste_fen_button: c.paste_fen_button,
+        load_fen_button: c.load_fen_button,
+        fen_file: c.fen_file,
+        export_pgn_button: c.export_pgn_button,
+        pgn_folder: c.pgn_folder,
+        white_player_name: c.white_player_name,
+        black_player_name: c.black_player_name,
+        load_pgn_button: c.load_pgn_button,
+        pgn_file: c.pgn_file,
+        previous_move_button: c.previous_move_button,
+        next_move_button: c.next_move_button,
+        previous_game_button: c.previous_game_button,
+        next_game_button: c.next_game_button,
+        history_panel_width: c.history_panel_width,
+        undo_button: c.undo_button,
+        redo_button: c.redo_button,
+        flip_board_button: c.flip_board_button,
+        board_orientation: c.board_orientation,
+        engine_path: c.engine_path,
+        engine_player: c.engine_player,
+        engine_movetime: c.engine_movetime,
+        ai_player: c.ai_player,
+        ai_level: c.ai_level,
+        claim_draw_button: c.claim_draw_button,
+        time_control: c.time_control,
+        time_bonus: c.time_bonus,
+        resign_button: c.resign_button,
+        offer_draw_button: c.offer_draw_button,
+        accept_draw_button: c.accept_draw_button,
+        decline_draw_button: c.decline_draw_button,
+        save_file: c.save_file,
+        chess960: c.chess960,
+        chess960_position: c.chess960_position,
+        variant: c.variant,
+    }
 }
 
 /// Matches the string to the actual KeyCode,
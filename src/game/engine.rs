@@ -0,0 +1,764 @@
+use std::mem;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, Receiver};
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use rand::Rng;
+
+use super::check;
+use super::history::History;
+use super::moves::{Move, MoveKind};
+use super::pieces::{Piece, PieceKind};
+use super::player::Player;
+use super::position::Position;
+use super::uci::Score;
+use super::zobrist;
+
+/// The score for mating right away, every half-move until the mate takes one off.
+pub const MATE: i32 = 100_000;
+const INFINITY: i32 = 1_000_000;
+/// How deep the search can go, counting the captures at the end.
+const MAX_PLY: usize = 128;
+/// How often the search checks if it should stop, in nodes.
+const CHECK_INTERVAL: u64 = 1024;
+
+// The bonuses for pieces standing on good squares, in hundredths of a pawn.
+// Seen from White, the first row being the 8th rank like on the Chessboard.
+const PAWN_TABLE: [[i32; 8]; 8] = [
+    [0, 0, 0, 0, 0, 0, 0, 0],
+    [50, 50, 50, 50, 50, 50, 50, 50],
+    [10, 10, 20, 30, 30, 20, 10, 10],
+    [5, 5, 10, 25, 25, 10, 5, 5],
+    [0, 0, 0, 20, 20, 0, 0, 0],
+    [5, -5, -10, 0, 0, -10, -5, 5],
+    [5, 10, 10, -20, -20, 10, 10, 5],
+    [0, 0, 0, 0, 0, 0, 0, 0],
+];
+
+const KNIGHT_TABLE: [[i32; 8]; 8] = [
+    [-50, -40, -30, -30, -30, -30, -40, -50],
+    [-40, -20, 0, 0, 0, 0, -20, -40],
+    [-30, 0, 10, 15, 15, 10, 0, -30],
+    [-30, 5, 15, 20, 20, 15, 5, -30],
+    [-30, 0, 15, 20, 20, 15, 0, -30],
+    [-30, 5, 10, 15, 15, 10, 5, -30],
+    [-40, -20, 0, 5, 5, 0, -20, -40],
+    [-50, -40, -30, -30, -30, -30, -40, -50],
+];
+
+const BISHOP_TABLE: [[i32; 8]; 8] = [
+    [-20, -10, -10, -10, -10, -10, -10, -20],
+    [-10, 0, 0, 0, 0, 0, 0, -10],
+    [-10, 0, 5, 10, 10, 5, 0, -10],
+    [-10, 5, 5, 10, 10, 5, 5, -10],
+    [-10, 0, 10, 10, 10, 10, 0, -10],
+    [-10, 10, 10, 10, 10, 10, 10, -10],
+    [-10, 5, 0, 0, 0, 0, 5, -10],
+    [-20, -10, -10, -10, -10, -10, -10, -20],
+];
+
+const ROOK_TABLE: [[i32; 8]; 8] = [
+    [0, 0, 0, 0, 0, 0, 0, 0],
+    [5, 10, 10, 10, 10, 10, 10, 5],
+    [-5, 0, 0, 0, 0, 0, 0, -5],
+    [-5, 0, 0, 0, 0, 0, 0, -5],
+    [-5, 0, 0, 0, 0, 0, 0, -5],
+    [-5, 0, 0, 0, 0, 0, 0, -5],
+    [-5, 0, 0, 0, 0, 0, 0, -5],
+    [0, 0, 0, 5, 5, 0, 0, 0],
+];
+
+const QUEEN_TABLE: [[i32; 8]; 8] = [
+    [-20, -10, -10, -5, -5, -10, -10, -20],
+    [-10, 0, 0, 0, 0, 0, 0, -10],
+    [-10, 0, 5, 5, 5, 5, 0, -10],
+    [-5, 0, 5, 5, 5, 5, 0, -5],
+    [0, 0, 5, 5, 5, 5, 0, -5],
+    [-10, 5, 5, 5, 5, 5, 0, -10],
+    [-10, 0, 5, 0, 0, 0, 0, -10],
+    [-20, -10, -10, -5, -5, -10, -10, -20],
+];
+
+const KING_TABLE: [[i32; 8]; 8] = [
+    [-30, -40, -40, -50, -50, -40, -40, -30],
+    [-30, -40, -40, -50, -50, -40, -40, -30],
+    [-30, -40, -40, -50, -50, -40, -40, -30],
+    [-30, -40, -40, -50, -50, -40, -40, -30],
+    [-20, -30, -30, -40, -40, -30, -30, -20],
+    [-10, -20, -20, -20, -20, -20, -20, -10],
+    [20, 20, 0, 0, 0, 0, 20, 20],
+    [20, 30, 10, 0, 0, 10, 30, 20],
+];
+
+/// Gets the value of a piece in hundredths of a pawn.
+fn piece_value(piece: char) -> i32 {
+    Piece::get_value(piece) as i32 * 100
+}
+
+/// Scores the position for the player on turn, by the pieces and where they stand.
+pub fn evaluate(position: &Position) -> i32 {
+    let mut score = 0;
+
+    for (row, pieces) in position.board.board.iter().enumerate() {
+        for (column, &piece) in pieces.iter().enumerate() {
+            let table = match piece.to_ascii_lowercase() {
+                'p' => &PAWN_TABLE,
+                'n' => &KNIGHT_TABLE,
+                'b' => &BISHOP_TABLE,
+                'r' => &ROOK_TABLE,
+                'q' => &QUEEN_TABLE,
+                'k' => &KING_TABLE,
+                _ => continue,
+            };
+
+            if Piece::get_player(piece) == Player::White {
+                score += piece_value(piece) + table[row][column];
+            } else {
+                score -= piece_value(piece) + table[7 - row][column];
+            }
+        }
+    }
+
+    // The pieces in the pockets in Crazyhouse are worth as much as the ones on the board.
+    for (kind, (white, black)) in PieceKind::POCKET.iter().zip(
+        position
+            .pocket(Player::White)
+            .into_iter()
+            .zip(position.pocket(Player::Black)),
+    ) {
+        score += piece_value(kind.to_char(Player::White)) * (white as i32 - black as i32);
+    }
+
+    score *= position.variant.rules().material_sign();
+
+    if position.turn == Player::Black {
+        -score
+    } else {
+        score
+    }
+}
+
+/// Turns a score of the search into a score of the UCI protocol.
+pub fn uci_score(score: i32) -> Score {
+    if score.abs() >= MATE - MAX_PLY as i32 {
+        // Half-moves until the mate, turned into full moves.
+        let moves = (MATE - score.abs() + 1) / 2;
+
+        Score::Mate(if score > 0 { moves } else { -moves })
+    } else {
+        Score::Centipawns(score)
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+enum Bound {
+    Exact,
+    /// The score is at least this good, the search stopped early.
+    Lower,
+    /// The score is at most this good, no move beat the alpha bound.
+    Upper,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Entry {
+    key: u64,
+    depth: u32,
+    score: i32,
+    bound: Bound,
+    best_move: Option<Move>,
+}
+
+/// Remembers the results of positions searched before, which come up again through different move orders.
+pub struct TranspositionTable {
+    entries: Vec<Option<Entry>>,
+}
+
+impl TranspositionTable {
+    /// Makes a table taking up about the given number of megabytes.
+    pub fn new(megabytes: usize) -> TranspositionTable {
+        let count = (megabytes * 1024 * 1024 / mem::size_of::<Option<Entry>>()).max(1);
+
+        TranspositionTable {
+            entries: vec![None; count],
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.entries.iter_mut().for_each(|entry| *entry = None);
+    }
+
+    fn index(&self, key: u64) -> usize {
+        (key % self.entries.len() as u64) as usize
+    }
+
+    fn probe(&self, key: u64) -> Option<Entry> {
+        self.entries[self.index(key)].filter(|entry| entry.key == key)
+    }
+
+    /// Stores an entry, replacing older ones unless they were searched deeper for the same position.
+    fn store(&mut self, entry: Entry) {
+        let index = self.index(entry.key);
+
+        match self.entries[index] {
+            Some(old) if old.key == entry.key && old.depth > entry.depth => (),
+            _ => self.entries[index] = Some(entry),
+        }
+    }
+}
+
+/// How long and deep the engine searches, and how much it plays at random.
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Default)]
+pub struct SearchLimits {
+    /// No depth and no time means searching until stopped.
+    pub depth: Option<u32>,
+    pub movetime: Option<Duration>,
+    /// At most this many hundredths of a pawn get added to every move at random, to play weaker.
+    pub randomness: i32,
+}
+
+impl SearchLimits {
+    /// The limits for a difficulty level from 1, the easiest, to 5, the hardest.
+    pub fn for_level(level: u8) -> SearchLimits {
+        let (depth, milliseconds, randomness) = match level {
+            0 | 1 => (Some(1), 500, 150),
+            2 => (Some(2), 1000, 80),
+            3 => (Some(3), 2000, 30),
+            4 => (Some(5), 3000, 0),
+            _ => (None, 5000, 0),
+        };
+
+        SearchLimits {
+            depth,
+            movetime: Some(Duration::from_millis(milliseconds)),
+            randomness,
+        }
+    }
+}
+
+/// What the engine found after searching to a depth.
+#[derive(Debug, PartialEq, Eq, Clone, Default)]
+pub struct SearchInfo {
+    pub depth: u32,
+    /// The score for the player on turn.
+    pub score: i32,
+    pub nodes: u64,
+    pub time: Duration,
+    /// The moves the engine expects to be played.
+    pub pv: Vec<Move>,
+}
+
+/// The move the engine picked, together with what it knows about it.
+#[derive(Debug, PartialEq, Eq, Clone, Default)]
+pub struct SearchResult {
+    /// None if there is no legal move to make.
+    pub best_move: Option<Move>,
+    pub info: SearchInfo,
+}
+
+/// The built-in engine, an alpha-beta search going one half-move deeper at a time.
+pub struct Engine {
+    pub table: TranspositionTable,
+    /// Setting this makes the current search stop as soon as possible.
+    pub stop: Arc<AtomicBool>,
+}
+
+impl Engine {
+    pub fn new(hash_megabytes: usize) -> Engine {
+        Engine {
+            table: TranspositionTable::new(hash_megabytes),
+            stop: Arc::new(AtomicBool::new(false)),
+        }
+    }
+
+    /// Forgets everything about the previous game.
+    pub fn new_game(&mut self) {
+        self.table.clear();
+    }
+
+    /// Searches the last position of the game for the best move.
+    /// The callback gets called after every depth that was searched completely.
+    /// The stop flag is not reset here, so a search can be stopped before it even started.
+    pub fn search(
+        &mut self,
+        history: &History,
+        limits: SearchLimits,
+        mut on_info: impl FnMut(&SearchInfo),
+    ) -> SearchResult {
+        let start = Instant::now();
+        let position = *history.current();
+
+        let mut rng = rand::thread_rng();
+
+        let mut root_moves: Vec<(Move, i32)> = position
+            .legal_moves()
+            .into_iter()
+            .map(|mv| (mv, rng.gen_range(0..=limits.randomness.max(0))))
+            .collect();
+
+        let mut result = SearchResult {
+            best_move: root_moves.first().map(|(mv, _)| *mv),
+            ..SearchResult::default()
+        };
+
+        if root_moves.is_empty() {
+            return result;
+        }
+
+        let mut search = Search {
+            table: &mut self.table,
+            stop: &self.stop,
+            deadline: limits.movetime.map(|time| start + time),
+            nodes: 0,
+            killers: [[None; 2]; MAX_PLY],
+            // Every position before the current one, for finding repetitions.
+            keys: history.keys[..history.len()].to_vec(),
+            stopped: false,
+        };
+
+        let max_depth = limits.depth.unwrap_or(MAX_PLY as u32 / 2).max(1);
+
+        for depth in 1..=max_depth {
+            let (best_move, score) = search.root(&position, &mut root_moves, depth);
+
+            // A search that got cut off only looked at some of the moves, the best of them can still be used.
+            if search.stopped && depth > 1 {
+                if let Some(best_move) = best_move {
+                    if score > result.info.score {
+                        result.best_move = Some(best_move);
+                    }
+                }
+                break;
+            }
+
+            result.best_move = best_move.or(result.best_move);
+            result.info = SearchInfo {
+                depth,
+                score,
+                nodes: search.nodes,
+                time: start.elapsed(),
+                pv: search.principal_variation(&position, depth as usize),
+            };
+
+            on_info(&result.info);
+
+            // There is no point searching deeper after finding a mate.
+            if search.stopped || score.abs() >= MATE - MAX_PLY as i32 {
+                break;
+            }
+
+            // The next depth takes a lot longer, it would most likely not finish in time.
+            if let Some(movetime) = limits.movetime {
+                if start.elapsed() > movetime / 2 {
+                    break;
+                }
+            }
+        }
+
+        result.info.nodes = search.nodes;
+        result.info.time = start.elapsed();
+
+        result
+    }
+}
+
+/// The state of one search.
+struct Search<'a> {
+    table: &'a mut TranspositionTable,
+    stop: &'a AtomicBool,
+    deadline: Option<Instant>,
+    nodes: u64,
+    /// Quiet moves that caused cutoffs, by distance from the root.
+    killers: [[Option<Move>; 2]; MAX_PLY],
+    /// The positions leading to the current one, for finding repetitions.
+    keys: Vec<u64>,
+    stopped: bool,
+}
+
+impl Search<'_> {
+    fn should_stop(&mut self) -> bool {
+        if !self.stopped && self.nodes.is_multiple_of(CHECK_INTERVAL) {
+            self.stopped = self.stop.load(Ordering::Relaxed)
+                || self
+                    .deadline
+                    .is_some_and(|deadline| Instant::now() >= deadline);
+        }
+
+        self.stopped
+    }
+
+    /// Checks if the position came up before, since the last pawn move or capture.
+    fn is_repetition(&self, position: &Position, key: u64) -> bool {
+        self.keys
+            .iter()
+            .rev()
+            .take(position.halfmove_clock)
+            .any(|&k| k == key)
+    }
+
+    /// Searches every move at the root, the best one from the last depth first.
+    /// The random bonus of every move is added to its score.
+    fn root(
+        &mut self,
+        position: &Position,
+        root_moves: &mut [(Move, i32)],
+        depth: u32,
+    ) -> (Option<Move>, i32) {
+        let key = zobrist::hash(position);
+        let randomized = root_moves.iter().any(|(_, bonus)| *bonus != 0);
+
+        let mut alpha = -INFINITY;
+        let mut best_move = None;
+        // The score of the position itself, without the random bonuses, for the transposition table.
+        let mut best_score = -INFINITY;
+
+        self.keys.push(key);
+
+        for (mv, bonus) in root_moves.iter() {
+            let next = position.play(*mv);
+
+            // With random bonuses the moves cannot be cut off by the best score, they all need a real score.
+            let bound = if randomized { INFINITY } else { -alpha };
+            let score = -self.negamax(&next, depth - 1, 1, -INFINITY, bound);
+
+            if self.stopped {
+                break;
+            }
+
+            best_score = best_score.max(score);
+
+            let score = score + bonus;
+
+            if score > alpha {
+                alpha = score;
+                best_move = Some(*mv);
+            }
+        }
+
+        self.keys.pop();
+
+        if let Some(best_move) = best_move {
+            // Searching the best move first next time.
+            let index = root_moves
+                .iter()
+                .position(|(mv, _)| *mv == best_move)
+                .unwrap();
+            root_moves[..=index].rotate_right(1);
+
+            if !self.stopped {
+                self.table.store(Entry {
+                    key,
+                    depth,
+                    score: best_score,
+                    bound: Bound::Exact,
+                    best_move: Some(best_move),
+                });
+            }
+        }
+
+        (best_move, alpha)
+    }
+
+    fn negamax(
+        &mut self,
+        position: &Position,
+        depth: u32,
+        ply: usize,
+        mut alpha: i32,
+        beta: i32,
+    ) -> i32 {
+        if self.should_stop() {
+            return 0;
+        }
+
+        self.nodes += 1;
+
+        if let Some(score) = variant_score(position, ply) {
+            return score;
+        }
+
+        let key = zobrist::hash(position);
+
+        if position.halfmove_clock >= 100 || self.is_repetition(position, key) {
+            return 0;
+        }
+
+        let in_check = position.in_check();
+
+        // Looking one half-move further when in check, so mates are not overlooked.
+        let depth = if in_check && ply < MAX_PLY / 2 {
+            depth + 1
+        } else {
+            depth
+        };
+
+        if depth == 0 || ply >= MAX_PLY - 1 {
+            return self.quiescence(position, ply, alpha, beta);
+        }
+
+        let entry = self.table.probe(key);
+
+        if let Some(entry) = entry {
+            if entry.depth >= depth {
+                let score = score_from_table(entry.score, ply);
+
+                match entry.bound {
+                    Bound::Exact => return score,
+                    Bound::Lower if score >= beta => return score,
+                    Bound::Upper if score <= alpha => return score,
+                    _ => (),
+                }
+            }
+        }
+
+        let mut moves = position.legal_moves();
+
+        if moves.is_empty() {
+            return if in_check { -MATE + ply as i32 } else { 0 };
+        }
+
+        let table_move = entry.and_then(|entry| entry.best_move);
+        self.order_moves(position, &mut moves, table_move, ply);
+
+        let original_alpha = alpha;
+        let mut best_score = -INFINITY;
+        let mut best_move = None;
+
+        self.keys.push(key);
+
+        for mv in moves {
+            let next = position.play(mv);
+            let score = -self.negamax(&next, depth - 1, ply + 1, -beta, -alpha);
+
+            if self.stopped {
+                self.keys.pop();
+                return 0;
+            }
+
+            if score > best_score {
+                best_score = score;
+                best_move = Some(mv);
+            }
+
+            if score > alpha {
+                alpha = score;
+            }
+
+            if alpha >= beta {
+                if !position.is_capture(mv) && self.killers[ply][0] != Some(mv) {
+                    self.killers[ply][1] = self.killers[ply][0];
+                    self.killers[ply][0] = Some(mv);
+                }
+                break;
+            }
+        }
+
+        self.keys.pop();
+
+        let bound = if best_score <= original_alpha {
+            Bound::Upper
+        } else if best_score >= beta {
+            Bound::Lower
+        } else {
+            Bound::Exact
+        };
+
+        self.table.store(Entry {
+            key,
+            depth,
+            score: score_to_table(best_score, ply),
+            bound,
+            best_move,
+        });
+
+        best_score
+    }
+
+    /// Searches only captures and promotions, so the position is quiet before it gets evaluated.
+    fn quiescence(&mut self, position: &Position, ply: usize, mut alpha: i32, beta: i32) -> i32 {
+        if self.should_stop() {
+            return 0;
+        }
+
+        self.nodes += 1;
+
+        if let Some(score) = variant_score(position, ply) {
+            return score;
+        }
+
+        let forced = position.variant.rules().forced_captures()
+            && position
+                .legal_moves()
+                .iter()
+                .any(|mv| position.is_capture(*mv));
+
+        // The player on turn does not have to capture, so the score is at least the current one.
+        // When the variant forces a capture, every capture gets searched instead.
+        if !forced || ply >= MAX_PLY - 1 {
+            let stand_pat = evaluate(position);
+
+            if stand_pat >= beta || ply >= MAX_PLY - 1 {
+                return stand_pat;
+            }
+
+            alpha = alpha.max(stand_pat);
+        }
+
+        let mut moves: Vec<Move> = position
+            .legal_moves()
+            .into_iter()
+            .filter(|mv| position.is_capture(*mv) || mv.promotion.is_some())
+            .collect();
+
+        self.order_moves(position, &mut moves, None, ply);
+
+        for mv in moves {
+            let score = -self.quiescence(&position.play(mv), ply + 1, -beta, -alpha);
+
+            if self.stopped {
+                return 0;
+            }
+
+            if score >= beta {
+                return score;
+            }
+
+            alpha = alpha.max(score);
+        }
+
+        alpha
+    }
+
+    /// Sorts the moves so the most promising ones get searched first:
+    /// the best move found before, then captures of valuable pieces by cheap ones, promotions and killer moves.
+    fn order_moves(
+        &self,
+        position: &Position,
+        moves: &mut [Move],
+        table_move: Option<Move>,
+        ply: usize,
+    ) {
+        moves.sort_by_cached_key(|mv| {
+            let score = if Some(*mv) == table_move {
+                1_000_000
+            } else if position.is_capture(*mv) {
+                let victim = if mv.kind == MoveKind::EnPassant {
+                    100
+                } else {
+                    piece_value(position.piece_at(mv.to))
+                };
+
+                100_000 + victim * 10 - piece_value(position.piece_at(mv.from)) / 10
+            } else if let Some(promotion) = mv.promotion {
+                90_000 + piece_value(promotion.to_char(Player::White))
+            } else if self.killers[ply].contains(&Some(*mv)) {
+                80_000
+            } else {
+                0
+            };
+
+            -score
+        });
+    }
+
+    /// Follows the best moves stored in the table, as long as they are legal.
+    fn principal_variation(&self, position: &Position, depth: usize) -> Vec<Move> {
+        let mut pv = Vec::new();
+        let mut position = *position;
+        let mut seen = Vec::new();
+
+        while pv.len() < depth {
+            let key = zobrist::hash(&position);
+
+            // Stopping at repetitions, which would go on forever.
+            if seen.contains(&key) {
+                break;
+            }
+
+            seen.push(key);
+
+            match self.table.probe(key).and_then(|entry| entry.best_move) {
+                Some(mv) if position.legal_moves().contains(&mv) => {
+                    pv.push(mv);
+                    position = position.play(mv);
+                }
+                _ => break,
+            }
+        }
+
+        pv
+    }
+}
+
+/// Scores a win by the rules of the variant like a checkmate, for the player on turn.
+fn variant_score(position: &Position, ply: usize) -> Option<i32> {
+    check::variant_winner(position).map(|winner| {
+        if winner == position.turn {
+            MATE - ply as i32
+        } else {
+            -MATE + ply as i32
+        }
+    })
+}
+
+/// Mate scores are stored as the distance from the position, not from the root.
+fn score_to_table(score: i32, ply: usize) -> i32 {
+    if score >= MATE - MAX_PLY as i32 {
+        score + ply as i32
+    } else if score <= -MATE + MAX_PLY as i32 {
+        score - ply as i32
+    } else {
+        score
+    }
+}
+
+fn score_from_table(score: i32, ply: usize) -> i32 {
+    if score >= MATE - MAX_PLY as i32 {
+        score - ply as i32
+    } else if score <= -MATE + MAX_PLY as i32 {
+        score + ply as i32
+    } else {
+        score
+    }
+}
+
+/// A search running on its own thread, so the window does not freeze while the engine thinks.
+pub struct BackgroundSearch {
+    /// The position being searched.
+    pub position: Position,
+    stop: Arc<AtomicBool>,
+    receiver: Receiver<(Engine, SearchResult)>,
+}
+
+impl BackgroundSearch {
+    /// Starts searching the last position of the game, the engine is handed back with the result.
+    pub fn start(mut engine: Engine, history: History, limits: SearchLimits) -> BackgroundSearch {
+        let (sender, receiver) = mpsc::channel();
+        let stop = engine.stop.clone();
+        let position = *history.current();
+
+        stop.store(false, Ordering::Relaxed);
+
+        thread::spawn(move || {
+            let result = engine.search(&history, limits, |_| ());
+            let _ = sender.send((engine, result));
+        });
+
+        BackgroundSearch {
+            position,
+            stop,
+            receiver,
+        }
+    }
+
+    /// Makes the search finish early.
+    pub fn stop(&self) {
+        self.stop.store(true, Ordering::Relaxed);
+    }
+
+    /// Gets the engine and its result once the search is done, without waiting.
+    pub fn poll(&self) -> Option<(Engine, SearchResult)> {
+        self.receiver.try_recv().ok()
+    }
+}
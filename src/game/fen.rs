@@ -0,0 +1,475 @@
+use std::error::Error;
+use std::fmt;
+
+use super::bitboard::{bit, Bitboard};
+use super::board::Chessboard;
+use super::notation::{parse_square, square_name};
+use super::pieces::{Piece, PieceKind};
+use super::player::Player;
+use super::position::Position;
+use super::variant::VariantKind;
+
+/// The standard starting position in Forsyth-Edwards Notation.
+pub const STARTING_FEN: &str = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1";
+
+/// The reasons a FEN string can be rejected by `Position::from_fen`.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum FenError {
+    /// A FEN needs 4 to 6 space separated fields.
+    FieldCount(usize),
+    /// The board needs 8 rows separated by slashes.
+    RowCount(usize),
+    /// A row does not add up to 8 squares, counting from the 8th rank.
+    RowLength(usize),
+    InvalidPiece(char),
+    InvalidTurn(String),
+    InvalidCastling(String),
+    InvalidEnPassant(String),
+    InvalidClock(String),
+    /// The checks left for White and Black in Three-check, like "3+2".
+    InvalidChecks(String),
+    /// The pieces in the pockets in Crazyhouse, like "[QNpp]".
+    InvalidPocket(String),
+    /// Every player needs exactly one king, except in Antichess.
+    KingCount(Player, usize),
+    /// More pieces than a player can have, even with promotions.
+    TooManyPieces(Player),
+    PawnOnBackRank,
+    /// A castling right without the king and a rook on the back rank to castle with.
+    CastlingMismatch(char),
+    /// The en passant square does not follow a pawn moving two squares.
+    EnPassantMismatch(String),
+    /// The player that just moved cannot still be in check.
+    OpponentInCheck,
+}
+
+impl fmt::Display for FenError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FenError::FieldCount(n) => write!(f, "expected 4 to 6 fields, found {}", n),
+            FenError::RowCount(n) => write!(f, "expected 8 rows, found {}", n),
+            FenError::RowLength(rank) => write!(f, "rank {} does not have 8 squares", rank),
+            FenError::InvalidPiece(c) => write!(f, "invalid piece '{}'", c),
+            FenError::InvalidTurn(s) => write!(f, "invalid side to move '{}'", s),
+            FenError::InvalidCastling(s) => write!(f, "invalid castling rights '{}'", s),
+            FenError::InvalidEnPassant(s) => write!(f, "invalid en passant square '{}'", s),
+            FenError::InvalidClock(s) => write!(f, "invalid move counter '{}'", s),
+            FenError::InvalidChecks(s) => write!(f, "invalid checks left '{}'", s),
+            FenError::InvalidPocket(s) => write!(f, "invalid pocket '{}'", s),
+            FenError::KingCount(player, n) => write!(f, "{} has {} kings", player, n),
+            FenError::TooManyPieces(player) => write!(f, "{} has too many pieces", player),
+            FenError::PawnOnBackRank => write!(f, "pawns cannot be on the first or last rank"),
+            FenError::CastlingMismatch(c) => {
+                write!(f, "castling right '{}' without king and rook in place", c)
+            }
+            FenError::EnPassantMismatch(s) => {
+                write!(f, "no pawn could have just moved past '{}'", s)
+            }
+            FenError::OpponentInCheck => write!(f, "the player not on turn is in check"),
+        }
+    }
+}
+
+impl Error for FenError {}
+
+impl Position {
+    /// Reads a position from a FEN string, checking that it could come up in a real game.
+    /// The halfmove clock and move number may be left out, they default to 0 and 1.
+    /// The checks each player has left in Three-check can follow the en passant square, like "3+3",
+    /// which makes the position one of Three-check.
+    /// The pockets in Crazyhouse follow the board in brackets, like "[Qnp]", and a "~" after a piece
+    /// marks it as promoted. This makes the position one of Crazyhouse.
+    pub fn from_fen(fen: &str) -> Result<Position, FenError> {
+        Position::from_variant_fen(fen, VariantKind::Standard)
+    }
+
+    /// Reads a position from a FEN string, to be played with the given variant,
+    /// unless the FEN is one of Three-check or Crazyhouse already.
+    /// The position is checked by the rules of the variant, some allow any number of kings.
+    pub fn from_variant_fen(fen: &str, variant: VariantKind) -> Result<Position, FenError> {
+        let mut fields: Vec<&str> = fen.split_whitespace().collect();
+
+        let checks = match fields.get(4) {
+            Some(field) if field.contains('+') => Some(parse_checks(fields.remove(4))?),
+            _ => None,
+        };
+
+        if fields.len() < 4 || fields.len() > 6 {
+            return Err(FenError::FieldCount(fields.len()));
+        }
+
+        let (field, pocket) = match fields[0].split_once('[') {
+            Some((field, pocket)) => (field, Some(pocket)),
+            None => (fields[0], None),
+        };
+
+        let (board, promoted) = parse_board(field)?;
+
+        let turn = match fields[1] {
+            "w" => Player::White,
+            "b" => Player::Black,
+            other => return Err(FenError::InvalidTurn(other.to_string())),
+        };
+
+        let castling = parse_castling(fields[2], &board)?;
+
+        let en_passant = match fields[3] {
+            "-" => None,
+            square => {
+                let (row, column) = parse_square(square)
+                    .ok_or_else(|| FenError::InvalidEnPassant(square.to_string()))?;
+
+                // The square the pawn skipped, the pawn itself and the square it came from.
+                let (skipped, pawn, start, expected) = if turn == Player::White {
+                    ((2, column), (3, column), (1, column), 'p')
+                } else {
+                    ((5, column), (4, column), (6, column), 'P')
+                };
+
+                if row != skipped.0
+                    || board.board[pawn.0][pawn.1] != expected
+                    || board.board[skipped.0][skipped.1] != ' '
+                    || board.board[start.0][start.1] != ' '
+                {
+                    return Err(FenError::EnPassantMismatch(square.to_string()));
+                }
+
+                Some(column)
+            }
+        };
+
+        let halfmove_clock = match fields.get(4) {
+            Some(s) => s
+                .parse()
+                .map_err(|_| FenError::InvalidClock(s.to_string()))?,
+            None => 0,
+        };
+
+        let turn_count = match fields.get(5) {
+            Some(s) => match s.parse() {
+                Ok(n) if n > 0 => n,
+                _ => return Err(FenError::InvalidClock(s.to_string())),
+            },
+            None => 1,
+        };
+
+        let position = Position {
+            board,
+            turn,
+            castling,
+            en_passant,
+            halfmove_clock,
+            turn_count,
+            variant: VariantKind::Standard,
+            checks: [0; 2],
+            pockets: [[0; 5]; 2],
+            promoted: 0,
+        };
+
+        let position = match checks {
+            Some(checks) => Position {
+                checks,
+                ..position.with_variant(VariantKind::ThreeCheck)
+            },
+            None => position,
+        };
+
+        let position = match pocket {
+            Some(pocket) => Position {
+                pockets: parse_pocket(pocket)?,
+                promoted,
+                ..position.with_variant(VariantKind::Crazyhouse)
+            },
+            None => position,
+        };
+
+        let position = if position.variant == VariantKind::Standard {
+            position.with_variant(variant)
+        } else {
+            position
+        };
+
+        validate_pieces(&position)?;
+
+        let waiting = Position {
+            turn: turn.opponent(),
+            ..position
+        };
+
+        if waiting.in_check() {
+            return Err(FenError::OpponentInCheck);
+        }
+
+        Ok(position)
+    }
+
+    /// Writes the position as a FEN string.
+    /// Castling rights are written like X-FEN, which is the same as standard FEN unless a rook
+    /// that can castle in Chess960 is not the outermost one on its side of the king.
+    pub fn to_fen(&self) -> String {
+        let mut rows = Vec::new();
+
+        let crazyhouse = self.variant == VariantKind::Crazyhouse;
+
+        for (x, row) in self.board.board.into_iter().enumerate() {
+            let mut fen_row = String::new();
+            let mut empty = 0;
+
+            for (y, square) in row.into_iter().enumerate() {
+                if square == ' ' {
+                    empty += 1;
+                } else {
+                    if empty > 0 {
+                        fen_row.push_str(&empty.to_string());
+                        empty = 0;
+                    }
+                    fen_row.push(square);
+
+                    if crazyhouse && self.promoted & bit((x, y)) != 0 {
+                        fen_row.push('~');
+                    }
+                }
+            }
+
+            if empty > 0 {
+                fen_row.push_str(&empty.to_string());
+            }
+
+            rows.push(fen_row);
+        }
+
+        let turn = if self.turn == Player::Black { "b" } else { "w" };
+
+        let mut castling = String::new();
+
+        for (i, right) in self.castling.iter().enumerate() {
+            let column = match right {
+                Some(column) => *column,
+                None => continue,
+            };
+
+            let (row, rook, c) = [(7, 'R', 'K'), (7, 'R', 'Q'), (0, 'r', 'k'), (0, 'r', 'q')][i];
+            let outside = if i % 2 == 0 { column + 1..8 } else { 0..column };
+
+            if outside
+                .into_iter()
+                .any(|other| self.board.board[row][other] == rook)
+            {
+                let file = (b'a' + column as u8) as char;
+
+                castling.push(if row == 7 {
+                    file.to_ascii_uppercase()
+                } else {
+                    file
+                });
+            } else {
+                castling.push(c);
+            }
+        }
+
+        if castling.is_empty() {
+            castling.push('-');
+        }
+
+        let en_passant = match self.en_passant {
+            Some(column) if self.turn == Player::White => square_name((2, column)),
+            Some(column) => square_name((5, column)),
+            None => "-".to_string(),
+        };
+
+        let mut board = rows.join("/");
+
+        if crazyhouse {
+            board.push('[');
+
+            for player in [Player::White, Player::Black] {
+                for (kind, count) in PieceKind::POCKET.iter().zip(self.pocket(player)).rev() {
+                    for _ in 0..count {
+                        board.push(kind.to_char(player));
+                    }
+                }
+            }
+
+            board.push(']');
+        }
+
+        let mut fields = vec![board, turn.to_string(), castling, en_passant];
+
+        if self.variant == VariantKind::ThreeCheck {
+            let left = |checks: u8| 3u8.saturating_sub(checks);
+
+            fields.push(format!("{}+{}", left(self.checks[0]), left(self.checks[1])));
+        }
+
+        fields.push(self.halfmove_clock.to_string());
+        fields.push(self.turn_count.to_string());
+
+        fields.join(" ")
+    }
+}
+
+/// Reads the pieces on the board, and the squares of the pieces marked as promoted with a "~".
+fn parse_board(field: &str) -> Result<(Chessboard, Bitboard), FenError> {
+    let rows: Vec<&str> = field.split('/').collect();
+
+    if rows.len() != 8 {
+        return Err(FenError::RowCount(rows.len()));
+    }
+
+    let mut board = Chessboard {
+        board: [[' '; 8]; 8],
+    };
+    let mut promoted = 0;
+
+    for (x, row) in rows.iter().enumerate() {
+        let mut y = 0;
+
+        for c in row.chars() {
+            if let Some(empty) = c.to_digit(10) {
+                y += empty as usize;
+            } else if "pnbrqkPNBRQK".contains(c) {
+                if y < 8 {
+                    board.board[x][y] = c;
+                }
+                y += 1;
+            } else if c == '~' && y > 0 && y <= 8 && board.board[x][y - 1] != ' ' {
+                promoted |= bit((x, y - 1));
+            } else {
+                return Err(FenError::InvalidPiece(c));
+            }
+
+            if y > 8 {
+                return Err(FenError::RowLength(8 - x));
+            }
+        }
+
+        if y != 8 {
+            return Err(FenError::RowLength(8 - x));
+        }
+    }
+
+    Ok((board, promoted))
+}
+
+/// Reads the pieces in the pockets from the part of the board field after the "[".
+fn parse_pocket(field: &str) -> Result<[[u8; 5]; 2], FenError> {
+    let error = || FenError::InvalidPocket(field.to_string());
+    let pieces = field.strip_suffix(']').ok_or_else(error)?;
+    let mut pockets = [[0u8; 5]; 2];
+
+    for c in pieces.chars() {
+        let index = PieceKind::from_char(c)
+            .and_then(|kind| PieceKind::POCKET.iter().position(|k| *k == kind))
+            .ok_or_else(error)?;
+
+        let pocket = &mut pockets[usize::from(c.is_ascii_lowercase())][index];
+        *pocket = pocket.checked_add(1).ok_or_else(error)?;
+    }
+
+    Ok(pockets)
+}
+
+/// Reads the castling rights, either like "KQkq" for the outermost rooks on either side of the king,
+/// or with the columns of the rooks like "HAha" in Shredder-FEN. X-FEN mixes both.
+fn parse_castling(field: &str, board: &Chessboard) -> Result<[Option<usize>; 4], FenError> {
+    let mut castling = [None; 4];
+
+    if field == "-" {
+        return Ok(castling);
+    }
+
+    for c in field.chars() {
+        let (row, king, rook, first_right) = if c.is_ascii_uppercase() {
+            (7, 'K', 'R', 0)
+        } else {
+            (0, 'k', 'r', 2)
+        };
+        let back_rank = board.board[row];
+
+        let king_column = back_rank
+            .iter()
+            .position(|p| *p == king)
+            .ok_or(FenError::CastlingMismatch(c))?;
+
+        let rook_column = match c.to_ascii_lowercase() {
+            'k' => (king_column + 1..8)
+                .rev()
+                .find(|column| back_rank[*column] == rook),
+            'q' => (0..king_column).find(|column| back_rank[*column] == rook),
+            file @ 'a'..='h' => Some(file as usize - 'a' as usize),
+            _ => return Err(FenError::InvalidCastling(field.to_string())),
+        }
+        .filter(|column| back_rank[*column] == rook)
+        .ok_or(FenError::CastlingMismatch(c))?;
+
+        let right = &mut castling[first_right + usize::from(rook_column < king_column)];
+
+        // Every right can only be listed once.
+        if right.is_some() {
+            return Err(FenError::InvalidCastling(field.to_string()));
+        }
+
+        *right = Some(rook_column);
+    }
+
+    Ok(castling)
+}
+
+/// Reads the checks White and Black have left, and turns them into the checks they gave.
+fn parse_checks(field: &str) -> Result<[u8; 2], FenError> {
+    let error = || FenError::InvalidChecks(field.to_string());
+    let (white, black) = field.split_once('+').ok_or_else(error)?;
+    let given = |left: &str| match left.parse::<u8>() {
+        Ok(left) if left <= 3 => Ok(3 - left),
+        _ => Err(error()),
+    };
+
+    Ok([given(white)?, given(black)?])
+}
+
+/// Checks that the pieces on the board could come up in a real game.
+fn validate_pieces(position: &Position) -> Result<(), FenError> {
+    let board = position.board.board;
+
+    for player in [Player::White, Player::Black] {
+        let pieces = Piece::get_all_player_pieces(player, position.board);
+        let count = |piece: char| {
+            pieces
+                .iter()
+                .filter(|p| p.0.eq_ignore_ascii_case(&piece))
+                .count()
+        };
+
+        let kings = count('k');
+
+        // Kings that are ordinary pieces can be taken or promoted to.
+        if kings != 1 && position.variant.rules().royal_king() {
+            return Err(FenError::KingCount(player, kings));
+        }
+
+        // Only the kings above and the pawns on the back ranks below are checked for the other variants.
+        if !position.variant.rules().limited_pieces() {
+            continue;
+        }
+
+        // Every piece beyond the starting set has to come from a promoted pawn.
+        let pawns = count('p');
+        let promoted = count('q').saturating_sub(1)
+            + count('r').saturating_sub(2)
+            + count('b').saturating_sub(2)
+            + count('n').saturating_sub(2)
+            + kings.saturating_sub(1);
+
+        if pieces.len() > 16 || pawns > 8 || pawns + promoted > 8 {
+            return Err(FenError::TooManyPieces(player));
+        }
+    }
+
+    for row in [0, 7] {
+        if board[row].iter().any(|p| p.eq_ignore_ascii_case(&'p')) {
+            return Err(FenError::PawnOnBackRank);
+        }
+    }
+
+    Ok(())
+}
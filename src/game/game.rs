@@ -1,6 +1,8 @@
-use std::path;
+use std::fs;
+use std::path::{self, Path};
+use std::time::{Duration, SystemTime};
 
-use crate::game::check::check;
+use arboard::Clipboard;
 
 use ggez::audio;
 use ggez::audio::SoundSource;
@@ -11,380 +13,1701 @@ use ggez::graphics::Image;
 use ggez::input;
 use ggez::input::keyboard;
 use ggez::input::keyboard::KeyCode;
+use ggez::timer;
 use ggez::{Context, GameResult};
 
 use glam::Vec2;
 
-use super::board::Chessboard;
-use super::check;
+use rand::Rng;
+
+use super::check::{self, DrawRule};
+use super::clock::{self, Clock, TimeBonus, TimeControl};
 use super::config::match_key;
 use super::config::{load_config, Config};
-use super::notation::print_move;
-use super::pieces::Piece;
+use super::engine::{self, BackgroundSearch, Engine, SearchLimits};
+use super::history::History;
+use super::moves::Move;
+use super::notation::{from_uci, print_move};
+use super::pgn;
+use super::pgn::PgnGame;
+use super::pieces::{Piece, PieceKind};
 use super::player::Player;
+use super::position::Position;
+use super::result::{computer_accepts_draw, DrawOffer, GameResult as Outcome};
+use super::save::{self, SavedGame, SavedHistory};
+use super::uci::UciEngine;
+use super::variant::VariantKind;
+
+/// How long the explosion of a capture in Atomic is shown.
+const EXPLOSION_TIME: Duration = Duration::from_millis(600);
+
+/// The buttons of the box shown over the board, when the game is over or an unfinished one can be resumed.
+#[derive(Clone, Copy)]
+enum OverlayButton {
+    NewGame,
+    Rematch,
+    Export,
+    Resume,
+}
+
+impl OverlayButton {
+    const GAME_OVER: [OverlayButton; 3] = [
+        OverlayButton::NewGame,
+        OverlayButton::Rematch,
+        OverlayButton::Export,
+    ];
+
+    const RESUME: [OverlayButton; 2] = [OverlayButton::Resume, OverlayButton::NewGame];
+
+    fn label(self) -> &'static str {
+        match self {
+            OverlayButton::NewGame => "New game",
+            OverlayButton::Rematch => "Rematch",
+            OverlayButton::Export => "Export PGN",
+            OverlayButton::Resume => "Resume",
+        }
+    }
+}
 
-#[derive(Clone)]
 pub struct MainGame {
-    pub board: Chessboard,
+    pub position: Position,
+    pub history: History,
+    /// When the current game started, for the PGN date and file name.
+    pub started: SystemTime,
     pub selected_piece: char,
     pub selected_piece_pos: (usize, usize),
-    pub castling: (bool, bool, bool, bool),
-    pub en_passant: Option<usize>,
-    pub turn: Player,
-    pub turn_count: usize,
+    /// The selected piece comes from the pocket in Crazyhouse, to be dropped on an empty square.
+    pub dropping: bool,
     pub config: Config,
     pub last_move: Option<((usize, usize), (usize, usize))>,
     pub draw_mode: bool,
+    /// The drawn pixels as seen from White, so they stay in place when the board is flipped.
     pub drawn_pixels: Vec<(f32, f32)>,
+    /// Turns the board around on top of the configured orientation.
+    pub flipped: bool,
+    /// The half-move shown on the board, the same as the history length when showing the current position.
+    pub viewed_ply: usize,
+    /// The games read from the last loaded PGN file.
+    pub loaded_games: Vec<PgnGame>,
+    pub loaded_game_index: usize,
+    /// The first row of moves shown in the move history panel.
+    pub history_scroll: usize,
+    /// The UCI engine playing for one or both players, if there is one.
+    pub engine: Option<UciEngine>,
+    /// What the engine thought of its last move, shown in the title.
+    pub engine_evaluation: Option<String>,
+    /// The built-in engine, while it is not thinking.
+    pub ai: Option<Engine>,
+    /// The built-in engine, while it is thinking.
+    pub ai_search: Option<BackgroundSearch>,
+    /// How the game ended, once it is over.
+    pub result: Option<Outcome>,
+    pub draw_offer: DrawOffer,
+    /// The unfinished game from the save file, until the player chooses whether to resume it.
+    pub saved_game: Option<SavedGame>,
+    /// The clocks of both players, if the game is played with a time control.
+    pub clock: Option<Clock>,
+    /// The time left on the clock of the player who moved, after every move.
+    pub clock_times: Vec<Duration>,
+    /// The square of the last capture in Atomic, and how long its explosion is still shown.
+    pub explosion: Option<((usize, usize), Duration)>,
 }
 
-impl MainGame {
-    fn new() -> GameResult<MainGame> {
-        let b = MainGame {
-            board: Chessboard::new(),
-            selected_piece: ' ',
-            selected_piece_pos: (9, 9),
-            castling: (true, true, true, true),
-            en_passant: None,
-            turn: Player::White,
-            turn_count: 1,
-            config: load_config(),
-            last_move: None,
-            draw_mode: false,
-            drawn_pixels: Vec::new(),
+impl MainGame {
+    fn new() -> GameResult<MainGame> {
+        let config = load_config();
+
+        let engine = if config.engine_path.is_empty() {
+            None
+        } else {
+            match UciEngine::start(&config.engine_path) {
+                Ok(engine) => Some(engine),
+                Err(e) => {
+                    println!("Could not start the engine {}: {}", config.engine_path, e);
+                    None
+                }
+            }
+        };
+
+        let mut b = MainGame {
+            position: Position::new(),
+            history: History::default(),
+            started: SystemTime::now(),
+            selected_piece: ' ',
+            selected_piece_pos: (9, 9),
+            dropping: false,
+            config,
+            last_move: None,
+            draw_mode: false,
+            drawn_pixels: Vec::new(),
+            flipped: false,
+            viewed_ply: 0,
+            loaded_games: Vec::new(),
+            loaded_game_index: 0,
+            history_scroll: 0,
+            engine,
+            engine_evaluation: None,
+            ai: Some(Engine::new(16)),
+            ai_search: None,
+            result: None,
+            draw_offer: DrawOffer::default(),
+            saved_game: None,
+            clock: None,
+            clock_times: Vec::new(),
+            explosion: None,
+        };
+
+        b.set_position(b.start_position());
+        b.saved_game = b.load_saved_game();
+
+        Ok(b)
+    }
+
+    /// Gets the configured variant, standard chess if it is unknown.
+    fn variant(&self) -> VariantKind {
+        VariantKind::parse(&self.config.variant).unwrap_or_else(|| {
+            println!(
+                "Unknown variant {}, playing standard chess.",
+                self.config.variant
+            );
+            VariantKind::Standard
+        })
+    }
+
+    /// Gets the position a new game starts from, set up for the configured variant.
+    /// With Chess960 it is a random or the configured one of its starting positions.
+    fn start_position(&self) -> Position {
+        let variant = self.variant();
+
+        let position = if self.config.chess960 {
+            let index = self
+                .config
+                .chess960_position
+                .filter(|i| *i < 960)
+                .unwrap_or_else(|| rand::thread_rng().gen_range(0..960));
+
+            println!("\nChess960 position {}", index);
+
+            Position::chess960(index)
+        } else {
+            Position::new()
+        };
+
+        variant
+            .rules()
+            .start_position(position.with_variant(variant))
+    }
+
+    /// Sets up the clocks for a new game with the configured time control.
+    fn new_clock(&self) -> Option<Clock> {
+        if self.config.time_control.is_empty() {
+            return None;
+        }
+
+        let bonus = TimeBonus::parse(&self.config.time_bonus).unwrap_or_else(|| {
+            println!(
+                "Unknown time bonus {}, using Fischer.",
+                self.config.time_bonus
+            );
+            TimeBonus::Fischer
+        });
+
+        match TimeControl::parse(&self.config.time_control, bonus) {
+            Some(control) => Some(Clock::new(control)),
+            None => {
+                println!("Invalid time control {}.", self.config.time_control);
+                None
+            }
+        }
+    }
+
+    /// Reads the save file, keeping the game only if it can still be played on.
+    fn load_saved_game(&self) -> Option<SavedGame> {
+        let path = Path::new(&self.config.save_file);
+
+        if !path.exists() {
+            return None;
+        }
+
+        match save::load_game(path) {
+            Ok(saved) => saved.is_unfinished().then_some(saved),
+            Err(e) => {
+                println!("Could not read the saved game {}: {}", path.display(), e);
+                None
+            }
+        }
+    }
+
+    /// Writes the game to the save file, to carry on with it after closing the window.
+    fn save_game(&self) {
+        let saved = SavedGame {
+            history: SavedHistory::new(&self.history),
+            started: self.started,
+            clock: self.clock.clone(),
+            clock_times: self.clock_times.clone(),
+            result: self.result,
+            draw_offer: self.draw_offer.offered_by,
+            flipped: self.flipped,
+            white_player_name: self.config.white_player_name.clone(),
+            black_player_name: self.config.black_player_name.clone(),
+            engine_player: self.config.engine_player.clone(),
+            ai_player: self.config.ai_player.clone(),
+        };
+
+        if let Err(e) = save::save_game(Path::new(&self.config.save_file), &saved) {
+            println!(
+                "\nCould not save the game to {}: {}",
+                self.config.save_file, e
+            );
+        }
+    }
+
+    /// Carries on with a saved game where it was left.
+    fn resume_game(&mut self, saved: SavedGame) {
+        let history = match saved.history.to_history() {
+            Ok(history) => history,
+            Err(e) => {
+                println!("\nCould not resume the saved game: {}", e);
+                return;
+            }
+        };
+
+        self.set_position(*history.start());
+
+        self.history = history;
+        self.position = *self.history.current();
+        self.started = saved.started;
+        self.clock = saved.clock;
+        self.clock_times = saved.clock_times;
+        self.result = saved.result;
+        self.draw_offer = DrawOffer {
+            offered_by: saved.draw_offer,
+        };
+        self.flipped = saved.flipped;
+        self.config.white_player_name = saved.white_player_name;
+        self.config.black_player_name = saved.black_player_name;
+        self.config.engine_player = saved.engine_player;
+        self.config.ai_player = saved.ai_player;
+
+        self.show_ply(self.history.len());
+
+        println!("\nResumed the game from {}", self.config.save_file);
+    }
+
+    /// Moves the selected piece to the given square, if the move is legal.
+    /// A piece from the pocket gets dropped there instead.
+    /// Returns true if the move was made.
+    pub fn make_move(&mut self, ctx: &mut Context, y_sq: usize, x_sq: usize) -> bool {
+        let promotion = self.get_promotion(ctx, (y_sq, x_sq));
+        let piece = self.selected_piece;
+
+        self.selected_piece = ' ';
+
+        let mv = if self.dropping {
+            self.dropping = false;
+
+            PieceKind::from_char(piece).and_then(|kind| {
+                self.position
+                    .legal_drops(kind)
+                    .into_iter()
+                    .find(|mv| mv.to == (y_sq, x_sq))
+            })
+        } else {
+            self.position
+                .find_move(self.selected_piece_pos, (y_sq, x_sq), promotion)
+        };
+
+        match mv {
+            Some(mv) => self.play_move(ctx, mv),
+            None => false,
+        }
+    }
+
+    /// Makes a move in the current position, if it is legal.
+    /// Returns true if the move was made.
+    pub fn play_move(&mut self, ctx: &mut Context, mv: Move) -> bool {
+        let new_position = match self.position.apply_move(mv) {
+            Ok(p) => p,
+            Err(_) => return false,
+        };
+
+        // Printing the move to the console.
+        print_move(&self.position, mv);
+
+        self.last_move = Some((mv.from, mv.destination()));
+
+        if self.position.variant.rules().explodes_on_capture() && self.position.is_capture(mv) {
+            self.explosion = Some((mv.to, EXPLOSION_TIME));
+        }
+
+        // Playing some sound effects. The default ones are from lichess.
+        if !self.position.is_capture(mv) {
+            let mut move_sound = audio::Source::new(ctx, "/sounds/move.mp3").unwrap();
+            move_sound.set_volume(self.config.volume);
+            let _ = move_sound.play_detached(ctx);
+        } else {
+            let mut capture_sound = audio::Source::new(ctx, "/sounds/capture.mp3").unwrap();
+            capture_sound.set_volume(self.config.volume);
+            let _ = capture_sound.play_detached(ctx);
+        }
+
+        // Moving instead of answering declines the draw offer of the other player.
+        if self.draw_offer.move_made(self.position.turn) {
+            println!("\n{} declined the draw.", self.position.turn);
+        }
+
+        // No clock runs before the first move, then every move starts the clock of the other player.
+        if let Some(clock) = self.clock.as_mut() {
+            clock.press(self.position.turn);
+
+            self.clock_times.truncate(self.history.len());
+            self.clock_times.push(clock.remaining(self.position.turn));
+        }
+
+        self.position = new_position;
+        self.history.push(mv, new_position);
+        self.viewed_ply = self.history.len();
+        self.scroll_to_ply(self.viewed_ply);
+
+        if let Some(result) = check::game_result(&self.history) {
+            self.end_game(result);
+            return true;
+        }
+
+        if let Some(rule) = check::claimable_draw(&self.history) {
+            println!(
+                "\n{} can claim a draw by {}, press {}.",
+                self.position.turn, rule, self.config.claim_draw_button
+            );
+        }
+
+        self.save_game();
+
+        true
+    }
+
+    /// Stores how the game ended, stops the clocks and saves the finished game.
+    fn end_game(&mut self, result: Outcome) {
+        self.result = Some(result);
+        self.draw_offer = DrawOffer::default();
+        self.selected_piece = ' ';
+        self.stop_clock();
+
+        println!("\n{}.", result);
+
+        self.save_game();
+        self.export_pgn();
+    }
+
+    fn stop_clock(&mut self) {
+        if let Some(clock) = self.clock.as_mut() {
+            clock.stop();
+        }
+    }
+
+    /// Lets the time pass on the clock of the player on turn, ending the game if it runs out.
+    fn update_clock(&mut self, ctx: &mut Context) {
+        if self.is_game_over() {
+            return;
+        }
+
+        let clock = match self.clock.as_mut() {
+            Some(clock) => clock,
+            None => return,
+        };
+
+        clock.tick(timer::delta(ctx));
+
+        if let Some(player) = clock.flagged() {
+            self.flag_fall(player);
+            self.update_title(ctx);
+        }
+    }
+
+    /// Lets the explosion of the last capture fade away.
+    fn update_explosion(&mut self, ctx: &mut Context) {
+        if let Some((square, time)) = self.explosion {
+            let time = time.saturating_sub(timer::delta(ctx));
+
+            self.explosion = (!time.is_zero()).then_some((square, time));
+        }
+    }
+
+    /// Ends the game when a player ran out of time.
+    /// The other player only wins if they still had a way to win.
+    fn flag_fall(&mut self, player: Player) {
+        self.stop_engine();
+
+        println!("\n{} ran out of time.", player);
+
+        if self
+            .position
+            .variant
+            .rules()
+            .can_win(&self.position, player.opponent())
+        {
+            self.end_game(Outcome::Timeout {
+                winner: player.opponent(),
+            });
+        } else {
+            self.end_game(Outcome::Draw(DrawRule::TimeoutVsInsufficientMaterial));
+        }
+    }
+
+    /// Ends the game in a draw, if the player on turn is allowed to claim one.
+    pub fn claim_draw(&mut self) {
+        if !self.is_viewing_current() || self.is_game_over() {
+            return;
+        }
+
+        match check::claimable_draw(&self.history) {
+            Some(rule) => {
+                self.stop_engine();
+                println!("\n{} claimed a draw.", self.position.turn);
+                self.end_game(Outcome::Draw(rule));
+            }
+            None => println!("\nThere is no draw to claim."),
+        }
+    }
+
+    fn is_computer_player(&self, player: Player) -> bool {
+        self.is_engine_player(player) || self.is_ai_player(player)
+    }
+
+    /// Checks if the computer is on turn in the history, playing against a person.
+    fn is_computer_on_turn(&self) -> bool {
+        let turn = self.history.current().turn;
+
+        self.is_computer_player(turn) && !self.is_computer_player(turn.opponent())
+    }
+
+    /// Gets the player at the board who resigns or offers a draw, which is the player on turn,
+    /// or the other one while the computer is thinking. Nobody does when the computer plays both sides.
+    fn acting_player(&self) -> Option<Player> {
+        let turn = self.position.turn;
+
+        if !self.is_computer_player(turn) {
+            Some(turn)
+        } else if !self.is_computer_player(turn.opponent()) {
+            Some(turn.opponent())
+        } else {
+            None
+        }
+    }
+
+    /// Ends the game with the acting player resigning.
+    pub fn resign(&mut self) {
+        if !self.is_viewing_current() || self.is_game_over() {
+            return;
+        }
+
+        if let Some(player) = self.acting_player() {
+            self.stop_engine();
+            println!("\n{} resigned.", player);
+            self.end_game(Outcome::Resignation {
+                winner: player.opponent(),
+            });
+        }
+    }
+
+    /// Offers a draw to the other player, or accepts theirs if they offered one already.
+    pub fn offer_draw(&mut self) {
+        if !self.is_viewing_current() || self.is_game_over() {
+            return;
+        }
+
+        let player = match self.acting_player() {
+            Some(player) => player,
+            None => return,
+        };
+
+        if self.draw_offer.offered_by == Some(player.opponent()) {
+            self.answer_draw(player, true);
+            return;
+        } else if !self.draw_offer.offer(player) {
+            return;
+        }
+
+        println!(
+            "\n{} offers a draw, {} to accept or {} to decline.",
+            player, self.config.accept_draw_button, self.config.decline_draw_button
+        );
+
+        // The computer answers right away.
+        let computer = player.opponent();
+
+        if self.is_computer_player(computer) {
+            let accept = computer_accepts_draw(&self.position, computer);
+
+            self.answer_draw(computer, accept);
+        }
+    }
+
+    /// Ends the game in a draw, if the other player of the acting one offered it.
+    pub fn accept_draw(&mut self) {
+        if !self.is_viewing_current() || self.is_game_over() {
+            return;
+        }
+
+        if let Some(player) = self.acting_player() {
+            self.answer_draw(player, true);
+        }
+    }
+
+    /// Turns down the draw the other player of the acting one offered, and the game goes on.
+    pub fn decline_draw(&mut self) {
+        if !self.is_viewing_current() || self.is_game_over() {
+            return;
+        }
+
+        if let Some(player) = self.acting_player() {
+            self.answer_draw(player, false);
+        }
+    }
+
+    /// Accepts or declines the draw offered to the player, if there is one.
+    fn answer_draw(&mut self, player: Player, accept: bool) {
+        if !accept {
+            if self.draw_offer.decline(player) {
+                println!("\n{} declined the draw.", player);
+            }
+            return;
+        }
+
+        if let Some(result) = self.draw_offer.accept(player) {
+            self.stop_engine();
+            println!("\n{} accepted the draw.", player);
+            self.end_game(result);
+        }
+    }
+
+    /// Checks if the game ended, in any way.
+    pub fn is_game_over(&self) -> bool {
+        self.result.is_some()
+    }
+
+    /// Writes the game so far to a PGN file in the configured folder.
+    pub fn export_pgn(&self) {
+        let tags = pgn::game_tags(
+            &self.config.white_player_name,
+            &self.config.black_player_name,
+            self.started,
+            self.result,
+            self.clock.as_ref().map(|clock| &clock.control),
+        );
+
+        let path = Path::new(&self.config.pgn_folder).join(pgn::pgn_file_name(self.started));
+
+        match pgn::save_pgn(
+            &path,
+            &pgn::write_pgn(&tags, &self.history, &self.clock_times),
+        ) {
+            Ok(()) => println!("\nSaved the game to {}", path.display()),
+            Err(e) => println!("\nCould not save the game to {}: {}", path.display(), e),
+        }
+    }
+
+    /// Starts over from the given position.
+    pub fn set_position(&mut self, position: Position) {
+        self.stop_engine();
+
+        self.position = position;
+        self.history = History::new(position);
+        self.started = SystemTime::now();
+        self.selected_piece = ' ';
+        self.selected_piece_pos = (9, 9);
+        self.last_move = None;
+        self.draw_mode = false;
+        self.drawn_pixels = Vec::new();
+        self.viewed_ply = 0;
+        self.history_scroll = 0;
+        self.result = None;
+        self.draw_offer = DrawOffer::default();
+        self.clock = self.new_clock();
+        self.clock_times = Vec::new();
+        self.explosion = None;
+    }
+
+    /// Loads a position from a FEN string, keeping the current one if the FEN is not valid.
+    /// The position is played with the configured variant, unless the FEN is one of Three-check or Crazyhouse.
+    pub fn load_fen(&mut self, fen: &str) {
+        match Position::from_variant_fen(fen.trim(), self.variant()) {
+            Ok(position) => {
+                println!("\nLoaded position {}", position.to_fen());
+                self.set_position(position);
+            }
+            Err(e) => println!("\nCould not load the position: {}", e),
+        }
+    }
+
+    /// Loads every game in the configured PGN file and shows the first one.
+    pub fn load_pgn(&mut self) {
+        let text = match fs::read_to_string(&self.config.pgn_file) {
+            Ok(text) => text,
+            Err(e) => {
+                println!("\nCould not read {}: {}", self.config.pgn_file, e);
+                return;
+            }
+        };
+
+        match pgn::parse_pgn(&text) {
+            Ok(games) if games.is_empty() => {
+                println!("\nThere are no games in {}", self.config.pgn_file)
+            }
+            Ok(games) => {
+                self.loaded_games = games;
+                self.show_game(0);
+            }
+            Err(e) => println!("\nCould not load the games: {}", e),
+        }
+    }
+
+    /// Shows a loaded game from its starting position, use the arrow keys to step through it.
+    pub fn show_game(&mut self, index: usize) {
+        let game = match self.loaded_games.get(index) {
+            Some(game) => game.clone(),
+            None => return,
+        };
+
+        let history = game.history();
+
+        self.set_position(*history.current());
+        self.history = history;
+        self.loaded_game_index = index;
+        self.result = check::game_result(&self.history);
+
+        println!(
+            "\nGame {} of {}: {} - {} ({})",
+            index + 1,
+            self.loaded_games.len(),
+            game.tag("White").unwrap_or("?"),
+            game.tag("Black").unwrap_or("?"),
+            game.result
+        );
+    }
+
+    /// Shows the position after the given number of half-moves, highlighting the move leading to it.
+    /// Moves can only be made again once the last position is shown.
+    pub fn show_ply(&mut self, ply: usize) {
+        if ply > self.history.len() {
+            return;
+        }
+
+        self.viewed_ply = ply;
+        self.selected_piece = ' ';
+        self.last_move = ply.checked_sub(1).map(|i| {
+            (
+                self.history.moves[i].from,
+                self.history.moves[i].destination(),
+            )
+        });
+        self.scroll_to_ply(ply);
+    }
+
+    /// Checks if the current position is shown, and not one from earlier in the game.
+    pub fn is_viewing_current(&self) -> bool {
+        self.viewed_ply == self.history.len()
+    }
+
+    /// The height of a row in the move history panel.
+    fn history_row_height(&self) -> f32 {
+        self.config.font_size * 1.5
+    }
+
+    /// The width of the move numbers in the move history panel, the moves share the rest.
+    fn history_number_width(&self) -> f32 {
+        self.config.font_size * 3.0
+    }
+
+    /// The height of the clocks above and below the move history panel, if there are any.
+    fn clock_height(&self) -> f32 {
+        if self.clock.is_some() {
+            self.config.font_size * 3.0
+        } else {
+            0.0
+        }
+    }
+
+    /// The height of the pockets in variants that have them, between the clocks and the move history panel.
+    fn pocket_height(&self) -> f32 {
+        if self.position.variant.rules().has_pockets() {
+            self.config.history_panel_width / PieceKind::POCKET.len() as f32
+        } else {
+            0.0
+        }
+    }
+
+    /// Where the move history panel starts, below the clock and the pocket of the player at the top.
+    fn history_top(&self) -> f32 {
+        self.clock_height() + self.pocket_height()
+    }
+
+    /// How many rows of moves fit into the move history panel.
+    fn history_visible_rows(&self) -> usize {
+        let height = self.config.window_size_vertical - 2.0 * self.history_top();
+
+        ((height / self.history_row_height()).floor() as usize).max(1)
+    }
+
+    /// Gets the row and column of a half-move in the move history panel, the columns being 0 for White and 1 for Black.
+    /// The first row leaves out the White move if Black made the first move.
+    fn history_slot(&self, ply: usize) -> (usize, usize) {
+        let slot = ply - 1 + (self.history.start().turn == Player::Black) as usize;
+
+        (slot / 2, slot % 2)
+    }
+
+    /// The number of rows of moves in the move history panel.
+    fn history_rows(&self) -> usize {
+        if self.history.is_empty() {
+            0
+        } else {
+            self.history_slot(self.history.len()).0 + 1
+        }
+    }
+
+    /// Scrolls the move history panel just enough to show the given half-move.
+    fn scroll_to_ply(&mut self, ply: usize) {
+        let row = if ply == 0 {
+            0
+        } else {
+            self.history_slot(ply).0
+        };
+
+        let visible_rows = self.history_visible_rows();
+
+        if row < self.history_scroll {
+            self.history_scroll = row;
+        } else if row >= self.history_scroll + visible_rows {
+            self.history_scroll = row + 1 - visible_rows;
+        }
+    }
+
+    /// Shows the half-move clicked on in the move history panel.
+    fn click_history_panel(&mut self, x: f32, y: f32) {
+        let x = x - self.config.window_size_horizontal - self.history_number_width();
+        let y = y - self.history_top();
+        let column_width = (self.config.history_panel_width - self.history_number_width()) / 2.0;
+
+        if x < 0.0 || y < 0.0 {
+            return;
+        }
+
+        let visible_row = (y / self.history_row_height()).floor() as usize;
+
+        // Clicks on the pocket or the clock below the moves.
+        if visible_row >= self.history_visible_rows() {
+            return;
+        }
+
+        let row = self.history_scroll + visible_row;
+        let column = if x < column_width { 0 } else { 1 };
+
+        let slot = row * 2 + column;
+        let offset = (self.history.start().turn == Player::Black) as usize;
+
+        if slot >= offset && slot + 1 - offset <= self.history.len() {
+            self.show_ply(slot + 1 - offset);
+        }
+    }
+
+    /// Draws the numbered moves of the game in two columns, next to the board.
+    /// The move leading to the position shown on the board is highlighted.
+    fn draw_history_panel(&self, ctx: &mut Context, font: graphics::Font) -> GameResult {
+        if self.history.is_empty() {
+            return Ok(());
+        }
+
+        let left = self.config.window_size_horizontal;
+        let top = self.history_top();
+        let row_height = self.history_row_height();
+        let number_width = self.history_number_width();
+        let column_width = (self.config.history_panel_width - number_width) / 2.0;
+
+        let last_row = self
+            .history_rows()
+            .min(self.history_scroll + self.history_visible_rows());
+
+        let highlight = graphics::Mesh::new_rectangle(
+            ctx,
+            graphics::DrawMode::fill(),
+            graphics::Rect::new(0.0, 0.0, column_width, row_height),
+            self.config.last_move_end_color.into(),
+        )?;
+
+        let offset = (self.history.start().turn == Player::Black) as usize;
+
+        for row in self.history_scroll..last_row {
+            let y = top
+                + (row - self.history_scroll) as f32 * row_height
+                // Centering the text in the row.
+                + (row_height - self.config.font_size) / 2.0;
+
+            let number = graphics::Text::new((
+                format!("{}.", self.history.start().turn_count + row),
+                font,
+                self.config.font_size,
+            ));
+
+            graphics::draw(
+                ctx,
+                &number,
+                graphics::DrawParam::default()
+                    .dest(Vec2::new(left + self.config.font_size / 2.0, y))
+                    .color(self.config.font_color.into()),
+            )?;
+
+            for column in 0..2 {
+                let slot = row * 2 + column;
+
+                if slot < offset || slot + 1 - offset > self.history.len() {
+                    continue;
+                }
+
+                let ply = slot + 1 - offset;
+                let x = left + number_width + column as f32 * column_width;
+
+                if ply == self.viewed_ply {
+                    graphics::draw(
+                        ctx,
+                        &highlight,
+                        graphics::DrawParam::default().dest(Vec2::new(
+                            x - self.config.font_size / 4.0,
+                            top + (row - self.history_scroll) as f32 * row_height,
+                        )),
+                    )?;
+                }
+
+                let text = graphics::Text::new((
+                    self.history.notations[ply - 1].as_str(),
+                    font,
+                    self.config.font_size,
+                ));
+
+                graphics::draw(
+                    ctx,
+                    &text,
+                    graphics::DrawParam::default()
+                        .dest(Vec2::new(x, y))
+                        .color(self.config.font_color.into()),
+                )?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Draws the clock of the player at the top of the board above the move history panel,
+    /// and the other one below it. The running clock is highlighted, and so is an empty one.
+    fn draw_clocks(&self, ctx: &mut Context, font: graphics::Font) -> GameResult {
+        let clock = match &self.clock {
+            Some(clock) => clock,
+            None => return Ok(()),
+        };
+
+        let left = self.config.window_size_horizontal;
+        let height = self.clock_height();
+        let font_size = self.config.font_size * 1.5;
+
+        let (top_player, bottom_player) = if self.is_flipped() {
+            (Player::White, Player::Black)
+        } else {
+            (Player::Black, Player::White)
+        };
+
+        for (player, y) in [
+            (top_player, 0.0),
+            (bottom_player, self.config.window_size_vertical - height),
+        ] {
+            let remaining = clock.remaining(player);
+
+            let background = if remaining.is_zero() {
+                Some(self.config.check_color)
+            } else if clock.running == Some(player) {
+                Some(self.config.last_move_end_color)
+            } else {
+                None
+            };
+
+            if let Some(color) = background {
+                let rectangle = graphics::Mesh::new_rectangle(
+                    ctx,
+                    graphics::DrawMode::fill(),
+                    graphics::Rect::new(left, y, self.config.history_panel_width, height),
+                    color.into(),
+                )?;
+
+                graphics::draw(ctx, &rectangle, graphics::DrawParam::default())?;
+            }
+
+            let text = graphics::Text::new((clock::format_time(remaining), font, font_size));
+
+            graphics::draw(
+                ctx,
+                &text,
+                graphics::DrawParam::default()
+                    .dest(Vec2::new(
+                        left + self.config.font_size / 2.0,
+                        y + (height - font_size) / 2.0,
+                    ))
+                    .color(self.config.font_color.into()),
+            )?;
+        }
+
+        Ok(())
+    }
+
+    /// Gets the pocket of the player at the top or the bottom of the board, with the top of the pocket.
+    fn pocket_tops(&self) -> [(Player, f32); 2] {
+        let (top_player, bottom_player) = if self.is_flipped() {
+            (Player::White, Player::Black)
+        } else {
+            (Player::Black, Player::White)
+        };
+
+        [
+            (top_player, self.clock_height()),
+            (
+                bottom_player,
+                self.config.window_size_vertical - self.history_top(),
+            ),
+        ]
+    }
+
+    /// Gets the player and the piece of the pocket slot at the pixel, in Crazyhouse.
+    fn pocket_slot_at(&self, x: f32, y: f32) -> Option<(Player, PieceKind)> {
+        let size = self.pocket_height();
+        let x = x - self.config.window_size_horizontal;
+
+        if size == 0.0 || x < 0.0 {
+            return None;
+        }
+
+        let (player, _) = self
+            .pocket_tops()
+            .into_iter()
+            .find(|(_, top)| y >= *top && y < top + size)?;
+
+        PieceKind::POCKET
+            .get((x / size).floor() as usize)
+            .map(|kind| (player, *kind))
+    }
+
+    /// Draws the pieces in the pockets in Crazyhouse, the one of the player at the top of the board
+    /// below the top clock and the other one above the bottom clock. Empty slots are faded out.
+    fn draw_pockets(&self, ctx: &mut Context, font: graphics::Font) -> GameResult {
+        let size = self.pocket_height();
+
+        if size == 0.0 {
+            return Ok(());
+        }
+
+        let position = self.history.positions[self.viewed_ply];
+
+        for (player, top) in self.pocket_tops() {
+            for (i, (kind, count)) in PieceKind::POCKET
+                .iter()
+                .zip(position.pocket(player))
+                .enumerate()
+            {
+                let x = self.config.window_size_horizontal + i as f32 * size;
+
+                // The piece being dragged out of the pocket is shown as one less.
+                let count = if self.dropping
+                    && player == self.position.turn
+                    && self.selected_piece == kind.to_char(player)
+                {
+                    count.saturating_sub(1)
+                } else {
+                    count
+                };
+
+                let alpha = if count == 0 { 0.25 } else { 1.0 };
+                let image = Image::new(ctx, Piece::get_image(kind.to_char(player)))?;
+
+                graphics::draw(
+                    ctx,
+                    &image,
+                    graphics::DrawParam::default()
+                        .dest(Vec2::new(x, top))
+                        // The default size for the pieces is 60x60.
+                        .scale(Vec2::new(size / 60.0, size / 60.0))
+                        .color(graphics::Color::new(1.0, 1.0, 1.0, alpha)),
+                )?;
+
+                if count > 1 {
+                    let text =
+                        graphics::Text::new((count.to_string(), font, self.config.font_size));
+
+                    graphics::draw(
+                        ctx,
+                        &text,
+                        graphics::DrawParam::default()
+                            .dest(Vec2::new(
+                                x + size - text.width(ctx),
+                                top + size - self.config.font_size,
+                            ))
+                            .color(self.config.font_color.into()),
+                    )?;
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Gets the title and the buttons of the box over the board, if one is shown.
+    /// The game over box is only shown on top of the final position.
+    fn overlay(&self) -> Option<(String, &'static [OverlayButton])> {
+        if self.saved_game.is_some() {
+            return Some((
+                "Resume the unfinished game?".to_string(),
+                &OverlayButton::RESUME,
+            ));
+        }
+
+        match self.result {
+            Some(result) if self.is_viewing_current() => Some((
+                format!("{} - {}", result.tag(), result),
+                &OverlayButton::GAME_OVER,
+            )),
+            _ => None,
+        }
+    }
+
+    /// The box over the board, in the middle of it.
+    fn overlay_rect(&self) -> graphics::Rect {
+        let width = self.config.window_size_horizontal * 0.8;
+        let height = self.config.font_size * 8.0;
+
+        graphics::Rect::new(
+            (self.config.window_size_horizontal - width) / 2.0,
+            (self.config.window_size_vertical - height) / 2.0,
+            width,
+            height,
+        )
+    }
+
+    /// The buttons in a row along the bottom of the box over the board.
+    fn overlay_buttons(&self, buttons: &[OverlayButton]) -> Vec<(OverlayButton, graphics::Rect)> {
+        let rect = self.overlay_rect();
+        let margin = self.config.font_size;
+        let height = self.config.font_size * 2.5;
+        let width = (rect.w - margin * (buttons.len() + 1) as f32) / buttons.len() as f32;
+
+        buttons
+            .iter()
+            .copied()
+            .enumerate()
+            .map(|(i, button)| {
+                (
+                    button,
+                    graphics::Rect::new(
+                        rect.x + margin + (width + margin) * i as f32,
+                        rect.bottom() - margin - height,
+                        width,
+                        height,
+                    ),
+                )
+            })
+            .collect()
+    }
+
+    /// Draws a box over the board, which still shines through a little.
+    fn draw_box(&self, ctx: &mut Context, rect: graphics::Rect) -> GameResult {
+        let (r, g, b) = self.config.light_square_color;
+
+        let background = graphics::Mesh::new_rectangle(
+            ctx,
+            graphics::DrawMode::fill(),
+            rect,
+            graphics::Color::from_rgba(r, g, b, 230),
+        )?;
+        let border = graphics::Mesh::new_rectangle(
+            ctx,
+            graphics::DrawMode::stroke(2.0),
+            rect,
+            self.config.dark_square_color.into(),
+        )?;
+
+        graphics::draw(ctx, &background, graphics::DrawParam::default())?;
+        graphics::draw(ctx, &border, graphics::DrawParam::default())
+    }
+
+    /// Draws the standing draw offer at the top of the board, for the other player to answer.
+    fn draw_draw_offer(&self, ctx: &mut Context, font: graphics::Font) -> GameResult {
+        let player = match self.draw_offer.offered_by {
+            Some(player) if self.is_viewing_current() => player,
+            _ => return Ok(()),
+        };
+
+        let width = self.config.window_size_horizontal * 0.8;
+        let rect = graphics::Rect::new(
+            (self.config.window_size_horizontal - width) / 2.0,
+            self.config.font_size,
+            width,
+            self.config.font_size * 4.0,
+        );
+
+        self.draw_box(ctx, rect)?;
+
+        let lines = [
+            (format!("{} offers a draw", player), 1.0),
+            (
+                format!(
+                    "{} to accept, {} to decline",
+                    self.config.accept_draw_button, self.config.decline_draw_button
+                ),
+                2.5,
+            ),
+        ];
+
+        for (line, row) in lines {
+            let text = graphics::Text::new((line, font, self.config.font_size));
+            let text_width = text.width(ctx);
+
+            graphics::draw(
+                ctx,
+                &text,
+                graphics::DrawParam::default()
+                    .dest(Vec2::new(
+                        rect.x + (rect.w - text_width) / 2.0,
+                        rect.y + self.config.font_size * (row - 0.5),
+                    ))
+                    .color(self.config.font_color.into()),
+            )?;
+        }
+
+        Ok(())
+    }
+
+    /// Draws the explosion of a capture in Atomic as a ball of fire growing over the squares around it,
+    /// fading away as it grows.
+    fn draw_explosion(&self, ctx: &mut Context) -> GameResult {
+        let (square, time) = match self.explosion {
+            Some(explosion) if self.is_viewing_current() => explosion,
+            _ => return Ok(()),
+        };
+
+        let progress = 1.0 - time.as_secs_f32() / EXPLOSION_TIME.as_secs_f32();
+        let square_size = Vec2::new(
+            self.config.window_size_horizontal / 8.0,
+            self.config.window_size_vertical / 8.0,
+        );
+
+        let screen = self.flip_square(square);
+        let center = Vec2::new(screen.1 as f32 + 0.5, screen.0 as f32 + 0.5) * square_size;
+        // Reaching over the squares next to the capture, which blow up as well.
+        let radius = square_size.min_element() * (0.5 + progress);
+        let alpha = 1.0 - progress;
+
+        for (scale, color) in [
+            (1.5, graphics::Color::new(0.9, 0.2, 0.0, alpha * 0.8)),
+            (1.0, graphics::Color::new(1.0, 0.6, 0.0, alpha * 0.9)),
+            (0.5, graphics::Color::new(1.0, 0.95, 0.6, alpha)),
+        ] {
+            let fire = graphics::Mesh::new_circle(
+                ctx,
+                graphics::DrawMode::fill(),
+                center,
+                radius * scale,
+                1.0,
+                color,
+            )?;
+
+            graphics::draw(ctx, &fire, graphics::DrawParam::default())?;
+        }
+
+        Ok(())
+    }
+
+    /// Draws the box over the board with the buttons for what to do next.
+    fn draw_overlay(&self, ctx: &mut Context, font: graphics::Font) -> GameResult {
+        let (title, buttons) = match self.overlay() {
+            Some(overlay) => overlay,
+            None => return Ok(()),
+        };
+
+        let rect = self.overlay_rect();
+
+        self.draw_box(ctx, rect)?;
+
+        let title = graphics::Text::new((title, font, self.config.font_size * 1.5));
+        let title_width = title.width(ctx);
+
+        graphics::draw(
+            ctx,
+            &title,
+            graphics::DrawParam::default()
+                .dest(Vec2::new(
+                    rect.x + (rect.w - title_width) / 2.0,
+                    rect.y + self.config.font_size * 1.5,
+                ))
+                .color(self.config.font_color.into()),
+        )?;
+
+        for (button, area) in self.overlay_buttons(buttons) {
+            let rectangle = graphics::Mesh::new_rectangle(
+                ctx,
+                graphics::DrawMode::fill(),
+                area,
+                self.config.dark_square_color.into(),
+            )?;
+
+            graphics::draw(ctx, &rectangle, graphics::DrawParam::default())?;
+
+            let label = graphics::Text::new((button.label(), font, self.config.font_size));
+            let size = label.dimensions(ctx);
+
+            graphics::draw(
+                ctx,
+                &label,
+                graphics::DrawParam::default()
+                    .dest(Vec2::new(
+                        area.x + (area.w - size.w) / 2.0,
+                        area.y + (area.h - size.h) / 2.0,
+                    ))
+                    .color(self.config.light_square_color.into()),
+            )?;
+        }
+
+        Ok(())
+    }
+
+    /// Handles a click while the box over the board is shown.
+    /// Returns true if the click should not reach the board, because it was inside of the box
+    /// or the player still has to choose whether to resume the unfinished game.
+    fn click_overlay(&mut self, x: f32, y: f32) -> bool {
+        let buttons = match self.overlay() {
+            Some((_, buttons)) => buttons,
+            None => return false,
         };
 
-        Ok(b)
-    }
+        let clicked = self
+            .overlay_buttons(buttons)
+            .into_iter()
+            .find(|(_, area)| area.contains([x, y]));
 
-    /// Moves a piece on the MainGame board.
-    /// Checks for the "special moves" such as castling, en passant etc.
-    pub fn make_move(
-        &mut self,
-        ctx: &Context,
-        piece: char,
-        piece_pos: (usize, usize),
-        possible_moves: Vec<(usize, usize)>,
-        y_sq: usize,
-        x_sq: usize,
-    ) {
-        if possible_moves.contains(&(y_sq, x_sq)) && Piece::get_player(piece) == self.turn {
-            // Checking for Castling:
-            if piece == 'K' {
-                if self.castling.0 == true
-                    && self.board.board[7][6] == ' '
-                    && self.board.board[7][5] == ' '
-                    && self.board.board[7][7] == 'R'
-                    && y_sq == 7
-                    && x_sq == 6
-                {
-                    self.board.board[7][7] = ' ';
-                    self.board.board[7][4] = ' ';
-                    self.board.board[7][5] = 'R';
-                } else if self.castling.1 == true
-                    && self.board.board[7][1] == ' '
-                    && self.board.board[7][2] == ' '
-                    && self.board.board[7][3] == ' '
-                    && self.board.board[7][0] == 'R'
-                    && y_sq == 7
-                    && x_sq == 2
-                {
-                    self.board.board[7][0] = ' ';
-                    self.board.board[7][4] = ' ';
-                    self.board.board[7][3] = 'R';
-                }
+        match clicked {
+            Some((OverlayButton::NewGame, _)) => {
+                self.saved_game = None;
+                self.set_position(self.start_position());
             }
-
-            if piece == 'k' {
-                if self.castling.2 == true
-                    && self.board.board[0][6] == ' '
-                    && self.board.board[0][5] == ' '
-                    && self.board.board[0][7] == 'r'
-                    && y_sq == 0
-                    && x_sq == 6
-                {
-                    self.board.board[0][7] = ' ';
-                    self.board.board[0][4] = ' ';
-                    self.board.board[0][5] = 'r';
-                } else if self.castling.3 == true
-                    && self.board.board[0][1] == ' '
-                    && self.board.board[0][2] == ' '
-                    && self.board.board[0][3] == ' '
-                    && self.board.board[0][0] == 'r'
-                    && y_sq == 0
-                    && x_sq == 2
-                {
-                    self.board.board[0][0] = ' ';
-                    self.board.board[0][4] = ' ';
-                    self.board.board[0][3] = 'r';
+            Some((OverlayButton::Rematch, _)) => self.rematch(),
+            Some((OverlayButton::Export, _)) => self.export_pgn(),
+            Some((OverlayButton::Resume, _)) => {
+                if let Some(saved) = self.saved_game.take() {
+                    self.resume_game(saved);
                 }
             }
+            None => (),
+        }
 
-            // Actually making the move.
-            self.board.board[piece_pos.0][piece_pos.1] = ' ';
-            self.board.board[y_sq][x_sq] = piece;
+        self.saved_game.is_some() || self.overlay_rect().contains([x, y])
+    }
 
-            // Checking for upgrading a pawn.
-            self.upgrade_pawn(ctx, (y_sq, x_sq));
+    /// Plays the same starting position again, with the players switching colours.
+    fn rematch(&mut self) {
+        std::mem::swap(
+            &mut self.config.white_player_name,
+            &mut self.config.black_player_name,
+        );
 
-            // Checking for en passant:
-            if piece == 'P' && piece_pos.0 == 3 && self.en_passant == Some(x_sq) && y_sq == 2 {
-                self.board.board[y_sq + 1][x_sq] = ' ';
-            }
+        for player in [&mut self.config.engine_player, &mut self.config.ai_player] {
+            let swapped = match player.to_ascii_uppercase().as_str() {
+                "WHITE" => "Black",
+                "BLACK" => "White",
+                _ => continue,
+            };
 
-            if piece == 'p' && piece_pos.0 == 4 && self.en_passant == Some(x_sq) && y_sq == 5 {
-                self.board.board[y_sq - 1][x_sq] = ' ';
-            }
+            *player = swapped.to_string();
+        }
+
+        // The player at the bottom of the board keeps sitting there.
+        if !self.config.board_orientation.eq_ignore_ascii_case("auto") {
+            self.flipped ^= true;
+        }
 
-            // Enabling en passant.
-            self.en_passant = None;
+        self.set_position(*self.history.start());
+    }
+
+    /// Checks if the engine makes the moves for the player.
+    pub fn is_engine_player(&self, player: Player) -> bool {
+        if self.engine.is_none() {
+            return false;
+        }
+
+        match self.config.engine_player.to_ascii_uppercase().as_str() {
+            "WHITE" => player == Player::White,
+            "BLACK" => player == Player::Black,
+            "BOTH" => true,
+            _ => false,
+        }
+    }
+
+    /// Checks if the built-in engine makes the moves for the player.
+    pub fn is_ai_player(&self, player: Player) -> bool {
+        match self.config.ai_player.to_ascii_uppercase().as_str() {
+            "WHITE" => player == Player::White,
+            "BLACK" => player == Player::Black,
+            "BOTH" => true,
+            _ => false,
+        }
+    }
 
-            if piece == 'p' && piece_pos.0 == 1 && y_sq == 3 {
-                self.en_passant = Some(piece_pos.1);
-            } else if piece == 'P' && piece_pos.0 == 6 && y_sq == 4 {
-                self.en_passant = Some(piece_pos.1);
+    /// Tells the engines to stop thinking, the moves they find anyway get ignored.
+    fn stop_engine(&mut self) {
+        if let Some(engine) = self.engine.as_mut() {
+            if engine.searching.is_some() {
+                let _ = engine.send("stop");
             }
+        }
+
+        if let Some(search) = &self.ai_search {
+            search.stop();
+        }
+
+        self.engine_evaluation = None;
+    }
+
+    /// Lets the engine think when it is its turn, and makes its move once it is done.
+    fn update_engine(&mut self, ctx: &mut Context) {
+        let engine = match self.engine.as_mut() {
+            Some(engine) => engine,
+            None => return,
+        };
 
-            // Disabling castling.
-            if piece == 'k' {
-                self.castling.2 = false;
-                self.castling.3 = false;
-            } else if piece == 'K' {
-                self.castling.0 = false;
-                self.castling.1 = false;
+        let result = engine.poll();
+        let info = engine.info.clone();
+        let name = engine.name.clone();
+
+        if !engine.running {
+            println!("\nThe engine {} stopped running.", name);
+            self.engine = None;
+            self.update_title(ctx);
+            return;
+        }
+
+        if let Some((position, best_move)) = result {
+            // The game could have moved on while the engine was thinking.
+            if position == self.position && self.is_viewing_current() {
+                match best_move.and_then(|mv| from_uci(&position, &mv)) {
+                    Some(mv) => {
+                        self.engine_evaluation = info.score.map(|score| {
+                            format!(
+                                "{}: {} (depth {})",
+                                name,
+                                score.for_white(position.turn),
+                                info.depth.unwrap_or(0)
+                            )
+                        });
+
+                        self.play_move(ctx, mv);
+                        self.update_title(ctx);
+                    }
+                    None => println!("\nThe engine {} did not make a legal move.", name),
+                }
             }
 
-            if piece == 'r' {
-                if piece_pos == (0, 0) {
-                    self.castling.3 = false;
-                } else if piece_pos == (0, 7) {
-                    self.castling.2 = false;
+            return;
+        }
+
+        let engine_to_move = self.is_engine_player(self.position.turn)
+            && self.is_viewing_current()
+            && !self.is_game_over();
+
+        if let Some(engine) = self.engine.as_mut() {
+            if engine_to_move && engine.ready && engine.searching.is_none() {
+                if let Err(e) = engine.go(&self.history, self.config.engine_movetime) {
+                    println!("\nCould not talk to the engine {}: {}", name, e);
                 }
             }
+        }
+    }
 
-            if piece == 'R' {
-                if piece_pos == (7, 0) {
-                    self.castling.1 = false;
-                } else if piece_pos == (7, 7) {
-                    self.castling.0 = false;
+    /// Lets the built-in engine think on its own thread when it is its turn, and makes its move once it is done.
+    fn update_ai(&mut self, ctx: &mut Context) {
+        if let Some(search) = &self.ai_search {
+            if let Some((ai, result)) = search.poll() {
+                let position = search.position;
+
+                self.ai = Some(ai);
+                self.ai_search = None;
+
+                // The game could have moved on while the engine was thinking.
+                if position == self.position && self.is_viewing_current() {
+                    if let Some(mv) = result.best_move {
+                        self.engine_evaluation = Some(format!(
+                            "Computer: {} (depth {})",
+                            engine::uci_score(result.info.score).for_white(position.turn),
+                            result.info.depth
+                        ));
+
+                        self.play_move(ctx, mv);
+                        self.update_title(ctx);
+                    }
                 }
             }
 
-            // Switching turns.
-            if self.turn == Player::White {
-                self.turn = Player::Black;
-            } else {
-                self.turn = Player::White;
+            return;
+        }
+
+        let ai_to_move = self.is_ai_player(self.position.turn)
+            && self.is_viewing_current()
+            && !self.is_game_over();
+
+        if ai_to_move {
+            if let Some(ai) = self.ai.take() {
+                self.ai_search = Some(BackgroundSearch::start(
+                    ai,
+                    self.history.clone(),
+                    SearchLimits::for_level(self.config.ai_level),
+                ));
             }
         }
-        self.selected_piece = ' ';
     }
 
-    /// Upgrades a pawn when it reaches the end of the board.
-    /// Hold the Q, R, N or B keys to get the different pieces.
-    fn upgrade_pawn(&mut self, ctx: &Context, position: (usize, usize)) {
-        let pressed_keys = keyboard::pressed_keys(ctx);
+    /// Checks if Black is at the bottom of the board.
+    pub fn is_flipped(&self) -> bool {
+        let black_at_bottom = match self.config.board_orientation.to_ascii_uppercase().as_str() {
+            "BLACK" => true,
+            // Good for two players sharing one screen.
+            "AUTO" => self.history.positions[self.viewed_ply].turn == Player::Black,
+            _ => false,
+        };
 
-        if self.selected_piece == 'P' && position.0 == 0 {
-            // They are sorted roughly in order of usefulness, in my opinion.
-            if pressed_keys.contains(
-                &match_key(self.config.queen_upgrade_button.to_string()).unwrap_or(KeyCode::Q),
-            ) {
-                self.board.board[position.0][position.1] = 'Q';
-            } else if pressed_keys.contains(
-                &match_key(self.config.rook_upgrade_button.to_string()).unwrap_or(KeyCode::R),
-            ) {
-                self.board.board[position.0][position.1] = 'R';
-            } else if pressed_keys.contains(
-                &match_key(self.config.knight_upgrade_button.to_string()).unwrap_or(KeyCode::N),
-            ) {
-                self.board.board[position.0][position.1] = 'N';
-            } else if pressed_keys.contains(
-                &match_key(self.config.bishop_upgrade_button.to_string()).unwrap_or(KeyCode::B),
-            ) {
-                self.board.board[position.0][position.1] = 'B';
-            } else {
-                self.board.board[position.0][position.1] = 'Q';
-            }
-        } else if self.selected_piece == 'p' && position.0 == 7 {
-            if pressed_keys.contains(
-                &match_key(self.config.queen_upgrade_button.to_string()).unwrap_or(KeyCode::Q),
-            ) {
-                self.board.board[position.0][position.1] = 'q';
-            } else if pressed_keys.contains(
-                &match_key(self.config.rook_upgrade_button.to_string()).unwrap_or(KeyCode::R),
-            ) {
-                self.board.board[position.0][position.1] = 'r';
-            } else if pressed_keys.contains(
-                &match_key(self.config.knight_upgrade_button.to_string()).unwrap_or(KeyCode::N),
-            ) {
-                self.board.board[position.0][position.1] = 'n';
-            } else if pressed_keys.contains(
-                &match_key(self.config.bishop_upgrade_button.to_string()).unwrap_or(KeyCode::B),
-            ) {
-                self.board.board[position.0][position.1] = 'b';
-            } else {
-                self.board.board[position.0][position.1] = 'q';
-            }
+        black_at_bottom ^ self.flipped
+    }
+
+    /// Turns a square on the board into the row and column on the screen, and the other way around.
+    pub fn flip_square(&self, square: (usize, usize)) -> (usize, usize) {
+        if self.is_flipped() {
+            (7 - square.0, 7 - square.1)
+        } else {
+            square
         }
     }
 
-    /// Takes a list of possible moves as an input and only returns the legal moves.
-    pub fn get_legal_moves(
-        &mut self,
-        ctx: &Context,
-        possible_moves: Vec<(usize, usize)>,
-        y_sq: usize,
-        x_sq: usize,
-    ) -> Vec<(usize, usize)> {
-        let mut possible_moves = possible_moves.clone();
+    /// Turns a pixel on the board around if the board is flipped, and back again.
+    fn flip_pixel(&self, pixel: (f32, f32)) -> (f32, f32) {
+        if self.is_flipped() {
+            (
+                self.config.window_size_horizontal - pixel.0,
+                self.config.window_size_vertical - pixel.1,
+            )
+        } else {
+            pixel
+        }
+    }
+
+    /// Gets the square under the mouse, if it is on the board.
+    fn square_at(&self, x: f32, y: f32) -> Option<(usize, usize)> {
+        if x < 0.0
+            || y < 0.0
+            || x >= self.config.window_size_horizontal
+            || y >= self.config.window_size_vertical
+        {
+            return None;
+        }
 
-        possible_moves = self.prevent_selfcheck_moves(ctx, possible_moves, y_sq, x_sq);
-        possible_moves = self.only_check_preventing_moves(ctx, possible_moves, y_sq, x_sq);
+        let x_sq = (x / (self.config.window_size_horizontal / 8.0)).floor() as usize;
+        let y_sq = (y / (self.config.window_size_vertical / 8.0)).floor() as usize;
 
-        return possible_moves;
+        Some(self.flip_square((y_sq.min(7), x_sq.min(7))))
     }
 
-    /// Returns moves that do not put the own king in check.
-    pub fn prevent_selfcheck_moves(
-        &mut self,
-        ctx: &Context,
-        possible_moves: Vec<(usize, usize)>,
-        y_sq: usize,
-        x_sq: usize,
-    ) -> Vec<(usize, usize)> {
-        let mut possible_moves = possible_moves;
+    /// Takes back the last move of the game.
+    /// Against the computer its moves are taken back too, so the person is on turn again.
+    pub fn undo(&mut self) {
+        if self.history.undo().is_none() {
+            return;
+        }
 
-        if self.turn == Player::White {
-            let mut temp_board = self.clone();
+        while self.is_computer_on_turn() && self.history.undo().is_some() {}
 
-            temp_board.make_move(
-                ctx,
-                self.selected_piece,
-                self.selected_piece_pos,
-                possible_moves.clone(),
-                y_sq,
-                x_sq,
-            );
+        self.follow_history();
+    }
 
-            if check(
-                temp_board.board.clone(),
-                Player::White,
-                temp_board.en_passant,
-                temp_board.castling,
-            ) {
-                possible_moves.retain(|x| x != &(y_sq, x_sq));
-            }
-        } else if self.turn == Player::Black {
-            let mut temp_board = self.clone();
+    /// Makes the last move taken back again, and the moves of the computer after it.
+    pub fn redo(&mut self) {
+        if self.history.redo().is_none() {
+            return;
+        }
 
-            temp_board.make_move(
-                ctx,
-                self.selected_piece,
-                self.selected_piece_pos,
-                possible_moves.clone(),
-                y_sq,
-                x_sq,
-            );
-            if check(
-                temp_board.board,
-                Player::Black,
-                temp_board.en_passant,
-                temp_board.castling,
-            ) {
-                possible_moves.retain(|x| x != &(y_sq, x_sq));
+        while self.is_computer_on_turn() && self.history.redo().is_some() {}
+
+        self.follow_history();
+    }
+
+    /// Continues the game from the last position in the history.
+    fn follow_history(&mut self) {
+        self.stop_engine();
+
+        self.position = *self.history.current();
+        self.clock_times.truncate(self.history.len());
+        self.draw_offer = DrawOffer::default();
+
+        // The clocks do not go back and what the players said stands,
+        // so running out of time, resigning and agreeing to a draw still end the game.
+        if self
+            .result
+            .is_none_or(|result| result.is_decided_by_board())
+        {
+            self.result = check::game_result(&self.history);
+
+            if let Some(clock) = self.clock.as_mut().filter(|clock| clock.running.is_some()) {
+                clock.resume(self.position.turn);
             }
         }
 
-        return possible_moves;
+        self.show_ply(self.history.len());
+        self.save_game();
     }
 
-    /// If the king is in check, only returns moves that are legal in that position.
-    pub fn only_check_preventing_moves(
-        &mut self,
-        ctx: &Context,
-        possible_moves: Vec<(usize, usize)>,
-        y_sq: usize,
-        x_sq: usize,
-    ) -> Vec<(usize, usize)> {
-        let mut possible_moves = possible_moves.clone();
-
-        if self.turn == Player::White {
-            if check(self.board, Player::White, self.en_passant, self.castling) == true {
-                let all_pieces = Piece::get_all_player_pieces(Player::White, self.board);
-
-                for p in all_pieces {
-                    for mv in Piece::get_possible_moves(
-                        p.0,
-                        p.1,
-                        self.board,
-                        self.en_passant,
-                        self.castling,
-                    ) {
-                        let mut temp_game: MainGame = self.clone();
-
-                        temp_game.make_move(
-                            ctx,
-                            self.selected_piece,
-                            self.selected_piece_pos,
-                            possible_moves.clone(),
-                            y_sq,
-                            x_sq,
-                        );
-
-                        if check(
-                            temp_game.board,
-                            Player::White,
-                            temp_game.en_passant,
-                            temp_game.castling,
-                        ) {
-                            possible_moves.retain(|x| x != &mv);
-                        }
-                    }
+    /// Setting the difference in piece value in the title, seemed like a nice touch.
+    fn update_title(&self, ctx: &mut Context) {
+        let mut difference: i8 = 0;
+
+        let black_missing_pieces = Piece::get_missing_pieces(Player::Black, self.position.board);
+        let white_missing_pieces = Piece::get_missing_pieces(Player::White, self.position.board);
+
+        for piece in black_missing_pieces {
+            difference += Piece::get_value(piece) as i8;
+        }
+
+        for piece in white_missing_pieces {
+            difference -= Piece::get_value(piece) as i8;
+        }
+
+        // Captured pieces come back in the variants with pockets, so the pieces on the board and in the pockets are counted instead.
+        if self.position.variant.rules().has_pockets() {
+            difference = 0;
+
+            for piece in self.position.board.board.iter().flatten() {
+                if Piece::get_player(*piece) == Player::White {
+                    difference += Piece::get_value(*piece) as i8;
+                } else {
+                    difference -= Piece::get_value(*piece) as i8;
                 }
             }
-        } else if self.turn == Player::Black {
-            if check(self.board, Player::Black, self.en_passant, self.castling) == true {
-                let all_pieces = Piece::get_all_player_pieces(Player::Black, self.board.clone());
-
-                for p in all_pieces {
-                    for mv in Piece::get_possible_moves(
-                        p.0,
-                        p.1,
-                        self.board,
-                        self.en_passant,
-                        self.castling,
-                    ) {
-                        let mut temp_game: MainGame = self.clone();
-
-                        temp_game.make_move(
-                            ctx,
-                            self.selected_piece,
-                            self.selected_piece_pos,
-                            possible_moves.clone(),
-                            y_sq,
-                            x_sq,
-                        );
-
-                        if check(
-                            temp_game.board,
-                            Player::Black,
-                            temp_game.en_passant,
-                            temp_game.castling,
-                        ) {
-                            possible_moves.retain(|x| x != &mv);
-                        }
-                    }
-                }
+
+            for (kind, (white, black)) in PieceKind::POCKET.iter().zip(
+                self.position
+                    .pocket(Player::White)
+                    .into_iter()
+                    .zip(self.position.pocket(Player::Black)),
+            ) {
+                let value = Piece::get_value(kind.to_char(Player::White)) as i8;
+
+                difference += value * (white as i8 - black as i8);
             }
         }
 
-        return possible_moves;
+        let mut title = if difference > 0 {
+            format!("Chess! (+{})", difference)
+        } else {
+            format!("Chess! ({})", difference)
+        };
+
+        if self.position.variant != VariantKind::Standard {
+            title.push_str(&format!(" - {}", self.position.variant));
+        }
+
+        if self.position.variant.rules().counts_checks() {
+            let [white, black] = self.position.checks;
+
+            title.push_str(&format!(" (checks {}-{})", white, black));
+        }
+
+        if let Some(evaluation) = &self.engine_evaluation {
+            title.push_str(&format!(" - {}", evaluation));
+        }
+
+        if let Some(result) = self.result {
+            title.push_str(&format!(" - {}", result));
+        } else if let Some(player) = self.draw_offer.offered_by {
+            title.push_str(&format!(" - {} offers a draw", player));
+        } else if let Some(rule) = check::claimable_draw(&self.history) {
+            title.push_str(&format!(" - Draw can be claimed by {}", rule));
+        }
+
+        graphics::set_window_title(ctx, &title);
+    }
+
+    /// Checks if the pieces can be moved with the mouse, which is not the case when looking at an earlier position or when it is the engine's turn.
+    pub fn can_move(&self) -> bool {
+        self.is_viewing_current()
+            && !self.is_game_over()
+            && !self.is_engine_player(self.position.turn)
+            && !self.is_ai_player(self.position.turn)
+    }
+
+    /// Prints the current position as FEN and copies it to the clipboard.
+    pub fn copy_fen(&self) {
+        let fen = self.position.to_fen();
+
+        println!("\n{}", fen);
+
+        if let Err(e) = Clipboard::new().and_then(|mut c| c.set_text(fen)) {
+            println!("Could not copy the position to the clipboard: {}", e);
+        }
+    }
+
+    /// Gets the piece a pawn gets upgraded to when it reaches the end of the board.
+    /// Hold the Q, R, N or B keys to get the different pieces.
+    fn get_promotion(&self, ctx: &Context, position: (usize, usize)) -> Option<PieceKind> {
+        if !Position::is_promotion(self.selected_piece, position) {
+            return None;
+        }
+
+        let pressed_keys = keyboard::pressed_keys(ctx);
+
+        // They are sorted roughly in order of usefulness, in my opinion.
+        if pressed_keys.contains(
+            &match_key(self.config.queen_upgrade_button.to_string()).unwrap_or(KeyCode::Q),
+        ) {
+            Some(PieceKind::Queen)
+        } else if pressed_keys
+            .contains(&match_key(self.config.rook_upgrade_button.to_string()).unwrap_or(KeyCode::R))
+        {
+            Some(PieceKind::Rook)
+        } else if pressed_keys.contains(
+            &match_key(self.config.knight_upgrade_button.to_string()).unwrap_or(KeyCode::N),
+        ) {
+            Some(PieceKind::Knight)
+        } else if pressed_keys.contains(
+            &match_key(self.config.bishop_upgrade_button.to_string()).unwrap_or(KeyCode::B),
+        ) {
+            Some(PieceKind::Bishop)
+        } else if self
+            .position
+            .variant
+            .rules()
+            .promotion_pieces()
+            .contains(&PieceKind::King)
+            && pressed_keys.contains(
+                &match_key(self.config.king_upgrade_button.to_string()).unwrap_or(KeyCode::K),
+            )
+        {
+            Some(PieceKind::King)
+        } else {
+            Some(PieceKind::Queen)
+        }
     }
 }
 
 impl event::EventHandler<ggez::GameError> for MainGame {
-    fn update(&mut self, _ctx: &mut Context) -> GameResult {
+    fn update(&mut self, ctx: &mut Context) -> GameResult {
+        // Nothing happens until the player chose whether to resume the unfinished game.
+        if self.saved_game.is_some() {
+            return Ok(());
+        }
+
+        self.update_clock(ctx);
+        self.update_explosion(ctx);
+        self.update_engine(ctx);
+        self.update_ai(ctx);
+
         Ok(())
     }
 
@@ -515,31 +1838,40 @@ impl event::EventHandler<ggez::GameError> for MainGame {
             self.config.draw_color.into(),
         )?;
 
-        // Checking for checks.
-        let current_king_in_check: bool =
-            check(self.board, self.turn, self.en_passant, self.castling);
-
-        // Checking if the game is over.
-        let tie = check::is_tie(
-            self.board,
-            self.en_passant,
-            self.castling,
-            self.clone(),
-            ctx,
-        );
+        // The position shown might be an earlier one, when stepping through a game.
+        let position = self.history.positions[self.viewed_ply];
 
-        let checkmate = check::player_checkmate(
-            self.board,
-            self.en_passant,
-            self.castling,
-            self.clone(),
-            ctx,
-        );
+        // Checking for checks.
+        let current_king_in_check: bool = position.in_check();
+
+        // Checking if the game is over, the draws by repetition, move count or agreement only show up in the current position.
+        let tie = check::is_tie(&position)
+            || (self.is_viewing_current()
+                && self.result.is_some_and(|result| result.winner().is_none()));
+
+        let checkmate = check::player_checkmate(&position);
+
+        // Only getting moves that are currently legal moves.
+        let possible_moves = if self.dropping {
+            PieceKind::from_char(self.selected_piece)
+                .map(|kind| position.legal_drops(kind))
+                .unwrap_or_default()
+        } else if self.selected_piece != ' ' {
+            position.legal_moves_from(self.selected_piece_pos)
+        } else {
+            Vec::new()
+        };
 
-        for y in self.board.board {
-            offset.0 = 0.0;
+        for y in position.board.board {
             coordinates.1 = 0;
             for x in y {
+                // The square is drawn where the player at the bottom of the board sees it.
+                let screen = self.flip_square(coordinates);
+                offset = (
+                    screen.1 as f32 * (self.config.window_size_horizontal / 8.0),
+                    screen.0 as f32 * (self.config.window_size_vertical / 8.0),
+                );
+
                 // First we draw on the dark squares.
                 if !last_square_dark {
                     graphics::draw(
@@ -550,14 +1882,14 @@ impl event::EventHandler<ggez::GameError> for MainGame {
                 }
 
                 // Then we draw the last move made, by default in dark and light green.
-                if self.last_move != None {
-                    if coordinates == self.last_move.unwrap().0 {
+                if let Some(last_move) = self.last_move {
+                    if coordinates == last_move.0 {
                         graphics::draw(
                             ctx,
                             &last_move_start_square,
                             graphics::DrawParam::default().dest(Vec2::new(offset.0, offset.1)),
                         )?;
-                    } else if coordinates == self.last_move.unwrap().1 {
+                    } else if coordinates == last_move.1 {
                         graphics::draw(
                             ctx,
                             &last_move_end_square,
@@ -568,7 +1900,7 @@ impl event::EventHandler<ggez::GameError> for MainGame {
 
                 // Then we highlight the currently selected piece and the possible moves.
                 if self.selected_piece != ' ' {
-                    if coordinates == self.selected_piece_pos {
+                    if !self.dropping && coordinates == self.selected_piece_pos {
                         graphics::draw(
                             ctx,
                             &selected_piece_square,
@@ -576,25 +1908,13 @@ impl event::EventHandler<ggez::GameError> for MainGame {
                         )?;
                     }
 
-                    let mut possible_moves = Piece::get_possible_moves(
-                        self.selected_piece,
-                        self.selected_piece_pos,
-                        self.board,
-                        self.en_passant,
-                        self.castling,
-                    );
-
-                    // Removing the illegal moves from the possible moves.
-                    possible_moves =
-                        self.get_legal_moves(ctx, possible_moves, coordinates.0, coordinates.1);
-
-                    // Highlighting some squares.
-                    if possible_moves.contains(&coordinates) {
+                    // Highlighting some squares, when a capture is forced in Antichess only the captures are left.
+                    if let Some(mv) = possible_moves
+                        .iter()
+                        .find(|mv| mv.to == coordinates || mv.destination() == coordinates)
+                    {
                         // If a piece is capturable we highlight it in magenta by default.
-                        if Piece::is_opposite_player(
-                            self.selected_piece,
-                            self.board.board[coordinates.0][coordinates.1],
-                        ) {
+                        if position.is_capture(*mv) {
                             graphics::draw(
                                 ctx,
                                 &capturable_square,
@@ -612,48 +1932,42 @@ impl event::EventHandler<ggez::GameError> for MainGame {
                 }
 
                 // Highlighting the kings square in red by default if the king is in check.
-                if self.turn == Player::White && x == 'K' {
-                    if current_king_in_check == true {
-                        graphics::draw(
-                            ctx,
-                            &checked_square,
-                            graphics::DrawParam::default().dest(Vec2::new(offset.0, offset.1)),
-                        )?;
-                    }
-                } else if self.turn == Player::Black && x == 'k' {
-                    if current_king_in_check == true {
+                if position.turn == Player::White && x == 'K' {
+                    if current_king_in_check {
                         graphics::draw(
                             ctx,
                             &checked_square,
                             graphics::DrawParam::default().dest(Vec2::new(offset.0, offset.1)),
                         )?;
                     }
+                } else if position.turn == Player::Black && x == 'k' && current_king_in_check {
+                    graphics::draw(
+                        ctx,
+                        &checked_square,
+                        graphics::DrawParam::default().dest(Vec2::new(offset.0, offset.1)),
+                    )?;
                 }
 
                 // If one player is in checkmate we color every piece of theirs in dark red by default.
-                if checkmate != None {
-                    if Piece::get_player(x) == checkmate.unwrap() {
-                        graphics::draw(
-                            ctx,
-                            &checkmate_square,
-                            graphics::DrawParam::default().dest(Vec2::new(offset.0, offset.1)),
-                        )?;
-                    }
+                if checkmate.is_some() && Piece::get_player(x) == checkmate.unwrap() {
+                    graphics::draw(
+                        ctx,
+                        &checkmate_square,
+                        graphics::DrawParam::default().dest(Vec2::new(offset.0, offset.1)),
+                    )?;
                 }
 
                 // If the game is tied we highlight every piece in a dark blue color by default.
-                if tie == true {
-                    if Piece::get_player(x) != Player::None {
-                        graphics::draw(
-                            ctx,
-                            &tied_square,
-                            graphics::DrawParam::default().dest(Vec2::new(offset.0, offset.1)),
-                        )?;
-                    }
+                if tie && Piece::get_player(x) != Player::None {
+                    graphics::draw(
+                        ctx,
+                        &tied_square,
+                        graphics::DrawParam::default().dest(Vec2::new(offset.0, offset.1)),
+                    )?;
                 }
 
                 // We draw on the coordinates of the chess board.
-                if coordinates.0 == 7 {
+                if screen.0 == 7 {
                     let displayed_text = match coordinates.1 {
                         0 => "A",
                         1 => "B",
@@ -682,7 +1996,7 @@ impl event::EventHandler<ggez::GameError> for MainGame {
                     )?;
                 }
 
-                if coordinates.1 == 7 {
+                if screen.1 == 7 {
                     let text = graphics::Text::new((
                         (format!("{}", (8 - coordinates.0))),
                         font,
@@ -719,14 +2033,14 @@ impl event::EventHandler<ggez::GameError> for MainGame {
 
                 // ^= just reverses a boolean.
                 last_square_dark ^= true;
-                offset.0 += self.config.window_size_horizontal / 8.0;
                 coordinates.1 += 1;
             }
             last_square_dark ^= true;
-            offset.1 += self.config.window_size_vertical / 8.0;
             coordinates.0 += 1;
         }
 
+        self.draw_explosion(ctx)?;
+
         // We draw the selected piece under the cursor, to create a drag and drop effect.
         if self.selected_piece != ' ' {
             let image = Image::new(ctx, Piece::get_image(self.selected_piece))?;
@@ -745,16 +2059,23 @@ impl event::EventHandler<ggez::GameError> for MainGame {
             )?;
         }
 
+        self.draw_history_panel(ctx, font)?;
+        self.draw_pockets(ctx, font)?;
+        self.draw_clocks(ctx, font)?;
+        self.draw_draw_offer(ctx, font)?;
+        self.draw_overlay(ctx, font)?;
+
         // First we push the pixels to the drawn ones,
         // there's probably a way to do this that yields better performance.
-        if self.draw_mode == true {
-            self.drawn_pixels
-                .push((input::mouse::position(ctx).x, input::mouse::position(ctx).y));
+        let mouse = input::mouse::position(ctx);
+
+        if self.draw_mode && mouse.x < self.config.window_size_horizontal {
+            self.drawn_pixels.push(self.flip_pixel((mouse.x, mouse.y)));
         }
 
         // Then we draw on the pixels in the Vec.
         if !self.drawn_pixels.is_empty() {
-            for p in &self.drawn_pixels {
+            for p in self.drawn_pixels.iter().map(|p| self.flip_pixel(*p)) {
                 graphics::draw(
                     ctx,
                     &draw_pixel,
@@ -781,51 +2102,42 @@ impl event::EventHandler<ggez::GameError> for MainGame {
     ) {
         match button {
             MouseButton::Left => {
-                let x_sq = (x / (self.config.window_size_horizontal / 8.0)).floor() as usize;
-                let y_sq = (y / (self.config.window_size_vertical / 8.0)).floor() as usize;
-
-                if self.selected_piece == ' ' {
-                    if Piece::get_player(self.board.board[y_sq][x_sq]) == self.turn {
-                        self.selected_piece = self.board.board[y_sq][x_sq];
-                        self.selected_piece_pos = (y_sq, x_sq);
-                    }
+                if self.click_overlay(x, y) {
+                    self.update_title(ctx);
+                    return;
                 }
 
-                // Checking if the game is over.
-                let checkmate = check::player_checkmate(
-                    self.board,
-                    self.en_passant,
-                    self.castling,
-                    self.clone(),
-                    ctx,
-                );
-
-                let tie = check::is_tie(
-                    self.board,
-                    self.en_passant,
-                    self.castling,
-                    self.clone(),
-                    ctx,
-                );
+                // Pieces in the pocket of the player on turn can be picked up in Crazyhouse.
+                if let Some((player, kind)) = self.pocket_slot_at(x, y) {
+                    if self.selected_piece == ' '
+                        && self.can_move()
+                        && player == self.position.turn
+                        && self.position.pocket(player)[kind as usize] > 0
+                    {
+                        self.selected_piece = kind.to_char(player);
+                        self.selected_piece_pos = (9, 9);
+                        self.dropping = true;
+                    }
 
-                // Restarting the game.
-                if checkmate != None || tie == true {
-                    let key_pressed = keyboard::pressed_keys(ctx);
+                    return;
+                }
 
-                    if !key_pressed.is_empty() {
-                        self.board = Chessboard::new();
-                        self.en_passant = None;
-                        self.castling = (true, true, true, true);
-                        self.selected_piece = ' ';
-                        self.selected_piece_pos = (9, 9);
-                        self.turn = Player::White;
-                        self.turn_count = 1;
-                        self.last_move = None;
-                        self.draw_mode = false;
-                        self.drawn_pixels = Vec::new();
+                // Clicks next to the board go to the move history panel.
+                let (y_sq, x_sq) = match self.square_at(x, y) {
+                    Some(square) => square,
+                    None => {
+                        self.click_history_panel(x, y);
+                        return;
                     }
+                };
 
-                    println!("\nPress any key and click the board to restart.\n")
+                if self.selected_piece == ' '
+                    && self.can_move()
+                    && Piece::get_player(self.position.piece_at((y_sq, x_sq))) == self.position.turn
+                {
+                    self.selected_piece = self.position.piece_at((y_sq, x_sq));
+                    self.selected_piece_pos = (y_sq, x_sq);
+                    self.dropping = false;
                 }
             }
             MouseButton::Right => {
@@ -844,92 +2156,30 @@ impl event::EventHandler<ggez::GameError> for MainGame {
     ) {
         match button {
             MouseButton::Left => {
-                let x_sq = (x / (self.config.window_size_horizontal / 8.0)).floor() as usize;
-                let y_sq = (y / (self.config.window_size_vertical / 8.0)).floor() as usize;
-
-                let mut possible_moves = Piece::get_possible_moves(
-                    self.selected_piece,
-                    self.selected_piece_pos,
-                    self.board,
-                    self.en_passant,
-                    self.castling,
-                );
-
-                // Only getting moves that are currently legal moves.
-                possible_moves = self.get_legal_moves(ctx, possible_moves, y_sq, x_sq);
+                // Letting go of a piece next to the board puts it back.
+                let (y_sq, x_sq) = match self.square_at(x, y) {
+                    Some(square) => square,
+                    None => {
+                        self.selected_piece = ' ';
+                        self.dropping = false;
+                        return;
+                    }
+                };
 
                 if self.selected_piece == ' ' {
-                    if Piece::get_player(self.board.board[y_sq][x_sq]) == self.turn {
-                        self.selected_piece = self.board.board[y_sq][x_sq];
+                    if self.can_move()
+                        && Piece::get_player(self.position.piece_at((y_sq, x_sq)))
+                            == self.position.turn
+                    {
+                        self.selected_piece = self.position.piece_at((y_sq, x_sq));
                         self.selected_piece_pos = (y_sq, x_sq);
+                        self.dropping = false;
                     }
                 } else {
-                    if possible_moves.contains(&(y_sq, x_sq)) {
-                        // Printing the move to the console.
-                        print_move(
-                            ctx,
-                            self.clone(),
-                            self.selected_piece,
-                            self.selected_piece_pos,
-                            possible_moves.clone(),
-                            (y_sq, x_sq),
-                            self.en_passant,
-                            self.castling,
-                            self.turn_count,
-                        );
-
-                        // The turn counter only gets incremented every other turn,
-                        // like in real chess, where one move is only a half-turn.
-                        if self.turn == Player::Black {
-                            self.turn_count += 1;
-                        }
-
-                        self.last_move = Some(((self.selected_piece_pos), (y_sq, x_sq)));
-
-                        // Playing some sound effects. The default ones are from lichess.
-                        if self.board.board[y_sq][x_sq] == ' ' {
-                            let mut move_sound =
-                                audio::Source::new(ctx, "/sounds/move.mp3").unwrap();
-                            move_sound.set_volume(self.config.volume);
-                            let _ = move_sound.play_detached(ctx);
-                        } else {
-                            let mut capture_sound =
-                                audio::Source::new(ctx, "/sounds/capture.mp3").unwrap();
-                            capture_sound.set_volume(self.config.volume);
-                            let _ = capture_sound.play_detached(ctx);
-                        }
-                    }
-
-                    // And then actually making the move.
-                    self.make_move(
-                        ctx,
-                        self.selected_piece,
-                        self.selected_piece_pos,
-                        possible_moves,
-                        y_sq,
-                        x_sq,
-                    );
+                    self.make_move(ctx, y_sq, x_sq);
                 }
 
-                // Setting the difference in piece value in the title, seemed like a nice touch.
-                let mut difference: i8 = 0;
-
-                let black_missing_pieces = Piece::get_missing_pieces(Player::Black, self.board);
-                let white_missing_pieces = Piece::get_missing_pieces(Player::White, self.board);
-
-                for piece in black_missing_pieces {
-                    difference += Piece::get_value(piece) as i8;
-                }
-
-                for piece in white_missing_pieces {
-                    difference -= Piece::get_value(piece) as i8;
-                }
-
-                if difference > 0 {
-                    graphics::set_window_title(ctx, &format!("Chess! (+{})", difference));
-                } else {
-                    graphics::set_window_title(ctx, &format!("Chess! ({})", difference));
-                }
+                self.update_title(ctx);
             }
             MouseButton::Right => {
                 self.draw_mode = false;
@@ -938,9 +2188,18 @@ impl event::EventHandler<ggez::GameError> for MainGame {
         }
     }
 
+    fn mouse_wheel_event(&mut self, _ctx: &mut Context, _x: f32, y: f32) {
+        if y > 0.0 {
+            self.history_scroll = self.history_scroll.saturating_sub(1);
+        } else if y < 0.0 && self.history_scroll + self.history_visible_rows() < self.history_rows()
+        {
+            self.history_scroll += 1;
+        }
+    }
+
     fn key_down_event(
         &mut self,
-        _ctx: &mut Context,
+        ctx: &mut Context,
         keycode: KeyCode,
         _keymods: event::KeyMods,
         _repeat: bool,
@@ -948,15 +2207,90 @@ impl event::EventHandler<ggez::GameError> for MainGame {
         if keycode == match_key(self.config.draw_clear_button.to_string()).unwrap_or(KeyCode::Space)
         {
             self.drawn_pixels = Vec::new();
+        } else if keycode
+            == match_key(self.config.copy_fen_button.to_string()).unwrap_or(KeyCode::C)
+        {
+            self.copy_fen();
+        } else if keycode
+            == match_key(self.config.paste_fen_button.to_string()).unwrap_or(KeyCode::V)
+        {
+            match Clipboard::new().and_then(|mut c| c.get_text()) {
+                Ok(fen) => self.load_fen(&fen),
+                Err(e) => println!("\nCould not read the clipboard: {}", e),
+            }
+        } else if keycode
+            == match_key(self.config.load_fen_button.to_string()).unwrap_or(KeyCode::L)
+        {
+            match fs::read_to_string(&self.config.fen_file) {
+                Ok(fen) => self.load_fen(&fen),
+                Err(e) => println!("\nCould not read {}: {}", self.config.fen_file, e),
+            }
+        } else if keycode
+            == match_key(self.config.export_pgn_button.to_string()).unwrap_or(KeyCode::E)
+        {
+            self.export_pgn();
+        } else if keycode
+            == match_key(self.config.load_pgn_button.to_string()).unwrap_or(KeyCode::O)
+        {
+            self.load_pgn();
+        } else if keycode
+            == match_key(self.config.previous_move_button.to_string()).unwrap_or(KeyCode::Left)
+        {
+            self.show_ply(self.viewed_ply.saturating_sub(1));
+        } else if keycode
+            == match_key(self.config.next_move_button.to_string()).unwrap_or(KeyCode::Right)
+        {
+            self.show_ply(self.viewed_ply + 1);
+        } else if keycode
+            == match_key(self.config.previous_game_button.to_string()).unwrap_or(KeyCode::Up)
+        {
+            if self.loaded_game_index > 0 {
+                self.show_game(self.loaded_game_index - 1);
+            }
+        } else if keycode
+            == match_key(self.config.next_game_button.to_string()).unwrap_or(KeyCode::Down)
+        {
+            self.show_game(self.loaded_game_index + 1);
+        } else if keycode
+            == match_key(self.config.flip_board_button.to_string()).unwrap_or(KeyCode::F)
+        {
+            self.flipped ^= true;
+        } else if keycode == match_key(self.config.undo_button.to_string()).unwrap_or(KeyCode::Z) {
+            self.undo();
+        } else if keycode == match_key(self.config.redo_button.to_string()).unwrap_or(KeyCode::Y) {
+            self.redo();
+        } else if keycode
+            == match_key(self.config.claim_draw_button.to_string()).unwrap_or(KeyCode::D)
+        {
+            self.claim_draw();
+        } else if keycode == match_key(self.config.resign_button.to_string()).unwrap_or(KeyCode::G)
+        {
+            self.resign();
+        } else if keycode
+            == match_key(self.config.offer_draw_button.to_string()).unwrap_or(KeyCode::H)
+        {
+            self.offer_draw();
+        } else if keycode
+            == match_key(self.config.accept_draw_button.to_string()).unwrap_or(KeyCode::A)
+        {
+            self.accept_draw();
+        } else if keycode
+            == match_key(self.config.decline_draw_button.to_string()).unwrap_or(KeyCode::X)
+        {
+            self.decline_draw();
         }
+
+        self.update_title(ctx);
     }
 }
 
 pub fn run_game() -> GameResult {
     let config = load_config();
 
-    let window = ggez::conf::WindowMode::default()
-        .dimensions(config.window_size_horizontal, config.window_size_vertical);
+    let window = ggez::conf::WindowMode::default().dimensions(
+        config.window_size_horizontal + config.history_panel_width,
+        config.window_size_vertical,
+    );
 
     let window_setup = ggez::conf::WindowSetup::default()
         .title("Chess! (0)")
@@ -0,0 +1,108 @@
+use super::moves::Move;
+use super::notation::to_san;
+use super::position::Position;
+use super::zobrist;
+
+/// The moves played in a game, together with the positions they were played in.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct History {
+    /// The starting position, followed by the position after every move.
+    pub positions: Vec<Position>,
+    pub moves: Vec<Move>,
+    /// Every move in the standard chess notation, worked out once when the move is recorded.
+    pub notations: Vec<String>,
+    /// The Zobrist hash of every position, for finding repetitions.
+    pub keys: Vec<u64>,
+    /// Moves that were taken back together with the positions they led to, the last one is redone first.
+    pub undone: Vec<(Move, Position)>,
+}
+
+impl Default for History {
+    fn default() -> Self {
+        History::new(Position::new())
+    }
+}
+
+impl History {
+    pub fn new(start: Position) -> History {
+        History {
+            positions: vec![start],
+            moves: Vec::new(),
+            notations: Vec::new(),
+            keys: vec![zobrist::hash(&start)],
+            undone: Vec::new(),
+        }
+    }
+
+    /// The position the game started from.
+    pub fn start(&self) -> &Position {
+        &self.positions[0]
+    }
+
+    /// The position after the last move.
+    pub fn current(&self) -> &Position {
+        &self.positions[self.positions.len() - 1]
+    }
+
+    /// The number of half-moves played.
+    pub fn len(&self) -> usize {
+        self.moves.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.moves.is_empty()
+    }
+
+    /// Counts how often the current position came up in the game, including now.
+    /// Only the positions since the last capture or pawn move can be the same.
+    pub fn repetitions(&self) -> usize {
+        let key = self.keys[self.keys.len() - 1];
+
+        self.keys
+            .iter()
+            .rev()
+            .take(self.current().halfmove_clock + 1)
+            .filter(|k| **k == key)
+            .count()
+    }
+
+    /// Records a move and the position it leads to.
+    /// The moves taken back can only be redone until a different move is made.
+    pub fn push(&mut self, mv: Move, position: Position) {
+        if self.undone.last().is_some_and(|(undone, _)| *undone == mv) {
+            self.undone.pop();
+        } else {
+            self.undone.clear();
+        }
+
+        self.record(mv, position);
+    }
+
+    /// Takes back the last move, returning it. It can be redone later.
+    pub fn undo(&mut self) -> Option<Move> {
+        let mv = self.moves.pop()?;
+        let position = self.positions.pop().unwrap();
+
+        self.notations.pop();
+        self.keys.pop();
+        self.undone.push((mv, position));
+
+        Some(mv)
+    }
+
+    /// Makes the last move taken back again, returning it.
+    pub fn redo(&mut self) -> Option<Move> {
+        let (mv, position) = self.undone.pop()?;
+
+        self.record(mv, position);
+
+        Some(mv)
+    }
+
+    fn record(&mut self, mv: Move, position: Position) {
+        self.notations.push(to_san(self.current(), mv));
+        self.moves.push(mv);
+        self.keys.push(zobrist::hash(&position));
+        self.positions.push(position);
+    }
+}
@@ -1,7 +1,22 @@
+pub mod bitboard;
 pub mod board;
 pub mod check;
+pub mod clock;
 pub mod config;
+pub mod engine;
+pub mod fen;
+#[allow(clippy::module_inception)]
 pub mod game;
+pub mod history;
+pub mod moves;
 pub mod notation;
+pub mod perft;
+pub mod pgn;
 pub mod pieces;
 pub mod player;
+pub mod position;
+pub mod result;
+pub mod save;
+pub mod uci;
+pub mod variant;
+pub mod zobrist;
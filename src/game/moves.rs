@@ -0,0 +1,85 @@
+use super::pieces::PieceKind;
+
+/// What kind of special rule, if any, a move makes use of.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum MoveKind {
+    Normal,
+    /// The king moves onto one of its own rooks, and both of them land where they would in standard chess,
+    /// the king on the g or c column and the rook next to it on the inside.
+    /// Written like this, castling works the same for every starting position of Chess960.
+    Castle,
+    /// A pawn captures a pawn that just moved two squares past it.
+    EnPassant,
+    /// A pawn moves two squares from its starting row.
+    DoublePush,
+    /// A piece from the pocket gets put on an empty square, in Crazyhouse.
+    /// The move starts and ends on that square.
+    Drop(PieceKind),
+}
+
+/// A move of a piece from one square to another.
+/// Squares are (row, column) pairs, row 0 being the 8th rank, like on the Chessboard.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct Move {
+    pub from: (usize, usize),
+    pub to: (usize, usize),
+    /// The piece a pawn turns into when it reaches the end of the board.
+    pub promotion: Option<PieceKind>,
+    pub kind: MoveKind,
+}
+
+impl Move {
+    /// A move without any special rules.
+    pub fn new(from: (usize, usize), to: (usize, usize)) -> Move {
+        Move {
+            from,
+            to,
+            promotion: None,
+            kind: MoveKind::Normal,
+        }
+    }
+
+    /// A piece of the player on turn put from the pocket on the given square.
+    pub fn drop(piece: PieceKind, to: (usize, usize)) -> Move {
+        Move {
+            from: to,
+            to,
+            promotion: None,
+            kind: MoveKind::Drop(piece),
+        }
+    }
+
+    /// Gets the piece put on the board, if the move is a drop.
+    pub fn dropped_piece(&self) -> Option<PieceKind> {
+        match self.kind {
+            MoveKind::Drop(piece) => Some(piece),
+            _ => None,
+        }
+    }
+
+    /// The square the moving piece ends up on, which for castling is where the king lands and not the rook.
+    pub fn destination(&self) -> (usize, usize) {
+        if self.kind == MoveKind::Castle {
+            (self.to.0, self.castle_columns().0)
+        } else {
+            self.to
+        }
+    }
+
+    /// The columns the king and the rook land on when castling, depending on the side of the rook.
+    pub fn castle_columns(&self) -> (usize, usize) {
+        if self.to.1 > self.from.1 {
+            (6, 5)
+        } else {
+            (2, 3)
+        }
+    }
+
+    /// A move of the same kind, but promoting to the given piece.
+    pub fn with_promotion(self, promotion: PieceKind) -> Move {
+        Move {
+            promotion: Some(promotion),
+            ..self
+        }
+    }
+}
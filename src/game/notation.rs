@@ -1,167 +1,333 @@
+use std::error::Error;
+use std::fmt;
 use std::io::{self, Write};
 
-use ggez::event::KeyCode;
-use ggez::input::keyboard;
-use ggez::Context;
-
 use super::check;
-use super::config::match_key;
-use super::game::MainGame;
+use super::moves::{Move, MoveKind};
+use super::pieces::PieceKind;
 use super::player::Player;
+use super::position::Position;
 
-/// Prints the moves to the console in the standard chess notation.
-pub fn print_move(
-    ctx: &Context,
-    game: MainGame,
-    piece: char,
-    piece_pos: (usize, usize),
-    possible_moves: Vec<(usize, usize)>,
-    end_pos: (usize, usize),
-    en_passant: Option<usize>,
-    castling: (bool, bool, bool, bool),
-    turn_count: usize,
-) {
-    let end_column = match end_pos.1 {
-        0 => 'a',
-        1 => 'b',
-        2 => 'c',
-        3 => 'd',
-        4 => 'e',
-        5 => 'f',
-        6 => 'g',
-        7 => 'h',
-        _ => ' ',
-    };
+/// The reasons a move in the standard chess notation cannot be read.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum SanError {
+    /// The text is not a move in the standard chess notation.
+    Invalid,
+    /// No legal move in the position fits the notation.
+    Illegal,
+    /// More than one legal move fits the notation.
+    Ambiguous,
+}
+
+impl fmt::Display for SanError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SanError::Invalid => write!(f, "not a valid move"),
+            SanError::Illegal => write!(f, "illegal move"),
+            SanError::Ambiguous => write!(f, "ambiguous move"),
+        }
+    }
+}
+
+impl Error for SanError {}
+
+/// Gets the name of a square, like "e4".
+pub fn square_name(square: (usize, usize)) -> String {
+    format!(
+        "{}{}",
+        (b'a' + square.1 as u8) as char,
+        (b'8' - square.0 as u8) as char
+    )
+}
+
+/// Parses the name of a square, like "e4", into its (row, column) on the board.
+pub fn parse_square(name: &str) -> Option<(usize, usize)> {
+    let mut chars = name.chars();
 
-    let end_row = match end_pos.0 {
-        0 => '8',
-        1 => '7',
-        2 => '6',
-        3 => '5',
-        4 => '4',
-        5 => '3',
-        6 => '2',
-        7 => '1',
-        _ => ' ',
+    let column = chars.next()?;
+    let row = chars.next()?;
+
+    if chars.next().is_some() || !('a'..='h').contains(&column) || !('1'..='8').contains(&row) {
+        return None;
+    }
+
+    Some(((b'8' - row as u8) as usize, (column as u8 - b'a') as usize))
+}
+
+/// Reads a move in the standard chess notation, like "Nbd7", "exd6", "O-O+" or "N@f3" for a drop.
+/// Check and annotation symbols at the end are ignored, and so is an "e.p." after en passant captures.
+pub fn from_san(position: &Position, san: &str) -> Result<Move, SanError> {
+    let san = san.trim_end_matches(['+', '#', '!', '?']);
+    let san = san.strip_suffix("e.p.").unwrap_or(san).trim_end();
+
+    let legal_moves = position.legal_moves();
+
+    // Castling is written the same for both players, some write it with zeros.
+    let castle_column = match san {
+        "O-O" | "0-0" => Some(6),
+        "O-O-O" | "0-0-0" => Some(2),
+        _ => None,
     };
 
-    let piece_print = match piece.to_ascii_lowercase() {
-        // This is a zero-width space character, bascially invisible.
-        // Except in the Windows Terminal, it seems.
-        'p' => '\u{200b}',
-        'b' => 'B',
-        'n' => 'N',
-        'r' => 'R',
-        'q' => 'Q',
-        'k' => 'K',
-        _ => ' ',
+    if let Some(column) = castle_column {
+        return legal_moves
+            .into_iter()
+            .find(|mv| mv.kind == MoveKind::Castle && mv.destination().1 == column)
+            .ok_or(SanError::Illegal);
+    }
+
+    // Drops in Crazyhouse name the piece and the square, pawns can be left out.
+    if let Some((piece, square)) = san.split_once('@') {
+        let piece = match piece {
+            "" | "P" => PieceKind::Pawn,
+            "N" => PieceKind::Knight,
+            "B" => PieceKind::Bishop,
+            "R" => PieceKind::Rook,
+            "Q" => PieceKind::Queen,
+            _ => return Err(SanError::Invalid),
+        };
+        let to = parse_square(square).ok_or(SanError::Invalid)?;
+
+        return legal_moves
+            .into_iter()
+            .find(|mv| mv.dropped_piece() == Some(piece) && mv.to == to)
+            .ok_or(SanError::Illegal);
+    }
+
+    let mut chars: Vec<char> = san.chars().collect();
+
+    // The piece letter, no letter means a pawn move.
+    let piece = match chars.first() {
+        Some('N') => PieceKind::Knight,
+        Some('B') => PieceKind::Bishop,
+        Some('R') => PieceKind::Rook,
+        Some('Q') => PieceKind::Queen,
+        Some('K') => PieceKind::King,
+        Some('a'..='h') => PieceKind::Pawn,
+        _ => return Err(SanError::Invalid),
     };
 
-    let mut check_symbol = '\u{200b}';
-    let mut capture_symbol = "\u{200b}".to_string();
-    let mut promotion_symbol = "\u{200b}";
-
-    let mut temp_game = game.clone();
-
-    temp_game.make_move(ctx, piece, piece_pos, possible_moves, end_pos.0, end_pos.1);
-
-    if check::check(temp_game.board, Player::White, en_passant, castling) == true
-        || check::check(temp_game.board, Player::Black, en_passant, castling) == true
-    {
-        check_symbol = '+';
-    }
-
-    if check::player_checkmate(
-        temp_game.board,
-        en_passant,
-        castling,
-        temp_game.clone(),
-        ctx,
-    ) != None
-    {
-        check_symbol = '#';
-    }
-
-    if check::is_tie(
-        temp_game.board,
-        en_passant,
-        castling,
-        temp_game.clone(),
-        ctx,
-    ) {
-        check_symbol = '=';
-    }
-
-    if game.board.board[end_pos.0][end_pos.1] != ' ' {
-        if piece.to_ascii_lowercase() == 'p' {
-            let piece_column = match piece_pos.1 {
-                0 => 'a',
-                1 => 'b',
-                2 => 'c',
-                3 => 'd',
-                4 => 'e',
-                5 => 'f',
-                6 => 'g',
-                7 => 'h',
-                _ => ' ',
-            };
-
-            capture_symbol = format!("{}x", piece_column);
-        } else {
-            capture_symbol = "x".to_string();
+    if piece != PieceKind::Pawn {
+        chars.remove(0);
+    }
+
+    // The promotion piece, with or without the equals sign.
+    let mut promotion = None;
+
+    if let Some(&last) = chars.last() {
+        // Pawns can turn into kings in Antichess.
+        if "NBRQK".contains(last) {
+            promotion = PieceKind::from_char(last);
+            chars.pop();
+
+            if chars.last() == Some(&'=') {
+                chars.pop();
+            }
         }
     }
 
-    if (piece == 'P' && end_pos.0 == 0) || (piece == 'p' && end_pos.0 == 7) {
-        let pressed_keys = keyboard::pressed_keys(ctx);
+    if chars.len() < 2 {
+        return Err(SanError::Invalid);
+    }
+
+    let destination: String = chars[chars.len() - 2..].iter().collect();
+    let to = parse_square(&destination).ok_or(SanError::Invalid)?;
 
-        if pressed_keys.contains(
-            &match_key(game.config.queen_upgrade_button.to_string()).unwrap_or(KeyCode::Q),
-        ) {
-            promotion_symbol = "=Q";
-        } else if pressed_keys
-            .contains(&match_key(game.config.rook_upgrade_button.to_string()).unwrap_or(KeyCode::R))
+    // Whatever is left is the disambiguation of the starting square, maybe followed by a capture.
+    let mut from_column = None;
+    let mut from_row = None;
+
+    for c in &chars[..chars.len() - 2] {
+        match c {
+            'a'..='h' if from_column.is_none() && from_row.is_none() => {
+                from_column = Some((*c as u8 - b'a') as usize)
+            }
+            '1'..='8' if from_row.is_none() => from_row = Some((b'8' - *c as u8) as usize),
+            'x' | ':' => (),
+            _ => return Err(SanError::Invalid),
+        }
+    }
+
+    let mut candidates = legal_moves.into_iter().filter(|mv| {
+        PieceKind::from_char(position.piece_at(mv.from)) == Some(piece)
+            && mv.kind != MoveKind::Castle
+            && mv.to == to
+            && mv.promotion == promotion
+            && from_column.is_none_or(|c| mv.from.1 == c)
+            && from_row.is_none_or(|r| mv.from.0 == r)
+    });
+
+    match (candidates.next(), candidates.next()) {
+        (Some(mv), None) => Ok(mv),
+        (Some(_), Some(_)) => Err(SanError::Ambiguous),
+        (None, _) => Err(SanError::Illegal),
+    }
+}
+
+/// Gets a move in the standard chess notation, like "Nf3", "Nbd7", "exd6", "O-O+" or "P@e4".
+pub fn to_san(position: &Position, mv: Move) -> String {
+    let piece = PieceKind::from_char(position.piece_at(mv.from));
+
+    let check_symbol = match position.apply_move(mv) {
+        // Winning by the rules of the variant gets marked like a checkmate.
+        Ok(new_position)
+            if check::player_checkmate(&new_position).is_some()
+                || check::variant_winner(&new_position).is_some() =>
         {
-            promotion_symbol = "=R";
-        } else if pressed_keys.contains(
-            &match_key(game.config.knight_upgrade_button.to_string()).unwrap_or(KeyCode::N),
-        ) {
-            promotion_symbol = "=N";
-        } else if pressed_keys.contains(
-            &match_key(game.config.bishop_upgrade_button.to_string()).unwrap_or(KeyCode::B),
-        ) {
-            promotion_symbol = "=B";
-        } else {
-            promotion_symbol = "=Q";
+            "#"
         }
+        Ok(new_position) if new_position.in_check() => "+",
+        _ => "",
+    };
+
+    // Castling has its own notation, the king and rook squares are not written down.
+    if mv.kind == MoveKind::Castle {
+        let castle = if mv.destination().1 == 6 {
+            "O-O"
+        } else {
+            "O-O-O"
+        };
+
+        return format!("{}{}", castle, check_symbol);
     }
 
-    // First we check for castling, since that has a special notation that is not really compatible.
-    if piece == 'K' && castling.0 == true && end_pos == (7, 6) {
-        print!("{}. O-O{}", turn_count, check_symbol);
-    } else if piece == 'k' && castling.2 == true && end_pos == (0, 6) {
-        print!(" O-O{}\n", check_symbol);
-    } else if piece == 'K' && castling.1 == true && end_pos == (7, 2) {
-        print!("{}. O-O-O{}", turn_count, check_symbol);
-    } else if piece == 'k' && castling.3 == true && end_pos == (0, 2) {
-        print!(" O-O-O{}\n", check_symbol);
-    } else if piece.is_uppercase() {
-        print!(
-            "{}. {}{}{}{}{}{}",
-            turn_count,
-            piece_print,
-            capture_symbol,
-            end_column,
-            end_row,
-            promotion_symbol,
+    if let Some(dropped) = mv.dropped_piece() {
+        return format!(
+            "{}@{}{}",
+            dropped.to_char(Player::White),
+            square_name(mv.to),
             check_symbol
         );
+    }
+
+    let capture = position.is_capture(mv);
+    let from = square_name(mv.from);
+
+    let mut san = String::new();
+
+    if piece == Some(PieceKind::Pawn) {
+        // Pawn captures always name the column the pawn came from.
+        if capture {
+            san.push_str(&from[..1]);
+        }
     } else {
-        print!(
-            " {}{}{}{}{}{}\n",
-            piece_print, capture_symbol, end_column, end_row, promotion_symbol, check_symbol
-        );
+        if let Some(piece) = piece {
+            san.push(piece.to_char(Player::White));
+        }
+
+        // Other pieces of the same kind that could also move to the same square.
+        let others: Vec<Move> = position
+            .legal_moves()
+            .into_iter()
+            .filter(|other| {
+                other.to == mv.to
+                    && other.from != mv.from
+                    && other.kind != MoveKind::Castle
+                    && PieceKind::from_char(position.piece_at(other.from)) == piece
+            })
+            .collect();
+
+        // The column is preferred, then the row, then the whole square if neither is enough.
+        let disambiguation = if others.is_empty() {
+            ""
+        } else if others.iter().all(|other| other.from.1 != mv.from.1) {
+            &from[..1]
+        } else if others.iter().all(|other| other.from.0 != mv.from.0) {
+            &from[1..]
+        } else {
+            &from
+        };
+
+        san.push_str(disambiguation);
+    }
+
+    if capture {
+        san.push('x');
+    }
+
+    san.push_str(&square_name(mv.to));
+
+    if let Some(promotion) = mv.promotion {
+        san.push('=');
+        san.push(promotion.to_char(Player::White));
+    }
+
+    san.push_str(check_symbol);
+
+    san
+}
+
+/// Prints the moves to the console in the standard chess notation.
+pub fn print_move(position: &Position, mv: Move) {
+    let notation = to_san(position, mv);
+
+    if position.turn == Player::White {
+        print!("{}. {}", position.turn_count, notation);
+    } else {
+        println!(" {}", notation);
     }
 
     io::stdout().flush().unwrap();
 }
+
+/// Gets a move in the notation of the UCI protocol, like "e2e4", "e7e8q" or "P@e4" for a drop.
+/// Castling is written as the king moving to the square it lands on, like "e1g1".
+pub fn to_uci(mv: Move) -> String {
+    write_uci(mv, mv.destination())
+}
+
+/// Gets a move in the notation of the UCI protocol for Chess960, where castling is written
+/// as the king moving onto its rook, like "e1h1". The king does not always move in Chess960.
+pub fn to_uci_chess960(mv: Move) -> String {
+    write_uci(mv, mv.to)
+}
+
+fn write_uci(mv: Move, to: (usize, usize)) -> String {
+    if let Some(dropped) = mv.dropped_piece() {
+        return format!("{}@{}", dropped.to_char(Player::White), square_name(to));
+    }
+
+    match mv.promotion {
+        Some(promotion) => format!(
+            "{}{}{}",
+            square_name(mv.from),
+            square_name(to),
+            promotion.to_char(Player::Black)
+        ),
+        None => format!("{}{}", square_name(mv.from), square_name(to)),
+    }
+}
+
+/// Reads a move in the notation of the UCI protocol, if it is legal in the position.
+/// Castling can be written both ways, as the king moving onto its rook or to the square it lands on.
+pub fn from_uci(position: &Position, uci: &str) -> Option<Move> {
+    if !uci.is_ascii() || uci.len() < 4 || uci.len() > 5 {
+        return None;
+    }
+
+    // Drops are written like "P@e4", some write the piece in lowercase.
+    if let Some((piece, square)) = uci.split_once('@') {
+        let mut chars = piece.chars();
+        let piece = PieceKind::from_char(chars.next()?)?;
+        let to = parse_square(square)?;
+
+        if chars.next().is_some() {
+            return None;
+        }
+
+        return position
+            .legal_moves()
+            .into_iter()
+            .find(|mv| mv.dropped_piece() == Some(piece) && mv.to == to);
+    }
+
+    let from = parse_square(&uci[0..2])?;
+    let to = parse_square(&uci[2..4])?;
+
+    let promotion = match uci[4..].chars().next() {
+        Some(c) => Some(PieceKind::from_char(c)?),
+        None => None,
+    };
+
+    position.find_move(from, to, promotion)
+}
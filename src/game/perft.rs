@@ -0,0 +1,36 @@
+use super::moves::Move;
+use super::position::Position;
+
+/// Counts the positions reached after playing every sequence of legal moves of the given length.
+/// The counts are well known for many positions, so this finds bugs in the move generation.
+pub fn perft(position: &Position, depth: usize) -> u64 {
+    if depth == 0 {
+        return 1;
+    }
+
+    let moves = position.legal_moves();
+
+    // The last half-move does not need to be played, only counted.
+    if depth == 1 {
+        return moves.len() as u64;
+    }
+
+    moves
+        .into_iter()
+        .map(|mv| perft(&position.play(mv), depth - 1))
+        .sum()
+}
+
+/// Counts the positions like `perft`, but separately for every legal move.
+/// Comparing this with another program shows which move the counts go wrong after.
+pub fn divide(position: &Position, depth: usize) -> Vec<(Move, u64)> {
+    if depth == 0 {
+        return Vec::new();
+    }
+
+    position
+        .legal_moves()
+        .into_iter()
+        .map(|mv| (mv, perft(&position.play(mv), depth - 1)))
+        .collect()
+}
@@ -0,0 +1,775 @@
+use std::error::Error;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::Path;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use super::clock::TimeControl;
+use super::history::History;
+use super::moves::Move;
+use super::notation::from_san;
+use super::player::Player;
+use super::position::Position;
+use super::result::GameResult;
+use super::variant::VariantKind;
+
+/// Lines of movetext get wrapped before they get longer than this.
+const LINE_WIDTH: usize = 80;
+
+/// Splits a point in time into (year, month, day, hour, minute, second) in UTC.
+fn date_time(time: SystemTime) -> (i64, u32, u32, u32, u32, u32) {
+    let seconds = time
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs() as i64)
+        .unwrap_or(0);
+
+    let days = seconds.div_euclid(86400);
+    let day_seconds = seconds.rem_euclid(86400) as u32;
+
+    // Converting the days since 1970 to a calendar date,
+    // see http://howardhinnant.github.io/date_algorithms.html#civil_from_days
+    let z = days + 719468;
+    let era = z.div_euclid(146097);
+    let day_of_era = z.rem_euclid(146097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let mp = (5 * day_of_year + 2) / 153;
+    let day = (day_of_year - (153 * mp + 2) / 5 + 1) as u32;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+    let year = year_of_era + era * 400 + if month <= 2 { 1 } else { 0 };
+
+    (
+        year,
+        month,
+        day,
+        day_seconds / 3600,
+        day_seconds % 3600 / 60,
+        day_seconds % 60,
+    )
+}
+
+/// Formats a point in time as a PGN date, like "2022.05.31".
+pub fn pgn_date(time: SystemTime) -> String {
+    let (year, month, day, ..) = date_time(time);
+
+    format!("{:04}.{:02}.{:02}", year, month, day)
+}
+
+/// Gets a file name for a game started at that time, like "2022-05-31_142501.pgn".
+pub fn pgn_file_name(time: SystemTime) -> String {
+    let (year, month, day, hour, minute, second) = date_time(time);
+
+    format!(
+        "{:04}-{:02}-{:02}_{:02}{:02}{:02}.pgn",
+        year, month, day, hour, minute, second
+    )
+}
+
+/// The Seven Tag Roster every PGN game starts with, in the order the standard wants.
+pub fn seven_tag_roster(
+    white: &str,
+    black: &str,
+    date: &str,
+    result: &str,
+) -> Vec<(String, String)> {
+    [
+        ("Event", "Casual Game"),
+        ("Site", "?"),
+        ("Date", date),
+        ("Round", "-"),
+        ("White", white),
+        ("Black", black),
+        ("Result", result),
+    ]
+    .iter()
+    .map(|(name, value)| (name.to_string(), value.to_string()))
+    .collect()
+}
+
+/// Gets the tags of a game played here, the Seven Tag Roster with the result or "*" if the game is still going,
+/// then the time control and the reason the game ended, if there are any.
+pub fn game_tags(
+    white: &str,
+    black: &str,
+    started: SystemTime,
+    result: Option<GameResult>,
+    control: Option<&TimeControl>,
+) -> Vec<(String, String)> {
+    let mut tags = seven_tag_roster(
+        white,
+        black,
+        &pgn_date(started),
+        result.map_or("*", |result| result.tag()),
+    );
+
+    if let Some(control) = control {
+        tags.push(("TimeControl".to_string(), control.to_string()));
+    }
+
+    if let Some(result) = result {
+        tags.push(("Termination".to_string(), result.termination().to_string()));
+    }
+
+    tags
+}
+
+/// Formats the time left on a clock after a move as a PGN comment, like "{[%clk 1:29:58]}".
+pub fn clock_comment(time: Duration) -> String {
+    let seconds = time.as_secs();
+
+    format!(
+        "{{[%clk {}:{:02}:{:02}]}}",
+        seconds / 3600,
+        seconds / 60 % 60,
+        seconds % 60
+    )
+}
+
+/// Writes a game as PGN, with the result taken from the Result tag.
+/// Games not starting from the standard position get the SetUp and FEN tags added,
+/// and the Variant tag if the game is not played with the standard rules or castling works like in Chess960.
+/// The clock times are the time left after every move, for as many moves as there are times.
+pub fn write_pgn(tags: &[(String, String)], history: &History, clock_times: &[Duration]) -> String {
+    let mut pgn = String::new();
+
+    let mut tags = tags.to_vec();
+
+    let start = history.start();
+
+    let variant = if start.variant != VariantKind::Standard {
+        Some(start.variant.to_string())
+    } else {
+        start.is_chess960().then(|| "Chess960".to_string())
+    };
+
+    if let Some(variant) = variant {
+        if !tags.iter().any(|(name, _)| name == "Variant") {
+            tags.push(("Variant".to_string(), variant));
+        }
+    }
+
+    let standard_start = start
+        .variant
+        .rules()
+        .start_position(Position::new().with_variant(start.variant));
+
+    if *start != standard_start && !tags.iter().any(|(name, _)| name == "FEN") {
+        tags.push(("SetUp".to_string(), "1".to_string()));
+        tags.push(("FEN".to_string(), history.start().to_fen()));
+    }
+
+    for (name, value) in &tags {
+        let value = value.replace('\\', "\\\\").replace('"', "\\\"");
+
+        pgn.push_str(&format!("[{} \"{}\"]\n", name, value));
+    }
+
+    pgn.push('\n');
+
+    let result = tags
+        .iter()
+        .find(|(name, _)| name == "Result")
+        .map(|(_, value)| value.as_str())
+        .unwrap_or("*");
+
+    let mut tokens = Vec::new();
+
+    for (i, (notation, position)) in history.notations.iter().zip(&history.positions).enumerate() {
+        if position.turn == Player::White {
+            tokens.push(format!("{}.", position.turn_count));
+        } else if i == 0 || clock_times.len() >= i {
+            // A Black move gets its own number after a comment, like the export format wants.
+            tokens.push(format!("{}...", position.turn_count));
+        }
+
+        tokens.push(notation.clone());
+
+        if let Some(time) = clock_times.get(i) {
+            tokens.push(clock_comment(*time));
+        }
+    }
+
+    tokens.push(result.to_string());
+
+    pgn.push_str(&wrap_tokens(&tokens));
+    pgn.push_str("\n\n");
+
+    pgn
+}
+
+/// Joins the tokens with spaces, starting a new line before one gets too long.
+fn wrap_tokens(tokens: &[String]) -> String {
+    let mut text = String::new();
+    let mut line_length = 0;
+
+    for token in tokens {
+        if line_length > 0 && line_length + 1 + token.len() > LINE_WIDTH {
+            text.push('\n');
+            line_length = 0;
+        } else if line_length > 0 {
+            text.push(' ');
+            line_length += 1;
+        }
+
+        text.push_str(token);
+        line_length += token.len();
+    }
+
+    text
+}
+
+/// Saves a PGN to a file, creating the folder if needed.
+pub fn save_pgn(path: &Path, pgn: &str) -> io::Result<()> {
+    if let Some(folder) = path.parent() {
+        fs::create_dir_all(folder)?;
+    }
+
+    fs::write(path, pgn)
+}
+
+/// A move read from a PGN, with everything written around it.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct PgnMove {
+    pub mv: Move,
+    /// The move as it was written in the PGN.
+    pub san: String,
+    /// Numeric annotation glyphs, "!" is written as $1, "?" as $2 and so on.
+    pub nags: Vec<u8>,
+    pub comments: Vec<String>,
+    /// Alternatives to this move, each starting from the position before it.
+    pub variations: Vec<Vec<PgnMove>>,
+}
+
+/// A game read from a PGN.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct PgnGame {
+    pub tags: Vec<(String, String)>,
+    /// The position from the FEN tag, or the standard starting position,
+    /// played with the rules of the Variant tag.
+    pub start: Position,
+    /// Comments written before the first move.
+    pub comments: Vec<String>,
+    /// The main line of the game.
+    pub moves: Vec<PgnMove>,
+    pub result: String,
+}
+
+impl PgnGame {
+    /// Gets the value of a tag, like "White" or "Event".
+    pub fn tag(&self, name: &str) -> Option<&str> {
+        self.tags
+            .iter()
+            .find(|(n, _)| n == name)
+            .map(|(_, value)| value.as_str())
+    }
+
+    /// Plays through the main line of the game.
+    pub fn history(&self) -> History {
+        let mut history = History::new(self.start);
+
+        for pgn_move in &self.moves {
+            let position = history.current().apply_move(pgn_move.mv).unwrap();
+
+            history.push(pgn_move.mv, position);
+        }
+
+        history
+    }
+}
+
+/// Describes where and why a PGN could not be read.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct PgnError {
+    /// The number of the game in the file, starting at 1.
+    pub game: usize,
+    pub line: usize,
+    /// The half-move the error happened at, counted from the start of the game.
+    pub ply: Option<usize>,
+    pub token: String,
+    pub message: String,
+}
+
+impl fmt::Display for PgnError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "game {}, line {}", self.game, self.line)?;
+
+        if let Some(ply) = self.ply {
+            write!(f, ", ply {}", ply)?;
+        }
+
+        write!(f, ": {} at '{}'", self.message, self.token)
+    }
+}
+
+impl Error for PgnError {}
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+enum TokenKind {
+    Tag(String, String),
+    Comment(String),
+    VariationStart,
+    VariationEnd,
+    Nag(u8),
+    MoveNumber,
+    Result,
+    Move,
+}
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+struct Token {
+    kind: TokenKind,
+    text: String,
+    line: usize,
+}
+
+/// The annotation symbols that are short for numeric annotation glyphs.
+fn symbol_nag(symbol: &str) -> Option<u8> {
+    match symbol {
+        "!" => Some(1),
+        "?" => Some(2),
+        "!!" => Some(3),
+        "??" => Some(4),
+        "!?" => Some(5),
+        "?!" => Some(6),
+        _ => None,
+    }
+}
+
+/// Checks if the next token starts a new game, the same way the games get split when they are read.
+/// A game ends after its result, or when the tags of the next game come right after its moves.
+fn starts_game(previous: Option<&Token>, next_is_tag: bool) -> bool {
+    match previous.map(|token| &token.kind) {
+        Some(TokenKind::Result) => true,
+        Some(TokenKind::Tag(..)) | None => false,
+        Some(_) => next_is_tag,
+    }
+}
+
+/// Gets the number of the game the next token belongs to, starting at 1.
+fn game_number(tokens: &[Token], next_is_tag: bool) -> usize {
+    let started = tokens
+        .windows(2)
+        .filter(|pair| starts_game(Some(&pair[0]), matches!(pair[1].kind, TokenKind::Tag(..))))
+        .count();
+
+    1 + started + starts_game(tokens.last(), next_is_tag) as usize
+}
+
+/// Splits a PGN into its tokens.
+fn tokenize(text: &str) -> Result<Vec<Token>, PgnError> {
+    let mut tokens = Vec::new();
+    let chars: Vec<char> = text.chars().collect();
+    let mut i = 0;
+    let mut line = 1;
+
+    let error = |game: usize, line: usize, token: &str, message: &str| PgnError {
+        game,
+        line,
+        ply: None,
+        token: token.to_string(),
+        message: message.to_string(),
+    };
+
+    while i < chars.len() {
+        let c = chars[i];
+        let start_line = line;
+
+        match c {
+            '\n' => {
+                line += 1;
+                i += 1;
+            }
+            _ if c.is_whitespace() => i += 1,
+            // Lines starting with a percent sign are escaped, and get ignored.
+            '%' if i == 0 || chars[i - 1] == '\n' => {
+                while i < chars.len() && chars[i] != '\n' {
+                    i += 1;
+                }
+            }
+            ';' => {
+                let start = i + 1;
+
+                while i < chars.len() && chars[i] != '\n' {
+                    i += 1;
+                }
+
+                let comment: String = chars[start..i].iter().collect();
+
+                tokens.push(Token {
+                    kind: TokenKind::Comment(comment.trim().to_string()),
+                    text: format!(";{}", comment),
+                    line: start_line,
+                });
+            }
+            '{' => {
+                let start = i + 1;
+
+                while i < chars.len() && chars[i] != '}' {
+                    if chars[i] == '\n' {
+                        line += 1;
+                    }
+                    i += 1;
+                }
+
+                if i == chars.len() {
+                    return Err(error(
+                        game_number(&tokens, false),
+                        start_line,
+                        "{",
+                        "unterminated comment",
+                    ));
+                }
+
+                let comment: String = chars[start..i].iter().collect();
+                i += 1;
+
+                tokens.push(Token {
+                    kind: TokenKind::Comment(
+                        comment.split_whitespace().collect::<Vec<_>>().join(" "),
+                    ),
+                    text: format!("{{{}}}", comment),
+                    line: start_line,
+                });
+            }
+            '[' => {
+                let start = i;
+                let mut in_string = false;
+
+                while i < chars.len() && (in_string || chars[i] != ']') {
+                    match chars[i] {
+                        '\\' if in_string => i += 1,
+                        '"' => in_string = !in_string,
+                        '\n' => line += 1,
+                        _ => (),
+                    }
+                    i += 1;
+                }
+
+                let tag: String = chars[start..i.min(chars.len())].iter().collect();
+
+                if i >= chars.len() {
+                    return Err(error(
+                        game_number(&tokens, true),
+                        start_line,
+                        &tag,
+                        "unterminated tag",
+                    ));
+                }
+
+                i += 1;
+
+                let inner = tag[1..].trim();
+                let (name, value) = match inner.split_once(char::is_whitespace) {
+                    Some((name, value)) => (name, value.trim()),
+                    None => {
+                        return Err(error(
+                            game_number(&tokens, true),
+                            start_line,
+                            &tag,
+                            "tag without a value",
+                        ))
+                    }
+                };
+
+                if value.len() < 2 || !value.starts_with('"') || !value.ends_with('"') {
+                    return Err(error(
+                        game_number(&tokens, true),
+                        start_line,
+                        &tag,
+                        "tag value needs quotes",
+                    ));
+                }
+
+                let value = value[1..value.len() - 1]
+                    .replace("\\\"", "\"")
+                    .replace("\\\\", "\\");
+
+                tokens.push(Token {
+                    kind: TokenKind::Tag(name.to_string(), value),
+                    text: format!("{}]", tag),
+                    line: start_line,
+                });
+            }
+            '(' | ')' => {
+                tokens.push(Token {
+                    kind: if c == '(' {
+                        TokenKind::VariationStart
+                    } else {
+                        TokenKind::VariationEnd
+                    },
+                    text: c.to_string(),
+                    line,
+                });
+                i += 1;
+            }
+            _ => {
+                let start = i;
+
+                while i < chars.len() && !chars[i].is_whitespace() && !"{}()[];".contains(chars[i])
+                {
+                    i += 1;
+                }
+
+                let word: String = chars[start..i].iter().collect();
+                let mut word_tokens = tokenize_word(&word, line)
+                    .map_err(|message| error(game_number(&tokens, false), line, &word, &message))?;
+
+                tokens.append(&mut word_tokens);
+            }
+        }
+    }
+
+    Ok(tokens)
+}
+
+/// Splits a word of movetext, like "12.Nf3!?", into its tokens.
+fn tokenize_word(word: &str, line: usize) -> Result<Vec<Token>, String> {
+    let token = |kind: TokenKind, text: &str| Token {
+        kind,
+        text: text.to_string(),
+        line,
+    };
+
+    if ["1-0", "0-1", "1/2-1/2", "*"].contains(&word) {
+        return Ok(vec![token(TokenKind::Result, word)]);
+    }
+
+    if let Some(nag) = word.strip_prefix('$') {
+        return match nag.parse() {
+            Ok(n) => Ok(vec![token(TokenKind::Nag(n), word)]),
+            Err(_) => Err("invalid annotation glyph".to_string()),
+        };
+    }
+
+    if let Some(nag) = symbol_nag(word) {
+        return Ok(vec![token(TokenKind::Nag(nag), word)]);
+    }
+
+    let mut tokens = Vec::new();
+    let mut rest = word;
+
+    // Move numbers can be written right in front of the move, like "1.e4" or "3...Nf6".
+    // Castling written with zeros, like "0-0", starts with a digit but is not one.
+    let digits = if rest.starts_with("0-0") {
+        0
+    } else {
+        rest.len() - rest.trim_start_matches(|c: char| c.is_ascii_digit()).len()
+    };
+
+    if digits > 0 {
+        let after = &rest[digits..];
+
+        if !after.starts_with('.') && !after.is_empty() {
+            return Err("invalid move number".to_string());
+        }
+
+        let dots = after.len() - after.trim_start_matches('.').len();
+
+        tokens.push(token(TokenKind::MoveNumber, &rest[..digits + dots]));
+        rest = &after[dots..];
+    } else if rest.starts_with('.') {
+        // Some write the dots apart from the number, like "3. ... Nf6".
+        return Ok(vec![token(TokenKind::MoveNumber, rest)]);
+    }
+
+    if rest.is_empty() {
+        return Ok(tokens);
+    }
+
+    // Annotation symbols can be written right after the move, like "Nf3!?".
+    let san = rest.trim_end_matches(['!', '?']);
+    let symbol = &rest[san.len()..];
+
+    tokens.push(token(TokenKind::Move, san));
+
+    if !symbol.is_empty() {
+        match symbol_nag(symbol) {
+            Some(nag) => tokens.push(token(TokenKind::Nag(nag), symbol)),
+            None => return Err("invalid annotation symbol".to_string()),
+        }
+    }
+
+    Ok(tokens)
+}
+
+/// Gets the number of the half-move that gets played in the position, starting at 1.
+fn ply(position: &Position) -> usize {
+    (position.turn_count - 1) * 2 + if position.turn == Player::Black { 2 } else { 1 }
+}
+
+/// Reads a line of moves, until the end of the variation or the game.
+fn parse_line(
+    tokens: &[Token],
+    index: &mut usize,
+    start: Position,
+    game: usize,
+    nested: bool,
+    comments: &mut Vec<String>,
+) -> Result<Vec<PgnMove>, PgnError> {
+    let mut moves: Vec<PgnMove> = Vec::new();
+    let mut position = start;
+    let mut previous = start;
+
+    while let Some(token) = tokens.get(*index) {
+        let error = |message: &str, ply: Option<usize>| PgnError {
+            game,
+            line: token.line,
+            ply,
+            token: token.text.clone(),
+            message: message.to_string(),
+        };
+
+        match &token.kind {
+            TokenKind::Move => {
+                let mv = from_san(&position, &token.text)
+                    .map_err(|e| error(&e.to_string(), Some(ply(&position))))?;
+
+                moves.push(PgnMove {
+                    mv,
+                    san: token.text.clone(),
+                    nags: Vec::new(),
+                    comments: Vec::new(),
+                    variations: Vec::new(),
+                });
+
+                previous = position;
+                position = position.apply_move(mv).unwrap();
+            }
+            TokenKind::Nag(nag) => match moves.last_mut() {
+                Some(last) => last.nags.push(*nag),
+                None => return Err(error("annotation before the first move", None)),
+            },
+            TokenKind::Comment(comment) => match moves.last_mut() {
+                Some(last) => last.comments.push(comment.clone()),
+                None => comments.push(comment.clone()),
+            },
+            TokenKind::MoveNumber => (),
+            TokenKind::VariationStart => {
+                if moves.is_empty() {
+                    return Err(error(
+                        "variation before the first move",
+                        Some(ply(&position)),
+                    ));
+                }
+
+                *index += 1;
+
+                // Comments in front of the first move of a variation get attached to that move.
+                let mut variation_comments = Vec::new();
+                let mut variation =
+                    parse_line(tokens, index, previous, game, true, &mut variation_comments)?;
+
+                if let Some(first) = variation.first_mut() {
+                    variation_comments.append(&mut first.comments);
+                    first.comments = variation_comments;
+                }
+
+                moves.last_mut().unwrap().variations.push(variation);
+            }
+            TokenKind::VariationEnd => {
+                if !nested {
+                    return Err(error("unexpected end of variation", Some(ply(&position))));
+                }
+
+                return Ok(moves);
+            }
+            TokenKind::Result | TokenKind::Tag(..) => {
+                if nested {
+                    return Err(error("unterminated variation", Some(ply(&position))));
+                }
+
+                return Ok(moves);
+            }
+        }
+
+        *index += 1;
+    }
+
+    if nested {
+        return Err(PgnError {
+            game,
+            line: tokens.last().map_or(1, |t| t.line),
+            ply: Some(ply(&position)),
+            token: String::new(),
+            message: "unterminated variation".to_string(),
+        });
+    }
+
+    Ok(moves)
+}
+
+/// Reads every game in a PGN.
+/// Comments, annotation glyphs and variations are kept, the main line is checked to be legal.
+pub fn parse_pgn(text: &str) -> Result<Vec<PgnGame>, PgnError> {
+    let tokens = tokenize(text)?;
+
+    let mut games = Vec::new();
+    let mut index = 0;
+
+    while index < tokens.len() {
+        let game = games.len() + 1;
+        let mut tags = Vec::new();
+
+        while let Some(Token {
+            kind: TokenKind::Tag(name, value),
+            ..
+        }) = tokens.get(index)
+        {
+            tags.push((name.clone(), value.clone()));
+            index += 1;
+        }
+
+        // Variants that are not known, like Chess960, are played with the standard rules.
+        let variant = tags
+            .iter()
+            .find(|(name, _)| name == "Variant")
+            .and_then(|(_, name)| VariantKind::parse(name));
+
+        let start = match tags.iter().find(|(name, _)| name == "FEN") {
+            Some((_, fen)) => Position::from_variant_fen(fen, variant.unwrap_or_default())
+                .map_err(|e| PgnError {
+                    game,
+                    line: tokens[index.saturating_sub(1)].line,
+                    ply: None,
+                    token: fen.clone(),
+                    message: e.to_string(),
+                })?,
+            None => variant.map_or(Position::new(), |variant| {
+                variant
+                    .rules()
+                    .start_position(Position::new().with_variant(variant))
+            }),
+        };
+
+        let mut comments = Vec::new();
+        let moves = parse_line(&tokens, &mut index, start, game, false, &mut comments)?;
+
+        // The result at the end of the movetext, the Result tag if it is missing.
+        let result = match tokens.get(index) {
+            Some(Token {
+                kind: TokenKind::Result,
+                text,
+                ..
+            }) => {
+                index += 1;
+                text.clone()
+            }
+            _ => tags
+                .iter()
+                .find(|(name, _)| name == "Result")
+                .map_or("*".to_string(), |(_, value)| value.clone()),
+        };
+
+        games.push(PgnGame {
+            tags,
+            start,
+            comments,
+            moves,
+            result,
+        });
+    }
+
+    Ok(games)
+}
@@ -1,6 +1,8 @@
 use std::fmt;
 
-#[derive(Debug, PartialEq, Clone, Copy)]
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash, Serialize, Deserialize)]
 pub enum Player {
     White,
     Black,
@@ -12,3 +14,14 @@ impl fmt::Display for Player {
         write!(f, "{:?}", &self)
     }
 }
+
+impl Player {
+    /// Gets the other player, None stays None.
+    pub fn opponent(&self) -> Player {
+        match self {
+            Player::White => Player::Black,
+            Player::Black => Player::White,
+            Player::None => Player::None,
+        }
+    }
+}
@@ -0,0 +1,401 @@
+use std::error::Error;
+use std::fmt;
+
+use super::bitboard::{self, Bitboard};
+use super::board::Chessboard;
+use super::moves::{Move, MoveKind};
+use super::pieces::{Piece, PieceKind};
+use super::player::Player;
+use super::variant::VariantKind;
+
+/// The reasons a move can be rejected by `Position::apply_move`.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum IllegalMove {
+    /// One of the squares is not on the board.
+    OutOfBounds,
+    /// There is no piece on the starting square.
+    NoPiece,
+    /// The piece on the starting square belongs to the player not on turn.
+    WrongTurn,
+    /// The piece cannot move to that square.
+    InvalidDestination,
+    /// The move would leave the own king in check.
+    KingInCheck,
+    /// A pawn reached the end of the board without a promotion piece.
+    MissingPromotion,
+    /// The promotion piece is not a queen, rook, bishop or knight, or the move is no promotion.
+    InvalidPromotion,
+    /// The move kind does not match the move, like a castle that is no castle.
+    WrongKind,
+    /// The move would be legal in standard chess, but not in the variant played.
+    VariantRule,
+    /// The piece is not in the pocket, or cannot be dropped on that square.
+    InvalidDrop,
+}
+
+impl fmt::Display for IllegalMove {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let reason = match self {
+            IllegalMove::OutOfBounds => "square is not on the board",
+            IllegalMove::NoPiece => "there is no piece on the starting square",
+            IllegalMove::WrongTurn => "it is not this player's turn",
+            IllegalMove::InvalidDestination => "the piece cannot move there",
+            IllegalMove::KingInCheck => "the move leaves the king in check",
+            IllegalMove::MissingPromotion => "a promotion piece is required",
+            IllegalMove::InvalidPromotion => "invalid promotion piece",
+            IllegalMove::WrongKind => "the move kind does not match the move",
+            IllegalMove::VariantRule => "the variant does not allow the move",
+            IllegalMove::InvalidDrop => "the piece cannot be dropped there",
+        };
+
+        write!(f, "Illegal move: {}", reason)
+    }
+}
+
+impl Error for IllegalMove {}
+
+/// The full state of a chess game at one point in time, independent of any window.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct Position {
+    pub board: Chessboard,
+    pub turn: Player,
+    /// The column of the rook every castling right castles with, or None once the right is lost.
+    /// White kingside, white queenside, black kingside, black queenside.
+    pub castling: [Option<usize>; 4],
+    /// The column of a pawn that just moved two squares.
+    pub en_passant: Option<usize>,
+    /// Half-moves since the last capture or pawn move.
+    pub halfmove_clock: usize,
+    /// The number of the current full move, starting at 1.
+    pub turn_count: usize,
+    /// The rules the game is played with.
+    pub variant: VariantKind,
+    /// The checks White and Black gave so far, which only count in Three-check.
+    pub checks: [u8; 2],
+    /// The pieces White and Black captured and can drop in Crazyhouse, counted in the order of `PieceKind::POCKET`.
+    pub pockets: [[u8; 5]; 2],
+    /// The squares of the pieces that were pawns before, which go back to being pawns when captured in Crazyhouse.
+    pub promoted: Bitboard,
+}
+
+impl Default for Position {
+    fn default() -> Self {
+        Position::new()
+    }
+}
+
+impl Position {
+    /// The standard starting position.
+    pub fn new() -> Position {
+        Position {
+            board: Chessboard::new(),
+            turn: Player::White,
+            castling: [Some(7), Some(0), Some(7), Some(0)],
+            en_passant: None,
+            halfmove_clock: 0,
+            turn_count: 1,
+            variant: VariantKind::Standard,
+            checks: [0; 2],
+            pockets: [[0; 5]; 2],
+            promoted: 0,
+        }
+    }
+
+    /// The same position, but played with the rules of the given variant.
+    /// The castling rights are dropped if the variant has no castling.
+    pub fn with_variant(self, variant: VariantKind) -> Position {
+        let castling = if variant.rules().castling() {
+            self.castling
+        } else {
+            [None; 4]
+        };
+
+        Position {
+            variant,
+            castling,
+            ..self
+        }
+    }
+
+    /// One of the 960 starting positions of Chess960, see `Chessboard::chess960`.
+    pub fn chess960(index: usize) -> Position {
+        let board = Chessboard::chess960(index);
+        let rooks: Vec<usize> = (0..8).filter(|c| board.board[7][*c] == 'R').collect();
+
+        Position {
+            board,
+            castling: [
+                Some(rooks[1]),
+                Some(rooks[0]),
+                Some(rooks[1]),
+                Some(rooks[0]),
+            ],
+            ..Position::new()
+        }
+    }
+
+    /// Checks if castling works differently than in standard chess here,
+    /// because a king or rook that can still castle is not on its usual square.
+    pub fn is_chess960(&self) -> bool {
+        self.castling.iter().enumerate().any(|(i, rook)| {
+            let row = if i < 2 { 7 } else { 0 };
+            let column = if i % 2 == 0 { 7 } else { 0 };
+
+            rook.is_some_and(|rook| {
+                rook != column || !self.board.board[row][4].eq_ignore_ascii_case(&'k')
+            })
+        })
+    }
+
+    /// The columns of the rooks the player can still castle with, kingside first.
+    pub fn castling_rooks(&self, player: Player) -> [Option<usize>; 2] {
+        let i = if player == Player::White { 0 } else { 2 };
+
+        [self.castling[i], self.castling[i + 1]]
+    }
+
+    /// Gets how many of every piece the player has in the pocket, in the order of `PieceKind::POCKET`.
+    pub fn pocket(&self, player: Player) -> [u8; 5] {
+        self.pockets[usize::from(player == Player::Black)]
+    }
+
+    /// Returns the piece on a square, or ' ' if the square is empty.
+    pub fn piece_at(&self, square: (usize, usize)) -> char {
+        self.board.board[square.0][square.1]
+    }
+
+    /// Returns true if the player on turn is in check, by the rules of the variant.
+    pub fn in_check(&self) -> bool {
+        self.variant.rules().in_check(self)
+    }
+
+    /// Returns true if a move by this piece to that square promotes a pawn.
+    pub fn is_promotion(piece: char, to: (usize, usize)) -> bool {
+        (piece == 'P' && to.0 == 0) || (piece == 'p' && to.0 == 7)
+    }
+
+    /// Gets the moves the player on turn could make with the piece on the given square,
+    /// not looking at the own king.
+    fn possible_moves_from(&self, from: (usize, usize)) -> Vec<Move> {
+        let piece = self.piece_at(from);
+
+        if Piece::get_player(piece) != self.turn {
+            return Vec::new();
+        }
+
+        let mut moves = bitboard::pseudo_legal_moves(self, |_| true);
+
+        moves.retain(|mv| mv.from == from);
+
+        moves
+    }
+
+    /// Gets the legal moves of the piece on the given square.
+    pub fn legal_moves_from(&self, from: (usize, usize)) -> Vec<Move> {
+        let mut moves = self.legal_moves();
+
+        moves.retain(|mv| mv.from == from && mv.dropped_piece().is_none());
+
+        moves
+    }
+
+    /// Gets the squares the player on turn can drop the piece on.
+    pub fn legal_drops(&self, piece: PieceKind) -> Vec<Move> {
+        let mut moves = self.legal_moves();
+
+        moves.retain(|mv| mv.dropped_piece() == Some(piece));
+
+        moves
+    }
+
+    /// Gets every legal move of the player on turn, by the rules of the variant.
+    pub fn legal_moves(&self) -> Vec<Move> {
+        let rules = self.variant.rules();
+        let mut moves = rules.legal_moves(self);
+
+        rules.filter_moves(self, &mut moves);
+
+        moves
+    }
+
+    /// Looks up the legal move between two squares, filling in the kind of the move.
+    /// Castling is moving the king onto the rook, but moving it to the square it lands on works too,
+    /// as long as the king has no normal move there.
+    pub fn find_move(
+        &self,
+        from: (usize, usize),
+        to: (usize, usize),
+        promotion: Option<PieceKind>,
+    ) -> Option<Move> {
+        if from.0 > 7 || from.1 > 7 {
+            return None;
+        }
+
+        let moves = self.legal_moves_from(from);
+
+        moves
+            .iter()
+            .find(|mv| mv.to == to && mv.promotion == promotion)
+            .or_else(|| {
+                moves
+                    .iter()
+                    .find(|mv| mv.kind == MoveKind::Castle && mv.destination() == to)
+            })
+            .copied()
+    }
+
+    /// Returns true if the move takes a piece of the other player.
+    pub fn is_capture(&self, mv: Move) -> bool {
+        match mv.kind {
+            MoveKind::Castle => false,
+            MoveKind::EnPassant => true,
+            _ => self.piece_at(mv.to) != ' ',
+        }
+    }
+
+    /// Checks a move against the rules, without making it.
+    pub fn validate_move(&self, mv: Move) -> Result<(), IllegalMove> {
+        if mv.from.0 > 7 || mv.from.1 > 7 || mv.to.0 > 7 || mv.to.1 > 7 {
+            return Err(IllegalMove::OutOfBounds);
+        }
+
+        if mv.dropped_piece().is_some() {
+            return if self.legal_moves().contains(&mv) {
+                Ok(())
+            } else {
+                Err(IllegalMove::InvalidDrop)
+            };
+        }
+
+        let piece = self.piece_at(mv.from);
+
+        if piece == ' ' {
+            return Err(IllegalMove::NoPiece);
+        }
+
+        if Piece::get_player(piece) != self.turn {
+            return Err(IllegalMove::WrongTurn);
+        }
+
+        let mut candidates = self.possible_moves_from(mv.from);
+
+        candidates.retain(|m| m.to == mv.to);
+
+        if candidates.is_empty() {
+            return Err(IllegalMove::InvalidDestination);
+        }
+
+        if Position::is_promotion(piece, mv.to) {
+            match mv.promotion {
+                Some(promotion)
+                    if !self.variant.rules().promotion_pieces().contains(&promotion) =>
+                {
+                    return Err(IllegalMove::InvalidPromotion)
+                }
+                Some(_) => (),
+                None => return Err(IllegalMove::MissingPromotion),
+            }
+        } else if mv.promotion.is_some() {
+            return Err(IllegalMove::InvalidPromotion);
+        }
+
+        // The promotion piece was checked above, the kind of the move still has to match.
+        if !candidates.iter().any(|candidate| candidate.kind == mv.kind) {
+            return Err(IllegalMove::WrongKind);
+        }
+
+        // The move is possible for the piece, so if it is not legal the king is the problem,
+        // unless standard chess would allow it.
+        if !self.legal_moves().contains(&mv) {
+            return Err(if bitboard::legal_moves(self).contains(&mv) {
+                IllegalMove::VariantRule
+            } else {
+                IllegalMove::KingInCheck
+            });
+        }
+
+        Ok(())
+    }
+
+    /// Returns true if the move is legal in this position.
+    pub fn is_legal(&self, mv: Move) -> bool {
+        self.validate_move(mv).is_ok()
+    }
+
+    /// Makes a move and returns the resulting position, or the reason the move is not allowed.
+    pub fn apply_move(&self, mv: Move) -> Result<Position, IllegalMove> {
+        self.validate_move(mv)?;
+
+        Ok(self.play(mv))
+    }
+
+    /// Moves a piece without checking if the move is legal.
+    /// Takes care of the "special moves" such as castling, en passant, promotion and drops.
+    /// Only meant for moves that came out of `legal_moves`, which is what the engine does.
+    pub(crate) fn play(&self, mv: Move) -> Position {
+        let mut next = *self;
+        let piece = match mv.dropped_piece() {
+            Some(dropped) => dropped.to_char(self.turn),
+            None => self.piece_at(mv.from),
+        };
+        let row = mv.from.0;
+
+        if mv.kind == MoveKind::Castle {
+            // Both pieces get lifted first, in Chess960 they can land on each other's squares.
+            let (king_column, rook_column) = mv.castle_columns();
+            let rook = self.piece_at(mv.to);
+
+            next.board.board[row][mv.from.1] = ' ';
+            next.board.board[row][mv.to.1] = ' ';
+            next.board.board[row][king_column] = piece;
+            next.board.board[row][rook_column] = rook;
+        } else {
+            // En passant captures the pawn next to the starting square.
+            if mv.kind == MoveKind::EnPassant {
+                next.board.board[row][mv.to.1] = ' ';
+            }
+
+            next.board.board[mv.from.0][mv.from.1] = ' ';
+            next.board.board[mv.to.0][mv.to.1] = piece;
+        }
+
+        if let Some(promotion) = mv.promotion {
+            next.board.board[mv.to.0][mv.to.1] = promotion.to_char(self.turn);
+        }
+
+        // Enabling en passant for the next move.
+        next.en_passant = None;
+
+        if mv.kind == MoveKind::DoublePush {
+            next.en_passant = Some(mv.from.1);
+        }
+
+        // Disabling castling, when the king or a rook moves or a rook gets captured.
+        for (i, rook) in self.castling.iter().enumerate() {
+            let (row, king) = if i < 2 { (7, 'K') } else { (0, 'k') };
+
+            if piece == king || rook.is_some_and(|column| [mv.from, mv.to].contains(&(row, column)))
+            {
+                next.castling[i] = None;
+            }
+        }
+
+        if piece.eq_ignore_ascii_case(&'p') || self.is_capture(mv) {
+            next.halfmove_clock = 0;
+        } else {
+            next.halfmove_clock += 1;
+        }
+
+        // The turn counter only gets incremented every other turn,
+        // like in real chess, where one move is only a half-turn.
+        if self.turn == Player::Black {
+            next.turn_count += 1;
+        }
+
+        next.turn = self.turn.opponent();
+
+        self.variant.rules().after_move(self, mv, &mut next);
+
+        next
+    }
+}
@@ -0,0 +1,150 @@
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+use super::check::DrawRule;
+use super::engine;
+use super::player::Player;
+use super::position::Position;
+use super::variant::VariantKind;
+
+/// How a game ended.
+/// Not to be confused with `ggez::GameResult`, which is the result of a function in ggez.
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Serialize, Deserialize)]
+pub enum GameResult {
+    Checkmate {
+        winner: Player,
+    },
+    /// The other player ran out of time, while the winner could still have checkmated.
+    Timeout {
+        winner: Player,
+    },
+    Resignation {
+        winner: Player,
+    },
+    /// The winner won by a rule of the variant, like bringing the king to the center.
+    Variant {
+        winner: Player,
+        variant: VariantKind,
+    },
+    /// Stalemate, repetition, the move rules, insufficient material or an agreement.
+    Draw(DrawRule),
+}
+
+impl fmt::Display for GameResult {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            GameResult::Checkmate { winner } => write!(f, "{} wins by checkmate", winner),
+            GameResult::Timeout { winner } => write!(f, "{} wins on time", winner),
+            GameResult::Resignation { winner } => write!(f, "{} wins by resignation", winner),
+            GameResult::Variant { winner, variant } => {
+                write!(f, "{} wins by {}", winner, variant.rules().win_reason())
+            }
+            GameResult::Draw(rule) => write!(f, "Draw by {}", rule),
+        }
+    }
+}
+
+impl GameResult {
+    /// Gets the player who won, or None for a draw.
+    pub fn winner(&self) -> Option<Player> {
+        match self {
+            GameResult::Checkmate { winner }
+            | GameResult::Timeout { winner }
+            | GameResult::Resignation { winner }
+            | GameResult::Variant { winner, .. } => Some(*winner),
+            GameResult::Draw(_) => None,
+        }
+    }
+
+    /// Gets the result for the PGN Result tag.
+    pub fn tag(&self) -> &'static str {
+        match self.winner() {
+            Some(Player::White) => "1-0",
+            Some(_) => "0-1",
+            None => "1/2-1/2",
+        }
+    }
+
+    /// Gets the reason the game ended for the PGN Termination tag.
+    pub fn termination(&self) -> &'static str {
+        match self {
+            GameResult::Timeout { .. }
+            | GameResult::Draw(DrawRule::TimeoutVsInsufficientMaterial) => "time forfeit",
+            _ => "normal",
+        }
+    }
+
+    /// Returns true if the position on the board ended the game, not the players or the clock.
+    /// Taking back moves takes back such a result too.
+    pub fn is_decided_by_board(&self) -> bool {
+        !matches!(
+            self,
+            GameResult::Timeout { .. }
+                | GameResult::Resignation { .. }
+                | GameResult::Draw(DrawRule::TimeoutVsInsufficientMaterial | DrawRule::Agreement)
+        )
+    }
+}
+
+/// A draw offered by one player, which stands until the other player answers it.
+#[derive(Debug, Default, PartialEq, Eq, Clone, Copy)]
+pub struct DrawOffer {
+    /// The player who offered the draw, while the offer stands.
+    pub offered_by: Option<Player>,
+}
+
+impl DrawOffer {
+    /// Offers a draw for the player.
+    /// Returns false if there already is an offer, the player's own or one of the other player to accept instead.
+    pub fn offer(&mut self, player: Player) -> bool {
+        if self.offered_by.is_some() {
+            return false;
+        }
+
+        self.offered_by = Some(player);
+        true
+    }
+
+    /// Accepts the draw the other player offered, giving the result of the game.
+    /// A player cannot accept their own offer.
+    pub fn accept(&mut self, player: Player) -> Option<GameResult> {
+        if self.offered_by != Some(player.opponent()) {
+            return None;
+        }
+
+        self.offered_by = None;
+        Some(GameResult::Draw(DrawRule::Agreement))
+    }
+
+    /// Declines the draw the other player offered, and the game goes on.
+    /// Returns false if there was no offer for the player to decline.
+    pub fn decline(&mut self, player: Player) -> bool {
+        if self.offered_by != Some(player.opponent()) {
+            return false;
+        }
+
+        self.offered_by = None;
+        true
+    }
+
+    /// Takes note of the player making a move, which declines the draw the other player offered.
+    /// An offer of the player themselves stands until the other player answers it.
+    /// Returns true if an offer was declined.
+    pub fn move_made(&mut self, player: Player) -> bool {
+        self.decline(player)
+    }
+}
+
+/// Checks if the computer takes a draw offered to it, which it only does if it is at least a pawn behind.
+pub fn computer_accepts_draw(position: &Position, computer: Player) -> bool {
+    let score = engine::evaluate(position);
+
+    let computer_score = if position.turn == computer {
+        score
+    } else {
+        -score
+    };
+
+    computer_score <= -100
+}
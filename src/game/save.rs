@@ -0,0 +1,152 @@
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::Path;
+use std::time::{Duration, SystemTime};
+
+use serde::{Deserialize, Serialize};
+
+use super::clock::Clock;
+use super::fen::FenError;
+use super::history::History;
+use super::notation::{from_uci, to_uci_chess960};
+use super::player::Player;
+use super::position::Position;
+use super::result::GameResult;
+use super::variant::VariantKind;
+
+/// The moves of a game, written as text so the save file stays readable.
+#[derive(Debug, PartialEq, Eq, Clone, Serialize, Deserialize)]
+pub struct SavedHistory {
+    /// The FEN of the position the game started from.
+    pub start: String,
+    /// The rules the game is played with, which the FEN does not show for every variant.
+    #[serde(default)]
+    pub variant: VariantKind,
+    /// The moves played, in the notation of the UCI protocol for Chess960,
+    /// which is the same as the normal one apart from castling.
+    pub moves: Vec<String>,
+    /// The moves taken back, the one that gets redone first comes first.
+    pub undone: Vec<String>,
+    /// The FEN of the current position, for making sure the moves were read correctly.
+    pub position: String,
+}
+
+impl SavedHistory {
+    pub fn new(history: &History) -> SavedHistory {
+        SavedHistory {
+            start: history.start().to_fen(),
+            variant: history.start().variant,
+            moves: history
+                .moves
+                .iter()
+                .map(|mv| to_uci_chess960(*mv))
+                .collect(),
+            undone: history
+                .undone
+                .iter()
+                .rev()
+                .map(|(mv, _)| to_uci_chess960(*mv))
+                .collect(),
+            position: history.current().to_fen(),
+        }
+    }
+
+    /// Plays through the moves again.
+    pub fn to_history(&self) -> Result<History, SaveError> {
+        let start =
+            Position::from_variant_fen(&self.start, self.variant).map_err(SaveError::Fen)?;
+        let mut history = History::new(start);
+
+        // The moves taken back get played and then taken back again, so they can be redone.
+        for uci in self.moves.iter().chain(&self.undone) {
+            let mv = from_uci(history.current(), uci)
+                .ok_or_else(|| SaveError::IllegalMove(uci.clone()))?;
+            let position = history.current().apply_move(mv).unwrap();
+
+            history.push(mv, position);
+        }
+
+        for _ in &self.undone {
+            history.undo();
+        }
+
+        if history.current().to_fen() != self.position {
+            return Err(SaveError::PositionMismatch);
+        }
+
+        Ok(history)
+    }
+}
+
+/// Everything needed to carry on with a game after closing the window.
+#[derive(Debug, PartialEq, Eq, Clone, Serialize, Deserialize)]
+pub struct SavedGame {
+    pub history: SavedHistory,
+    pub started: SystemTime,
+    pub clock: Option<Clock>,
+    /// The time left after every move, for the PGN.
+    pub clock_times: Vec<Duration>,
+    pub result: Option<GameResult>,
+    pub draw_offer: Option<Player>,
+    /// Whether the board was turned around on top of the configured orientation.
+    pub flipped: bool,
+    pub white_player_name: String,
+    pub black_player_name: String,
+    /// The players the engines move for, which switch with a rematch.
+    pub engine_player: String,
+    pub ai_player: String,
+}
+
+impl SavedGame {
+    /// Checks if the game can still be played on, which it can if it started and did not end yet.
+    pub fn is_unfinished(&self) -> bool {
+        self.result.is_none() && !(self.history.moves.is_empty() && self.history.undone.is_empty())
+    }
+}
+
+/// The reasons a saved game could not be written or read back.
+#[derive(Debug)]
+pub enum SaveError {
+    Io(io::Error),
+    Json(serde_json::Error),
+    Fen(FenError),
+    /// A move that is not legal in the position it was saved in.
+    IllegalMove(String),
+    /// The moves lead to a different position than the one saved.
+    PositionMismatch,
+}
+
+impl fmt::Display for SaveError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SaveError::Io(e) => write!(f, "{}", e),
+            SaveError::Json(e) => write!(f, "invalid save file: {}", e),
+            SaveError::Fen(e) => write!(f, "invalid starting position: {}", e),
+            SaveError::IllegalMove(uci) => write!(f, "illegal move {}", uci),
+            SaveError::PositionMismatch => write!(f, "the moves do not lead to the saved position"),
+        }
+    }
+}
+
+/// Writes the game to a JSON file, creating the folder if needed.
+/// The file is written next to the old one first, so a crash halfway does not lose the last save.
+pub fn save_game(path: &Path, game: &SavedGame) -> Result<(), SaveError> {
+    let json = serde_json::to_string_pretty(game).map_err(SaveError::Json)?;
+
+    if let Some(folder) = path.parent() {
+        fs::create_dir_all(folder).map_err(SaveError::Io)?;
+    }
+
+    let temporary = path.with_extension("tmp");
+
+    fs::write(&temporary, json).map_err(SaveError::Io)?;
+    fs::rename(&temporary, path).map_err(SaveError::Io)
+}
+
+/// Reads a game written by `save_game`.
+pub fn load_game(path: &Path) -> Result<SavedGame, SaveError> {
+    let json = fs::read_to_string(path).map_err(SaveError::Io)?;
+
+    serde_json::from_str(&json).map_err(SaveError::Json)
+}
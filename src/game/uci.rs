@@ -0,0 +1,246 @@
+use std::fmt;
+use std::io::{self, BufRead, BufReader, Write};
+use std::process::{Child, ChildStdin, Command, Stdio};
+use std::sync::mpsc::{self, Receiver, TryRecvError};
+use std::thread;
+
+use super::history::History;
+use super::notation::{to_uci, to_uci_chess960};
+use super::player::Player;
+use super::position::Position;
+use super::variant::VariantKind;
+
+/// How good the engine thinks the position is for the player on turn.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum Score {
+    /// In hundredths of a pawn.
+    Centipawns(i32),
+    /// Moves until mate, negative if the player on turn gets mated.
+    Mate(i32),
+}
+
+impl Score {
+    /// Turns a score for the player on turn into a score for White, like scores are usually shown.
+    pub fn for_white(self, turn: Player) -> Score {
+        match (self, turn) {
+            (Score::Centipawns(cp), Player::Black) => Score::Centipawns(-cp),
+            (Score::Mate(moves), Player::Black) => Score::Mate(-moves),
+            _ => self,
+        }
+    }
+}
+
+impl fmt::Display for Score {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Score::Centipawns(cp) => write!(f, "{:+.2}", *cp as f32 / 100.0),
+            Score::Mate(moves) => write!(f, "#{}", moves),
+        }
+    }
+}
+
+/// What the engine reports about its search in "info" lines.
+#[derive(Debug, PartialEq, Eq, Clone, Default)]
+pub struct UciInfo {
+    pub depth: Option<u32>,
+    pub score: Option<Score>,
+    pub nodes: Option<u64>,
+    /// The moves the engine expects to be played, in UCI notation.
+    pub pv: Vec<String>,
+}
+
+/// Reads an "info" line, returns None for other lines.
+/// Parts of the line that are not understood are skipped.
+pub fn parse_info(line: &str) -> Option<UciInfo> {
+    let mut words = line.split_whitespace();
+
+    if words.next() != Some("info") {
+        return None;
+    }
+
+    let mut info = UciInfo::default();
+
+    while let Some(word) = words.next() {
+        match word {
+            "depth" => info.depth = words.next().and_then(|w| w.parse().ok()),
+            "nodes" => info.nodes = words.next().and_then(|w| w.parse().ok()),
+            "score" => {
+                let kind = words.next();
+                let value = words.next().and_then(|w| w.parse().ok());
+
+                info.score = match (kind, value) {
+                    (Some("cp"), Some(value)) => Some(Score::Centipawns(value)),
+                    (Some("mate"), Some(value)) => Some(Score::Mate(value)),
+                    _ => None,
+                };
+            }
+            // The principal variation goes until the end of the line.
+            "pv" => info.pv = words.by_ref().map(|w| w.to_string()).collect(),
+            // Whatever comes after "string" is free text.
+            "string" => break,
+            _ => (),
+        }
+    }
+
+    Some(info)
+}
+
+/// Reads a "bestmove" line, returning the move in UCI notation.
+/// Returns None for other lines and if the engine has no move to make.
+pub fn parse_bestmove(line: &str) -> Option<String> {
+    let mut words = line.split_whitespace();
+
+    if words.next() != Some("bestmove") {
+        return None;
+    }
+
+    match words.next() {
+        Some("(none)") | Some("0000") | None => None,
+        Some(mv) => Some(mv.to_string()),
+    }
+}
+
+/// An engine program speaking the UCI protocol, running in the background.
+pub struct UciEngine {
+    child: Child,
+    stdin: ChildStdin,
+    lines: Receiver<String>,
+    /// The name the engine gave itself.
+    pub name: String,
+    /// Set once the engine answered "uciok".
+    pub ready: bool,
+    /// Set while the engine program is still running.
+    pub running: bool,
+    /// The position the engine is thinking about.
+    pub searching: Option<Position>,
+    /// The last info the engine gave about its current search.
+    pub info: UciInfo,
+    /// Whether the engine was told to play Chess960, which changes how castling is written.
+    pub chess960: bool,
+    /// The variant the engine was told to play, with the UCI_Variant option.
+    pub variant: VariantKind,
+}
+
+impl UciEngine {
+    /// Starts the engine program at the path and greets it.
+    pub fn start(path: &str) -> io::Result<UciEngine> {
+        let mut child = Command::new(path)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::null())
+            .spawn()?;
+
+        let stdin = child.stdin.take().unwrap();
+        let stdout = child.stdout.take().unwrap();
+
+        // The engine's output is read on its own thread, so the game never waits for it.
+        let (sender, lines) = mpsc::channel();
+
+        thread::spawn(move || {
+            for line in BufReader::new(stdout).lines().map_while(Result::ok) {
+                if sender.send(line).is_err() {
+                    break;
+                }
+            }
+        });
+
+        let mut engine = UciEngine {
+            child,
+            stdin,
+            lines,
+            name: path.to_string(),
+            ready: false,
+            running: true,
+            searching: None,
+            info: UciInfo::default(),
+            chess960: false,
+            variant: VariantKind::Standard,
+        };
+
+        engine.send("uci")?;
+
+        Ok(engine)
+    }
+
+    /// Sends a command to the engine.
+    pub fn send(&mut self, command: &str) -> io::Result<()> {
+        writeln!(self.stdin, "{}", command)?;
+        self.stdin.flush()
+    }
+
+    /// Lets the engine think about the last position of the game for the given time.
+    pub fn go(&mut self, history: &History, movetime: u64) -> io::Result<()> {
+        let chess960 = history.start().is_chess960();
+
+        if chess960 != self.chess960 {
+            self.send(&format!("setoption name UCI_Chess960 value {}", chess960))?;
+            self.chess960 = chess960;
+        }
+
+        let variant = history.start().variant;
+
+        if variant != self.variant {
+            self.send(&format!(
+                "setoption name UCI_Variant value {}",
+                variant.uci_name()
+            ))?;
+            self.variant = variant;
+        }
+
+        let mut command = format!("position fen {}", history.start().to_fen());
+
+        if !history.is_empty() {
+            let write_move = if chess960 { to_uci_chess960 } else { to_uci };
+            let moves: Vec<String> = history.moves.iter().map(|mv| write_move(*mv)).collect();
+
+            command.push_str(" moves ");
+            command.push_str(&moves.join(" "));
+        }
+
+        self.send(&command)?;
+        self.send(&format!("go movetime {}", movetime))?;
+
+        self.searching = Some(*history.current());
+        self.info = UciInfo::default();
+
+        Ok(())
+    }
+
+    /// Reads what the engine said since the last call, without waiting.
+    /// Returns the position searched together with the move the engine picked for it, once it is done.
+    pub fn poll(&mut self) -> Option<(Position, Option<String>)> {
+        loop {
+            let line = match self.lines.try_recv() {
+                Ok(line) => line,
+                Err(TryRecvError::Empty) => return None,
+                Err(TryRecvError::Disconnected) => {
+                    self.running = false;
+                    return self.searching.take().map(|position| (position, None));
+                }
+            };
+
+            if line == "uciok" {
+                self.ready = true;
+            } else if let Some(name) = line.strip_prefix("id name ") {
+                self.name = name.trim().to_string();
+            } else if let Some(info) = parse_info(&line) {
+                // Some info lines only carry the current move or the hash usage.
+                if info.depth.is_some() || info.score.is_some() || !info.pv.is_empty() {
+                    self.info = info;
+                }
+            } else if line.starts_with("bestmove") {
+                if let Some(position) = self.searching.take() {
+                    return Some((position, parse_bestmove(&line)));
+                }
+            }
+        }
+    }
+}
+
+impl Drop for UciEngine {
+    fn drop(&mut self) {
+        let _ = self.send("quit");
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+    }
+}
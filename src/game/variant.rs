@@ -0,0 +1,585 @@
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+use super::bitboard::{self, bit, Bitboards};
+use super::check;
+use super::moves::{Move, MoveKind};
+use super::pieces::{Piece, PieceKind};
+use super::player::Player;
+use super::position::Position;
+
+/// The rules of a chess variant, as changes to the rules of standard chess.
+/// Every hook does what standard chess does unless a variant overrides it.
+pub trait Variant: Sync {
+    /// The name for the PGN Variant tag.
+    fn name(&self) -> &'static str;
+
+    /// How a player wins by the rules of the variant, like "three checks".
+    fn win_reason(&self) -> &'static str {
+        "checkmate"
+    }
+
+    /// The pieces a pawn can turn into.
+    fn promotion_pieces(&self) -> &'static [PieceKind] {
+        &PieceKind::PROMOTIONS
+    }
+
+    /// Returns true if every player has exactly one king, which has to be kept safe.
+    fn royal_king(&self) -> bool {
+        true
+    }
+
+    /// Returns true if the players only have the pieces they started with and what their pawns turned into.
+    fn limited_pieces(&self) -> bool {
+        true
+    }
+
+    /// Returns true if the players can castle.
+    fn castling(&self) -> bool {
+        true
+    }
+
+    /// Returns true if a capture blows up the pieces around it.
+    fn explodes_on_capture(&self) -> bool {
+        false
+    }
+
+    /// Returns true if the players keep the pieces they take in a pocket, to drop them later.
+    fn has_pockets(&self) -> bool {
+        false
+    }
+
+    /// Returns true if the checks given count towards winning.
+    fn counts_checks(&self) -> bool {
+        false
+    }
+
+    /// Whether having more material is good, 1, or bad, -1, for the evaluation of the engine.
+    fn material_sign(&self) -> i32 {
+        1
+    }
+
+    /// Sets up the starting position, given the standard or a Chess960 one.
+    fn start_position(&self, position: Position) -> Position {
+        position
+    }
+
+    /// Gets every legal move of the player on turn, for variants where other moves are legal than in standard chess.
+    fn legal_moves(&self, position: &Position) -> Vec<Move> {
+        bitboard::legal_moves(position)
+    }
+
+    /// Returns true if a player who can take a piece has to.
+    fn forced_captures(&self) -> bool {
+        false
+    }
+
+    /// Takes out the moves the variant does not allow, from the moves of `legal_moves`.
+    fn filter_moves(&self, _position: &Position, _moves: &mut Vec<Move>) {}
+
+    /// Returns true if the player on turn is in check.
+    fn in_check(&self, position: &Position) -> bool {
+        Bitboards::from_board(&position.board).in_check(position.turn)
+    }
+
+    /// Changes the position after a move was played, for rules that do more than move the pieces.
+    fn after_move(&self, _before: &Position, _mv: Move, _after: &mut Position) {}
+
+    /// Gets the player who won by the rules of the variant, looked at before checkmate and the draws.
+    fn winner(&self, _position: &Position) -> Option<Player> {
+        None
+    }
+
+    /// Returns true if neither player can win anymore, whatever moves are played.
+    fn insufficient_material(&self, position: &Position) -> bool {
+        check::insufficient_material(position)
+    }
+
+    /// Returns true if the player could still win, which decides who wins when the other player runs out of time.
+    fn can_win(&self, position: &Position, player: Player) -> bool {
+        check::can_checkmate(position, player)
+    }
+}
+
+/// The rule sets the game can be played with.
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Default, Serialize, Deserialize)]
+pub enum VariantKind {
+    #[default]
+    Standard,
+    KingOfTheHill,
+    ThreeCheck,
+    Atomic,
+    Crazyhouse,
+    Antichess,
+}
+
+impl fmt::Display for VariantKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.rules().name())
+    }
+}
+
+impl VariantKind {
+    pub const ALL: [VariantKind; 6] = [
+        VariantKind::Standard,
+        VariantKind::KingOfTheHill,
+        VariantKind::ThreeCheck,
+        VariantKind::Atomic,
+        VariantKind::Crazyhouse,
+        VariantKind::Antichess,
+    ];
+
+    /// Gets the rules of the variant.
+    pub fn rules(&self) -> &'static dyn Variant {
+        match self {
+            VariantKind::Standard => &Standard,
+            VariantKind::KingOfTheHill => &KingOfTheHill,
+            VariantKind::ThreeCheck => &ThreeCheck,
+            VariantKind::Atomic => &Atomic,
+            VariantKind::Crazyhouse => &Crazyhouse,
+            VariantKind::Antichess => &Antichess,
+        }
+    }
+
+    /// The name of the variant in the UCI_Variant option, which is how most engines call it.
+    pub fn uci_name(&self) -> &'static str {
+        match self {
+            VariantKind::Standard => "chess",
+            VariantKind::KingOfTheHill => "kingofthehill",
+            VariantKind::ThreeCheck => "3check",
+            VariantKind::Atomic => "atomic",
+            VariantKind::Crazyhouse => "crazyhouse",
+            VariantKind::Antichess => "antichess",
+        }
+    }
+
+    /// Reads the name of a variant, like in the PGN Variant tag or the UCI_Variant option.
+    /// Case, spaces and dashes do not matter.
+    pub fn parse(name: &str) -> Option<VariantKind> {
+        let name: String = name
+            .chars()
+            .filter(|c| !matches!(c, ' ' | '-' | '_'))
+            .collect::<String>()
+            .to_ascii_lowercase();
+
+        VariantKind::ALL.into_iter().find(|variant| {
+            name == variant.uci_name()
+                || name
+                    == variant
+                        .rules()
+                        .name()
+                        .replace([' ', '-'], "")
+                        .to_ascii_lowercase()
+        })
+    }
+}
+
+/// Returns true if the player has any pieces besides the king.
+fn has_pieces(position: &Position, player: Player) -> bool {
+    position
+        .board
+        .board
+        .iter()
+        .flatten()
+        .any(|piece| !piece.eq_ignore_ascii_case(&'k') && Piece::get_player(*piece) == player)
+}
+
+/// The normal rules of chess.
+pub struct Standard;
+
+impl Variant for Standard {
+    fn name(&self) -> &'static str {
+        "Standard"
+    }
+}
+
+/// A player also wins by bringing the king to one of the four squares in the center.
+pub struct KingOfTheHill;
+
+/// The squares the king has to reach in King of the Hill, d5, e5, d4 and e4.
+const HILL: [(usize, usize); 4] = [(3, 3), (3, 4), (4, 3), (4, 4)];
+
+impl Variant for KingOfTheHill {
+    fn name(&self) -> &'static str {
+        "King of the Hill"
+    }
+
+    fn win_reason(&self) -> &'static str {
+        "reaching the center"
+    }
+
+    fn winner(&self, position: &Position) -> Option<Player> {
+        HILL.iter()
+            .find_map(|square| match position.piece_at(*square) {
+                'K' => Some(Player::White),
+                'k' => Some(Player::Black),
+                _ => None,
+            })
+    }
+
+    // A lone king can still walk to the center.
+    fn insufficient_material(&self, _position: &Position) -> bool {
+        false
+    }
+
+    fn can_win(&self, _position: &Position, _player: Player) -> bool {
+        true
+    }
+}
+
+/// A player also wins by giving check three times.
+pub struct ThreeCheck;
+
+impl Variant for ThreeCheck {
+    fn name(&self) -> &'static str {
+        "Three-check"
+    }
+
+    fn win_reason(&self) -> &'static str {
+        "three checks"
+    }
+
+    fn counts_checks(&self) -> bool {
+        true
+    }
+
+    fn after_move(&self, before: &Position, _mv: Move, after: &mut Position) {
+        if after.in_check() {
+            after.checks[usize::from(before.turn == Player::Black)] += 1;
+        }
+    }
+
+    fn winner(&self, position: &Position) -> Option<Player> {
+        [Player::White, Player::Black]
+            .into_iter()
+            .zip(position.checks)
+            .find(|(_, checks)| *checks >= 3)
+            .map(|(player, _)| player)
+    }
+
+    // Any piece can give check, so only the kings alone are a draw.
+    fn insufficient_material(&self, position: &Position) -> bool {
+        !self.can_win(position, Player::White) && !self.can_win(position, Player::Black)
+    }
+
+    fn can_win(&self, position: &Position, player: Player) -> bool {
+        has_pieces(position, player)
+    }
+}
+
+/// Captures explode, taking the capturing piece and every piece but the pawns next to the capture with them.
+/// A player wins by blowing up the king of the other player, which is why kings cannot capture.
+pub struct Atomic;
+
+impl Atomic {
+    /// Gets the squares around a square, not the square itself.
+    pub fn blast_radius(square: (usize, usize)) -> impl Iterator<Item = (usize, usize)> {
+        let rows = square.0.saturating_sub(1)..=(square.0 + 1).min(7);
+
+        rows.flat_map(move |row| {
+            let columns = square.1.saturating_sub(1)..=(square.1 + 1).min(7);
+
+            columns.map(move |column| (row, column))
+        })
+        .filter(move |s| *s != square)
+    }
+
+    fn has_king(position: &Position, player: Player) -> bool {
+        Bitboards::from_board(&position.board).piece(player, PieceKind::King) != 0
+    }
+}
+
+impl Variant for Atomic {
+    fn name(&self) -> &'static str {
+        "Atomic"
+    }
+
+    fn win_reason(&self) -> &'static str {
+        "exploding the king"
+    }
+
+    fn explodes_on_capture(&self) -> bool {
+        true
+    }
+
+    /// Tries out every move the pieces can make.
+    /// A move is legal if the own king survives it, and then either the enemy king is gone
+    /// or the own king is not in check.
+    fn legal_moves(&self, position: &Position) -> Vec<Move> {
+        let player = position.turn;
+        let mut moves = Vec::new();
+
+        if !Atomic::has_king(position, player) {
+            return moves;
+        }
+
+        for mv in
+            bitboard::pseudo_legal_moves(position, |board| !check::atomic_check(*board, player))
+        {
+            let is_king = position.piece_at(mv.from).eq_ignore_ascii_case(&'k');
+
+            if is_king && position.is_capture(mv) {
+                continue;
+            }
+
+            let next = position.play(mv);
+
+            if Atomic::has_king(&next, player)
+                && (!Atomic::has_king(&next, player.opponent())
+                    || !check::atomic_check(next.board, player))
+            {
+                moves.push(mv);
+            }
+        }
+
+        moves
+    }
+
+    fn in_check(&self, position: &Position) -> bool {
+        check::atomic_check(position.board, position.turn)
+    }
+
+    fn after_move(&self, before: &Position, mv: Move, after: &mut Position) {
+        if !before.is_capture(mv) {
+            return;
+        }
+
+        after.board.board[mv.to.0][mv.to.1] = ' ';
+
+        for (row, column) in Atomic::blast_radius(mv.to) {
+            if !after.board.board[row][column].eq_ignore_ascii_case(&'p') {
+                after.board.board[row][column] = ' ';
+            }
+        }
+
+        // Rooks that blew up cannot castle anymore.
+        for (i, right) in before.castling.iter().enumerate() {
+            let (row, rook) = if i < 2 { (7, 'R') } else { (0, 'r') };
+
+            if right.is_some_and(|column| after.board.board[row][column] != rook) {
+                after.castling[i] = None;
+            }
+        }
+    }
+
+    fn winner(&self, position: &Position) -> Option<Player> {
+        match (
+            Atomic::has_king(position, Player::White),
+            Atomic::has_king(position, Player::Black),
+        ) {
+            (true, false) => Some(Player::White),
+            (false, true) => Some(Player::Black),
+            _ => None,
+        }
+    }
+
+    // A king cannot capture, so a lone king can never win.
+    fn insufficient_material(&self, position: &Position) -> bool {
+        !self.can_win(position, Player::White) && !self.can_win(position, Player::Black)
+    }
+
+    fn can_win(&self, position: &Position, player: Player) -> bool {
+        has_pieces(position, player)
+    }
+}
+
+/// Captured pieces go into the pocket of the player who took them, and can be put back on the board as their own.
+/// Promoted pieces turn back into pawns when they get captured.
+pub struct Crazyhouse;
+
+impl Variant for Crazyhouse {
+    fn name(&self) -> &'static str {
+        "Crazyhouse"
+    }
+
+    // Captured pieces change sides, so a player can have any number of them, even more than 8 pawns.
+    fn limited_pieces(&self) -> bool {
+        false
+    }
+
+    fn has_pockets(&self) -> bool {
+        true
+    }
+
+    /// The moves of standard chess, and putting a piece from the pocket on any empty square.
+    /// Pawns cannot be dropped on the first or last rank.
+    fn legal_moves(&self, position: &Position) -> Vec<Move> {
+        let mut moves = bitboard::legal_moves(position);
+        let in_check = position.in_check();
+
+        for (piece, count) in PieceKind::POCKET
+            .into_iter()
+            .zip(position.pocket(position.turn))
+        {
+            if count == 0 {
+                continue;
+            }
+
+            let rows = if piece == PieceKind::Pawn { 1..7 } else { 0..8 };
+
+            for row in rows {
+                for column in 0..8 {
+                    if position.piece_at((row, column)) != ' ' {
+                        continue;
+                    }
+
+                    let mv = Move::drop(piece, (row, column));
+
+                    // Putting a piece down cannot uncover the own king, but it might block a check.
+                    if !in_check || !check::check(position.play(mv).board, position.turn) {
+                        moves.push(mv);
+                    }
+                }
+            }
+        }
+
+        moves
+    }
+
+    fn after_move(&self, before: &Position, mv: Move, after: &mut Position) {
+        let pocket = &mut after.pockets[usize::from(before.turn == Player::Black)];
+
+        if let Some(piece) = mv.dropped_piece() {
+            pocket[piece as usize] -= 1;
+        }
+
+        if before.is_capture(mv) {
+            let square = if mv.kind == MoveKind::EnPassant {
+                (mv.from.0, mv.to.1)
+            } else {
+                mv.to
+            };
+
+            let captured = if before.promoted & bit(square) != 0 {
+                Some(PieceKind::Pawn)
+            } else {
+                PieceKind::from_char(before.piece_at(square))
+            };
+
+            if let Some(captured) = captured.filter(|kind| *kind != PieceKind::King) {
+                pocket[captured as usize] += 1;
+            }
+        }
+
+        // A promoted piece stays promoted wherever it goes.
+        let was_promoted = before.promoted & bit(mv.from) != 0 && mv.dropped_piece().is_none();
+
+        after.promoted &= !bit(mv.from) & !bit(mv.to);
+
+        if was_promoted || mv.promotion.is_some() {
+            after.promoted |= bit(mv.to);
+        }
+    }
+
+    // Captured pieces come back, so only the kings alone are a draw.
+    fn insufficient_material(&self, position: &Position) -> bool {
+        !has_pieces(position, Player::White)
+            && !has_pieces(position, Player::Black)
+            && position.pockets.iter().flatten().all(|count| *count == 0)
+    }
+
+    // Either player can capture any piece left and drop it as their own,
+    // so it does not matter whose pieces they are.
+    fn can_win(&self, position: &Position, _player: Player) -> bool {
+        !self.insufficient_material(position)
+    }
+}
+
+/// Captures are forced, and the king is a piece like any other, without check or castling.
+/// A player wins by losing all the pieces, or by having no moves left.
+pub struct Antichess;
+
+impl Variant for Antichess {
+    fn name(&self) -> &'static str {
+        "Antichess"
+    }
+
+    fn win_reason(&self) -> &'static str {
+        "having no moves left"
+    }
+
+    fn promotion_pieces(&self) -> &'static [PieceKind] {
+        &[
+            PieceKind::Queen,
+            PieceKind::Rook,
+            PieceKind::Knight,
+            PieceKind::Bishop,
+            PieceKind::King,
+        ]
+    }
+
+    fn royal_king(&self) -> bool {
+        false
+    }
+
+    // Losing the pieces is the goal, so the material counts the other way around.
+    fn material_sign(&self) -> i32 {
+        -1
+    }
+
+    fn castling(&self) -> bool {
+        false
+    }
+
+    /// Every move the pieces can make, as the king does not need to be safe.
+    /// Pawns can also turn into a king.
+    fn legal_moves(&self, position: &Position) -> Vec<Move> {
+        let mut moves = Vec::new();
+
+        for mv in bitboard::pseudo_legal_moves(position, |_| true) {
+            moves.push(mv);
+
+            if mv.promotion == Some(PieceKind::Queen) {
+                moves.push(mv.with_promotion(PieceKind::King));
+            }
+        }
+
+        moves
+    }
+
+    fn forced_captures(&self) -> bool {
+        true
+    }
+
+    /// Taking a piece is forced, if there is anything to take.
+    fn filter_moves(&self, position: &Position, moves: &mut Vec<Move>) {
+        if moves.iter().any(|mv| position.is_capture(*mv)) {
+            moves.retain(|mv| position.is_capture(*mv));
+        }
+    }
+
+    fn in_check(&self, _position: &Position) -> bool {
+        false
+    }
+
+    fn winner(&self, position: &Position) -> Option<Player> {
+        if self.legal_moves(position).is_empty() {
+            Some(position.turn)
+        } else {
+            None
+        }
+    }
+
+    // Only bishops on squares of different colours are left, they can never take each other.
+    fn insufficient_material(&self, position: &Position) -> bool {
+        let mut colours = [[false; 2]; 2];
+
+        for (row, pieces) in position.board.board.iter().enumerate() {
+            for (column, piece) in pieces.iter().enumerate() {
+                match piece {
+                    ' ' => (),
+                    'B' => colours[0][(row + column) % 2] = true,
+                    'b' => colours[1][(row + column) % 2] = true,
+                    _ => return false,
+                }
+            }
+        }
+
+        (0..2).all(|colour| !(colours[0][colour] && colours[1][colour]))
+    }
+
+    fn can_win(&self, position: &Position, _player: Player) -> bool {
+        !self.insufficient_material(position)
+    }
+}
@@ -0,0 +1,139 @@
+use std::sync::OnceLock;
+
+use super::bitboard;
+use super::moves::MoveKind;
+use super::pieces::PieceKind;
+use super::player::Player;
+use super::position::Position;
+
+/// A random number for every piece on every square, the player on turn,
+/// every castling right, every en passant column, every number of checks given in Three-check
+/// every number of pieces of a kind in a pocket in Crazyhouse and every square a promoted piece can be on.
+/// The hash of a position is all of its numbers xor-ed together.
+struct Keys {
+    pieces: [[[u64; 64]; 6]; 2],
+    black_to_move: u64,
+    castling: [u64; 4],
+    en_passant: [u64; 8],
+    checks: [[u64; 3]; 2],
+    pockets: [[[u64; 16]; 5]; 2],
+    promoted: [u64; 64],
+}
+
+/// A small random number generator, always starting from the same seed so the hashes never change.
+/// See https://prng.di.unimi.it/splitmix64.c
+fn split_mix(state: &mut u64) -> u64 {
+    *state = state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+
+    let mut z = *state;
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+
+    z ^ (z >> 31)
+}
+
+impl Keys {
+    fn new() -> Keys {
+        let mut state = 0x2545_F491_4F6C_DD1D;
+
+        let mut keys = Keys {
+            pieces: [[[0; 64]; 6]; 2],
+            black_to_move: split_mix(&mut state),
+            castling: [0; 4],
+            en_passant: [0; 8],
+            checks: [[0; 3]; 2],
+            pockets: [[[0; 16]; 5]; 2],
+            promoted: [0; 64],
+        };
+
+        for key in keys.pieces.iter_mut().flatten().flatten() {
+            *key = split_mix(&mut state);
+        }
+
+        for key in keys.castling.iter_mut().chain(keys.en_passant.iter_mut()) {
+            *key = split_mix(&mut state);
+        }
+
+        // Drawn last, so the hashes of standard positions stay the same.
+        for key in keys.checks.iter_mut().flatten() {
+            *key = split_mix(&mut state);
+        }
+
+        for key in keys.pockets.iter_mut().flatten().flatten() {
+            *key = split_mix(&mut state);
+        }
+
+        for key in keys.promoted.iter_mut() {
+            *key = split_mix(&mut state);
+        }
+
+        keys
+    }
+}
+
+fn keys() -> &'static Keys {
+    static KEYS: OnceLock<Keys> = OnceLock::new();
+
+    KEYS.get_or_init(Keys::new)
+}
+
+/// Gets the Zobrist hash of a position, which is the same for the same arrangement of pieces,
+/// player on turn, castling rights, en passant capture, checks given, pieces in the pockets
+/// and which pieces turn back into pawns when captured in Crazyhouse.
+/// The move counters are left out, so repeated positions get the same hash.
+pub fn hash(position: &Position) -> u64 {
+    let keys = keys();
+    let mut hash = 0;
+
+    for (row, pieces) in position.board.board.iter().enumerate() {
+        for (column, piece) in pieces.iter().enumerate() {
+            if let Some(kind) = PieceKind::from_char(*piece) {
+                let color = usize::from(piece.is_lowercase());
+
+                hash ^= keys.pieces[color][kind as usize][row * 8 + column];
+            }
+        }
+    }
+
+    if position.turn == Player::Black {
+        hash ^= keys.black_to_move;
+    }
+
+    for (right, key) in position.castling.iter().zip(keys.castling) {
+        if right.is_some() {
+            hash ^= key;
+        }
+    }
+
+    for (checks, key) in position.checks.iter().zip(keys.checks) {
+        if *checks > 0 {
+            hash ^= key[usize::from(*checks).min(3) - 1];
+        }
+    }
+
+    for (pocket, keys) in position.pockets.iter().zip(&keys.pockets) {
+        for (count, key) in pocket.iter().zip(keys) {
+            if *count > 0 {
+                hash ^= key[usize::from(*count).min(16) - 1];
+            }
+        }
+    }
+
+    for square in bitboard::squares(position.promoted) {
+        hash ^= keys.promoted[square];
+    }
+
+    // A double pawn move only changes the position if the pawn can actually be taken en passant,
+    // otherwise a repetition would be missed.
+    if let Some(column) = position.en_passant {
+        if position
+            .legal_moves()
+            .iter()
+            .any(|mv| mv.kind == MoveKind::EnPassant)
+        {
+            hash ^= keys.en_passant[column];
+        }
+    }
+
+    hash
+}
@@ -1,5 +1,61 @@
-mod game;
+use std::env;
+use std::time::Instant;
+
+use chess::game::notation::to_uci;
+use chess::game::perft::divide;
+use chess::game::position::Position;
+
+/// Prints the perft count for every move, run with `perft <depth> [fen]`.
+fn run_perft(args: &[String]) {
+    let depth = match args.first().and_then(|d| d.parse().ok()) {
+        // Depth 0 has no moves to list, only the position itself.
+        Some(0) => {
+            println!("The depth has to be at least 1.");
+            return;
+        }
+        Some(depth) => depth,
+        None => {
+            println!("Usage: chess perft <depth> [fen]");
+            return;
+        }
+    };
+
+    let position = if args.len() > 1 {
+        match Position::from_fen(&args[1..].join(" ")) {
+            Ok(position) => position,
+            Err(e) => {
+                println!("Invalid FEN: {}", e);
+                return;
+            }
+        }
+    } else {
+        Position::new()
+    };
+
+    let start = Instant::now();
+    let mut total = 0;
+
+    for (mv, nodes) in divide(&position, depth) {
+        println!("{}: {}", to_uci(mv), nodes);
+        total += nodes;
+    }
+
+    let elapsed = start.elapsed();
+
+    println!("\nNodes searched: {}", total);
+    println!("Time: {:?}", elapsed);
+    println!(
+        "Nodes per second: {}",
+        (total as f64 / elapsed.as_secs_f64().max(1e-9)) as u64
+    );
+}
 
 fn main() {
-    game::game::run_game().unwrap();
+    let args: Vec<String> = env::args().skip(1).collect();
+
+    if args.first().map(String::as_str) == Some("perft") {
+        run_perft(&args[1..]);
+    } else {
+        chess::game::game::run_game().unwrap();
+    }
 }
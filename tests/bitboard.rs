@@ -0,0 +1,85 @@
+use chess::game::bitboard::{
+    bishop_attacks, bit, legal_moves, line, pseudo_legal_moves, rook_attacks, square_index,
+    squares, Bitboards,
+};
+use chess::game::moves::MoveKind;
+use chess::game::notation::{parse_square, square_name};
+use chess::game::player::Player;
+use chess::game::position::Position;
+
+fn index(name: &str) -> usize {
+    square_index(parse_square(name).unwrap())
+}
+
+fn names(bitboard: u64) -> Vec<String> {
+    let mut names: Vec<String> = squares(bitboard)
+        .map(|index| square_name((index / 8, index % 8)))
+        .collect();
+    names.sort();
+    names
+}
+
+#[test]
+fn sliding_attacks_stop_at_the_first_piece() {
+    let occupied = bit(parse_square("d6").unwrap()) | bit(parse_square("b4").unwrap());
+
+    assert_eq!(
+        names(rook_attacks(index("d4"), occupied)),
+        ["b4", "c4", "d1", "d2", "d3", "d5", "d6", "e4", "f4", "g4", "h4"]
+    );
+    assert_eq!(
+        names(bishop_attacks(index("a1"), occupied)),
+        ["b2", "c3", "d4", "e5", "f6", "g7", "h8"]
+    );
+    assert_eq!(line(index("a1"), index("b3")), 0);
+}
+
+#[test]
+fn finds_checkers_pins_and_attacks() {
+    let position = Position::from_fen("4k3/8/8/8/1b6/8/3N4/4K2q w - - 0 1").unwrap();
+    let bitboards = Bitboards::from_board(&position.board);
+
+    assert_eq!(names(bitboards.checkers(Player::White)), ["h1"]);
+    assert_eq!(names(bitboards.pinned(Player::White)), ["d2"]);
+    assert!(bitboards.attacks(Player::Black) & bit(parse_square("f1").unwrap()) != 0);
+
+    // The pinned knight cannot block the check on f1, only the king can move.
+    let moves = position.legal_moves();
+    assert!(moves
+        .iter()
+        .all(|mv| mv.from == parse_square("e1").unwrap()));
+    assert_eq!(moves.len(), 2);
+}
+
+#[test]
+fn pseudo_legal_moves_ignore_the_own_king() {
+    // The knight is pinned and f1 is attacked, which only matters for the legal moves.
+    let position = Position::from_fen("4k3/8/8/8/1b6/8/3N4/R3K2R w KQ - 0 1").unwrap();
+    let knight = parse_square("d2").unwrap();
+
+    assert!(!legal_moves(&position).iter().any(|mv| mv.from == knight));
+    assert_eq!(
+        pseudo_legal_moves(&position, |_| true)
+            .iter()
+            .filter(|mv| mv.from == knight)
+            .count(),
+        6
+    );
+
+    let castles = |safe: bool| {
+        pseudo_legal_moves(&position, |_| safe)
+            .iter()
+            .filter(|mv| mv.kind == MoveKind::Castle)
+            .count()
+    };
+
+    assert_eq!(castles(true), 2);
+    assert_eq!(castles(false), 0);
+    assert_eq!(
+        legal_moves(&position)
+            .iter()
+            .filter(|mv| mv.kind == MoveKind::Castle)
+            .count(),
+        2
+    );
+}
@@ -0,0 +1,133 @@
+use std::collections::HashSet;
+
+use chess::game::moves::MoveKind;
+use chess::game::notation::{from_uci, to_san, to_uci, to_uci_chess960};
+use chess::game::perft::perft;
+use chess::game::position::Position;
+
+#[test]
+fn all_960_starting_positions() {
+    assert_eq!(Position::chess960(518), Position::new());
+
+    let mut back_ranks = HashSet::new();
+
+    for index in 0..960 {
+        let position = Position::chess960(index);
+        let back_rank = position.board.board[7];
+        let column = |piece: char| back_rank.iter().position(|p| *p == piece).unwrap();
+        let bishops: Vec<usize> = (0..8).filter(|c| back_rank[*c] == 'B').collect();
+        let [kingside, queenside, _, _] = position.castling;
+
+        assert_ne!(bishops[0] % 2, bishops[1] % 2, "{}", index);
+        assert!(queenside.unwrap() < column('K') && column('K') < kingside.unwrap());
+        assert_eq!(Position::from_fen(&position.to_fen()), Ok(position));
+
+        back_ranks.insert(back_rank);
+    }
+
+    assert_eq!(back_ranks.len(), 960);
+}
+
+#[test]
+fn perft_chess960() {
+    let cases = [
+        (
+            "bqnb1rkr/pp3ppp/3ppn2/2p5/5P2/P2P4/NPP1P1PP/BQ1BNRKR w HFhf - 2 9",
+            [21, 528, 12189, 326672],
+        ),
+        (
+            "2nnrbkr/p1qppppp/8/1ppb4/6PP/3PP3/PPP2P2/BQNNRBKR w HEhe - 1 9",
+            [21, 807, 18002, 667366],
+        ),
+        (
+            "b1q1rrkb/pppppppp/3nn3/8/P7/1PPP4/4PPPP/BQNNRKRB w GE - 1 9",
+            [20, 479, 10471, 273318],
+        ),
+        (
+            "qbbnnrkr/2pp2pp/p7/1p2pp2/8/P3PP2/1PPP1KPP/QBBNNR1R w hf - 0 9",
+            [22, 593, 13440, 382958],
+        ),
+    ];
+
+    for (fen, counts) in cases {
+        let position = Position::from_fen(fen).unwrap();
+
+        for (depth, count) in counts.iter().enumerate() {
+            assert_eq!(perft(&position, depth + 1), *count, "{}", fen);
+        }
+
+        // The slower move checks have to agree with the move generator.
+        for mv in position.legal_moves() {
+            assert!(position.is_legal(mv), "{:?} in {}", mv, fen);
+        }
+    }
+}
+
+#[test]
+fn castling_rights_in_x_fen_and_shredder_fen() {
+    let shredder = Position::from_fen("bqnb1rkr/8/8/8/8/8/8/BQNB1RKR w HFhf - 0 1").unwrap();
+
+    assert_eq!(shredder.castling, [Some(7), Some(5), Some(7), Some(5)]);
+    assert_eq!(
+        shredder.to_fen(),
+        "bqnb1rkr/8/8/8/8/8/8/BQNB1RKR w KQkq - 0 1"
+    );
+
+    // A rook that is not the outermost one on its side needs its column written down.
+    let inner = Position::from_fen("4k3/8/8/8/8/8/8/R1R1K3 w C - 0 1").unwrap();
+    assert_eq!(inner.castling, [None, Some(2), None, None]);
+    assert_eq!(inner.to_fen(), "4k3/8/8/8/8/8/8/R1R1K3 w C - 0 1");
+
+    let outer = Position::from_fen("4k3/8/8/8/8/8/8/R1R1K3 w Q - 0 1").unwrap();
+    assert_eq!(outer.castling, [None, Some(0), None, None]);
+
+    for invalid in [
+        "4k3/8/8/8/8/8/8/R3K3 w K - 0 1",
+        "4k3/8/8/8/8/8/8/R3K3 w B - 0 1",
+        "4k3/8/8/8/8/8/4K3/R7 w Q - 0 1",
+    ] {
+        assert!(Position::from_fen(invalid).is_err(), "{}", invalid);
+    }
+}
+
+#[test]
+fn castling_moves_the_king_onto_its_rook() {
+    // The king does not move at all when castling here, only the rook does.
+    let position = Position::from_fen("4k3/8/8/8/8/8/8/6KR w H - 0 1").unwrap();
+    let castle = position.find_move((7, 6), (7, 7), None).unwrap();
+
+    assert_eq!(castle.kind, MoveKind::Castle);
+    assert_eq!(to_san(&position, castle), "O-O");
+    assert_eq!(to_uci_chess960(castle), "g1h1");
+    assert_eq!(from_uci(&position, "g1h1"), Some(castle));
+
+    let after = position.apply_move(castle).unwrap();
+    assert_eq!(after.piece_at((7, 6)), 'K');
+    assert_eq!(after.piece_at((7, 5)), 'R');
+    assert_eq!(after.piece_at((7, 7)), ' ');
+    assert_eq!(after.castling, [None; 4]);
+
+    // Moving the king to the square it lands on is a normal move if the king can go there anyway.
+    let position = Position::from_fen("4k3/8/8/8/8/8/8/5K1R w H - 0 1").unwrap();
+    assert_eq!(
+        position.find_move((7, 5), (7, 6), None).unwrap().kind,
+        MoveKind::Normal
+    );
+    assert_eq!(
+        position.find_move((7, 5), (7, 7), None).unwrap().kind,
+        MoveKind::Castle
+    );
+
+    // In standard chess both ways of writing it work.
+    let position =
+        Position::from_fen("r3k2r/pppppppp/8/8/8/8/PPPPPPPP/R3K2R w KQkq - 0 1").unwrap();
+    let castle = from_uci(&position, "e1g1").unwrap();
+
+    assert_eq!(castle.kind, MoveKind::Castle);
+    assert_eq!(from_uci(&position, "e1h1"), Some(castle));
+    assert_eq!(to_uci(castle), "e1g1");
+    assert_eq!(
+        to_san(&position, from_uci(&position, "e1c1").unwrap()),
+        "O-O-O"
+    );
+}
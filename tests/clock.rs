@@ -0,0 +1,178 @@
+mod common;
+
+use std::time::Duration;
+
+use chess::game::check::can_checkmate;
+use chess::game::clock::{format_time, Clock, TimeBonus, TimeControl};
+use chess::game::history::History;
+use chess::game::pgn::{parse_pgn, write_pgn};
+use chess::game::player::Player;
+use chess::game::position::Position;
+
+use common::play;
+
+fn seconds(seconds: u64) -> Duration {
+    Duration::from_secs(seconds)
+}
+
+fn clock(control: &str, bonus: TimeBonus) -> Clock {
+    Clock::new(TimeControl::parse(control, bonus).unwrap())
+}
+
+#[test]
+fn parses_pgn_time_controls() {
+    let control = TimeControl::parse("40/5400+30:1800+30", TimeBonus::Fischer).unwrap();
+
+    assert_eq!(control.stages.len(), 2);
+    assert_eq!(control.stages[0].moves, Some(40));
+    assert_eq!(control.stages[0].time, seconds(5400));
+    assert_eq!(control.stages[1].moves, None);
+    assert_eq!(control.stages[1].bonus, seconds(30));
+    assert_eq!(control.to_string(), "40/5400+30:1800+30");
+
+    assert_eq!(
+        TimeControl::parse("180+2", TimeBonus::Fischer)
+            .unwrap()
+            .to_string(),
+        "180+2"
+    );
+
+    for invalid in ["", "abc", "300:40/60", "0/300", "300+"] {
+        assert_eq!(
+            TimeControl::parse(invalid, TimeBonus::Fischer),
+            None,
+            "{}",
+            invalid
+        );
+    }
+}
+
+#[test]
+fn fischer_adds_the_increment_after_every_move() {
+    let mut clock = clock("60+5", TimeBonus::Fischer);
+
+    clock.resume(Player::White);
+    clock.tick(seconds(10));
+    clock.press(Player::White);
+
+    assert_eq!(clock.remaining(Player::White), seconds(55));
+    assert_eq!(clock.running, Some(Player::Black));
+
+    clock.tick(seconds(1));
+    assert_eq!(clock.remaining(Player::Black), seconds(59));
+}
+
+#[test]
+fn bronstein_gives_back_at_most_the_delay() {
+    let mut clock = clock("60+5", TimeBonus::Bronstein);
+
+    clock.resume(Player::White);
+    clock.tick(seconds(3));
+    clock.press(Player::White);
+    assert_eq!(clock.remaining(Player::White), seconds(60));
+
+    clock.tick(seconds(10));
+    clock.press(Player::Black);
+    assert_eq!(clock.remaining(Player::Black), seconds(55));
+}
+
+#[test]
+fn delay_waits_before_counting_down() {
+    let mut clock = clock("60+5", TimeBonus::Delay);
+
+    clock.resume(Player::White);
+    clock.tick(seconds(3));
+    assert_eq!(clock.remaining(Player::White), seconds(60));
+
+    clock.tick(seconds(4));
+    assert_eq!(clock.remaining(Player::White), seconds(58));
+
+    clock.press(Player::White);
+    assert_eq!(clock.remaining(Player::White), seconds(58));
+}
+
+#[test]
+fn stages_add_their_time_after_the_moves() {
+    let mut clock = clock("2/60:30", TimeBonus::Fischer);
+
+    for _ in 0..2 {
+        clock.resume(Player::White);
+        clock.tick(seconds(10));
+        clock.press(Player::White);
+    }
+
+    assert_eq!(clock.remaining(Player::White), seconds(70));
+
+    // The last stage lasts for the rest of the game.
+    for _ in 0..4 {
+        clock.resume(Player::White);
+        clock.press(Player::White);
+    }
+
+    assert_eq!(clock.remaining(Player::White), seconds(70));
+}
+
+#[test]
+fn running_out_of_time_flags() {
+    let mut clock = clock("5", TimeBonus::Fischer);
+
+    clock.resume(Player::Black);
+    clock.tick(seconds(4));
+    assert_eq!(clock.flagged(), None);
+
+    clock.tick(seconds(2));
+    assert_eq!(clock.flagged(), Some(Player::Black));
+    assert_eq!(clock.remaining(Player::Black), Duration::ZERO);
+}
+
+#[test]
+fn formats_clock_times() {
+    assert_eq!(format_time(seconds(5400)), "1:30:00");
+    assert_eq!(format_time(seconds(299)), "4:59");
+    assert_eq!(format_time(Duration::from_millis(9540)), "0:09.5");
+}
+
+#[test]
+fn mating_material_after_a_timeout() {
+    // The FEN, and whether White could still mate.
+    let cases = [
+        ("4k3/8/8/8/8/8/8/4K3 w - - 0 1", false),
+        ("4k3/8/8/8/8/8/8/3QK3 w - - 0 1", true),
+        ("4k3/8/8/8/8/8/8/3NK3 w - - 0 1", false),
+        ("4k3/4p3/8/8/8/8/8/3NK3 w - - 0 1", true),
+        ("3qk3/8/8/8/8/8/8/3NK3 w - - 0 1", false),
+        ("4k3/8/8/8/8/8/8/2NNK3 w - - 0 1", true),
+        ("3rk3/8/8/8/8/8/8/3BK3 w - - 0 1", false),
+        ("3nk3/8/8/8/8/8/8/3BK3 w - - 0 1", true),
+        ("3bk3/8/8/8/8/8/8/3BK3 w - - 0 1", true),
+        ("2b1k3/8/8/8/8/8/8/3BK3 w - - 0 1", false),
+        ("4k3/8/8/8/8/8/8/2NBK3 w - - 0 1", true),
+    ];
+
+    for (fen, mate) in cases {
+        let position = Position::from_fen(fen).unwrap();
+
+        assert_eq!(can_checkmate(&position, Player::White), mate, "{}", fen);
+    }
+}
+
+#[test]
+fn writes_clock_times_to_pgn() {
+    let mut history = History::default();
+
+    play(&mut history, &["e2e4", "e7e5", "g1f3"]);
+
+    let pgn = write_pgn(
+        &[("Result".to_string(), "*".to_string())],
+        &history,
+        &[seconds(5398), seconds(5400), seconds(5361)],
+    );
+
+    assert!(
+        pgn.contains("1. e4 {[%clk 1:29:58]} 1... e5 {[%clk 1:30:00]} 2. Nf3 {[%clk 1:29:21]} *")
+    );
+
+    let games = parse_pgn(&pgn).unwrap();
+    assert_eq!(games[0].moves.len(), 3);
+    assert_eq!(games[0].moves[1].comments, ["[%clk 1:30:00]"]);
+}
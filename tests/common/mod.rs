@@ -0,0 +1,12 @@
+use chess::game::history::History;
+use chess::game::notation::from_uci;
+
+/// Plays the moves in the UCI notation, which all have to be legal.
+pub fn play(history: &mut History, moves: &[&str]) {
+    for uci in moves {
+        let mv = from_uci(history.current(), uci).unwrap();
+        let position = history.current().apply_move(mv).unwrap();
+
+        history.push(mv, position);
+    }
+}
@@ -0,0 +1,102 @@
+mod common;
+
+use chess::game::check::{automatic_draw, claimable_draw, DrawRule};
+use chess::game::history::History;
+use chess::game::position::Position;
+use chess::game::zobrist;
+
+use common::play;
+
+const KNIGHT_DANCE: [&str; 4] = ["g1f3", "g8f6", "f3g1", "f6g8"];
+
+#[test]
+fn threefold_repetition_can_be_claimed() {
+    let mut history = History::default();
+
+    play(&mut history, &KNIGHT_DANCE);
+    assert_eq!(history.repetitions(), 2);
+    assert_eq!(claimable_draw(&history), None);
+
+    play(&mut history, &KNIGHT_DANCE);
+    assert_eq!(history.repetitions(), 3);
+    assert_eq!(
+        claimable_draw(&history),
+        Some(DrawRule::ThreefoldRepetition)
+    );
+    assert_eq!(automatic_draw(&history), None);
+}
+
+#[test]
+fn fivefold_repetition_ends_the_game() {
+    let mut history = History::default();
+
+    for _ in 0..4 {
+        play(&mut history, &KNIGHT_DANCE);
+    }
+
+    assert_eq!(history.repetitions(), 5);
+    assert_eq!(automatic_draw(&history), Some(DrawRule::FivefoldRepetition));
+    assert_eq!(claimable_draw(&history), None);
+}
+
+#[test]
+fn pawn_moves_reset_the_repetitions() {
+    let mut history = History::default();
+
+    play(&mut history, &KNIGHT_DANCE);
+    play(&mut history, &["e2e4", "e7e5"]);
+    play(&mut history, &KNIGHT_DANCE);
+
+    assert_eq!(history.repetitions(), 2);
+}
+
+#[test]
+fn move_rules_count_half_moves() {
+    let fifty = Position::from_fen("4k3/8/8/8/8/8/8/R3K3 w - - 99 80").unwrap();
+    let mut history = History::new(fifty);
+
+    assert_eq!(claimable_draw(&history), None);
+    play(&mut history, &["a1a2"]);
+    assert_eq!(claimable_draw(&history), Some(DrawRule::FiftyMoveRule));
+
+    let seventy_five = Position::from_fen("4k3/8/8/8/8/8/8/R3K3 w - - 149 100").unwrap();
+    let mut history = History::new(seventy_five);
+
+    play(&mut history, &["a1a2"]);
+    assert_eq!(
+        automatic_draw(&history),
+        Some(DrawRule::SeventyFiveMoveRule)
+    );
+}
+
+#[test]
+fn checkmate_beats_the_seventy_five_move_rule() {
+    let position = Position::from_fen("6k1/8/6K1/8/8/8/8/R7 w - - 149 100").unwrap();
+    let mut history = History::new(position);
+
+    play(&mut history, &["a1a8"]);
+
+    assert_eq!(automatic_draw(&history), None);
+}
+
+#[test]
+fn en_passant_only_counts_when_it_can_be_played() {
+    // Black could take en passant after e2e4 here, but not in the position without the pawn on d4.
+    let with_capture = Position::from_fen("4k3/8/8/8/3pP3/8/8/4K3 b - e3 0 1").unwrap();
+    let mut without_capture = with_capture;
+    without_capture.en_passant = None;
+
+    assert_ne!(
+        zobrist::hash(&with_capture),
+        zobrist::hash(&without_capture)
+    );
+
+    let no_pawn_nearby = Position::from_fen("4k3/8/8/8/4P3/8/8/4K3 b - e3 0 1").unwrap();
+    let mut without_column = no_pawn_nearby;
+    without_column.en_passant = None;
+
+    assert_eq!(
+        zobrist::hash(&no_pawn_nearby),
+        zobrist::hash(&without_column)
+    );
+}
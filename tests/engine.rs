@@ -0,0 +1,155 @@
+use std::thread;
+use std::time::{Duration, Instant};
+
+use chess::game::engine::{uci_score, BackgroundSearch, Engine, SearchLimits, SearchResult};
+use chess::game::history::History;
+use chess::game::notation::to_uci;
+use chess::game::position::Position;
+use chess::game::uci::Score;
+
+fn search(fen: &str, depth: u32) -> SearchResult {
+    let history = History::new(Position::from_fen(fen).unwrap());
+    let limits = SearchLimits {
+        depth: Some(depth),
+        movetime: None,
+        randomness: 0,
+    };
+
+    Engine::new(1).search(&history, limits, |_| ())
+}
+
+/// Waits for a search running in the background to finish.
+fn wait_for(search: &BackgroundSearch) -> SearchResult {
+    let start = Instant::now();
+
+    loop {
+        if let Some((_, result)) = search.poll() {
+            return result;
+        }
+
+        assert!(
+            start.elapsed() < Duration::from_secs(10),
+            "the search did not finish"
+        );
+        thread::sleep(Duration::from_millis(5));
+    }
+}
+
+#[test]
+fn finds_mate_in_one() {
+    let result = search("6k1/5ppp/8/8/8/8/8/R5K1 w - - 0 1", 3);
+
+    assert_eq!(result.best_move.map(to_uci).as_deref(), Some("a1a8"));
+    assert_eq!(uci_score(result.info.score), Score::Mate(1));
+}
+
+#[test]
+fn finds_mate_in_two() {
+    // Kb6 Kb8 Rh8# or Kc7 Ka7 Ra1#, checking right away lets the king out.
+    let result = search("k7/8/2K5/8/8/8/8/7R w - - 0 1", 5);
+
+    assert!(
+        matches!(
+            result.best_move.map(to_uci).as_deref(),
+            Some("c6b6" | "c6c7")
+        ),
+        "{:?}",
+        result.best_move
+    );
+    assert_eq!(uci_score(result.info.score), Score::Mate(2));
+
+    // Seen from the player getting mated.
+    let result = search("k7/8/1K6/8/8/8/8/7R b - - 0 1", 3);
+
+    assert_eq!(result.best_move.map(to_uci).as_deref(), Some("a8b8"));
+    assert_eq!(uci_score(result.info.score), Score::Mate(-1));
+}
+
+#[test]
+fn takes_a_hanging_queen() {
+    let result = search("4k3/8/8/3q4/8/8/8/3RK3 w - - 0 1", 1);
+
+    assert_eq!(result.best_move.map(to_uci).as_deref(), Some("d1d5"));
+    assert!(result.info.score > 0, "{}", result.info.score);
+}
+
+#[test]
+fn stops_at_the_depth() {
+    let history = History::default();
+    let limits = SearchLimits {
+        depth: Some(3),
+        movetime: None,
+        randomness: 0,
+    };
+
+    let mut depths = Vec::new();
+    let result = Engine::new(1).search(&history, limits, |info| depths.push(info.depth));
+
+    assert_eq!(depths, [1, 2, 3]);
+    assert_eq!(result.info.depth, 3);
+    assert_eq!(result.info.pv.first(), result.best_move.as_ref());
+}
+
+#[test]
+fn stops_in_time() {
+    let history = History::default();
+    let limits = SearchLimits {
+        depth: None,
+        movetime: Some(Duration::from_millis(200)),
+        randomness: 0,
+    };
+
+    let start = Instant::now();
+    let result = Engine::new(1).search(&history, limits, |_| ());
+
+    assert!(result.best_move.is_some());
+    assert!(
+        start.elapsed() < Duration::from_secs(1),
+        "{:?}",
+        start.elapsed()
+    );
+}
+
+#[test]
+fn harder_levels_search_deeper_and_play_less_at_random() {
+    let levels: Vec<SearchLimits> = (1..=5).map(SearchLimits::for_level).collect();
+
+    assert_eq!(levels[0].depth, Some(1));
+    assert_eq!(levels[4].depth, None);
+    assert_eq!(SearchLimits::for_level(0), levels[0]);
+    assert_eq!(SearchLimits::for_level(9), levels[4]);
+
+    for pair in levels.windows(2) {
+        assert!(pair[0].randomness >= pair[1].randomness);
+        assert!(pair[0].movetime < pair[1].movetime);
+    }
+}
+
+#[test]
+fn background_search_hands_back_a_move() {
+    let history = History::default();
+    let limits = SearchLimits {
+        depth: Some(2),
+        movetime: None,
+        randomness: 0,
+    };
+
+    let search = BackgroundSearch::start(Engine::new(1), history, limits);
+
+    assert_eq!(search.position, Position::new());
+    assert!(wait_for(&search).best_move.is_some());
+}
+
+#[test]
+fn background_search_can_be_stopped() {
+    let history = History::default();
+
+    let search = BackgroundSearch::start(Engine::new(1), history, SearchLimits::default());
+
+    thread::sleep(Duration::from_millis(50));
+    assert!(search.poll().is_none());
+
+    search.stop();
+
+    assert!(wait_for(&search).best_move.is_some());
+}
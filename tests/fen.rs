@@ -0,0 +1,155 @@
+use chess::game::fen::{FenError, STARTING_FEN};
+use chess::game::player::Player;
+use chess::game::position::Position;
+
+fn fen_error(fen: &str) -> FenError {
+    Position::from_fen(fen).unwrap_err()
+}
+
+#[test]
+fn fen_round_trips() {
+    let fens = [
+        STARTING_FEN,
+        "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+        "rnbqkbnr/ppp1p1pp/8/3pPp2/8/8/PPPP1PPP/RNBQKBNR w KQkq f6 0 3",
+        "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 b - - 12 40",
+    ];
+
+    for fen in fens {
+        assert_eq!(Position::from_fen(fen).unwrap().to_fen(), fen);
+    }
+
+    assert_eq!(Position::from_fen(STARTING_FEN).unwrap(), Position::new());
+}
+
+#[test]
+fn clocks_can_be_left_out() {
+    let position = Position::from_fen("4k3/8/8/8/8/8/8/4K3 b - -").unwrap();
+
+    assert_eq!(position.halfmove_clock, 0);
+    assert_eq!(position.turn_count, 1);
+    assert_eq!(position.to_fen(), "4k3/8/8/8/8/8/8/4K3 b - - 0 1");
+}
+
+#[test]
+fn rejects_malformed_fields() {
+    let cases = [
+        ("4k3/8/8/8/8/8/8/4K3 w -", FenError::FieldCount(3)),
+        ("4k3/8/8/8/8/8/4K3 w - - 0 1", FenError::RowCount(7)),
+        ("4k3/8/8/8/8/8/8/4K2 w - - 0 1", FenError::RowLength(1)),
+        ("4k3/8/8/8/8/8/8/4X3 w - - 0 1", FenError::InvalidPiece('X')),
+        (
+            "4k3/8/8/8/8/8/8/4K3 x - - 0 1",
+            FenError::InvalidTurn("x".to_string()),
+        ),
+        (
+            "4k3/8/8/8/8/8/8/4K3 w - - a 1",
+            FenError::InvalidClock("a".to_string()),
+        ),
+        (
+            "4k3/8/8/8/8/8/8/4K3 w - - 0 0",
+            FenError::InvalidClock("0".to_string()),
+        ),
+    ];
+
+    for (fen, error) in cases {
+        assert_eq!(fen_error(fen), error, "{}", fen);
+    }
+}
+
+#[test]
+fn every_player_needs_one_king() {
+    assert_eq!(
+        fen_error("4k3/8/8/8/8/8/8/8 w - - 0 1"),
+        FenError::KingCount(Player::White, 0)
+    );
+    assert_eq!(
+        fen_error("4k3/8/8/8/8/8/8/3KK3 w - - 0 1"),
+        FenError::KingCount(Player::White, 2)
+    );
+    assert_eq!(
+        fen_error("4kk2/8/8/8/8/8/8/4K3 w - - 0 1"),
+        FenError::KingCount(Player::Black, 2)
+    );
+}
+
+#[test]
+fn rejects_pieces_that_cannot_be_there() {
+    assert_eq!(
+        fen_error("4k3/pppppppp/p7/8/8/8/8/4K3 w - - 0 1"),
+        FenError::TooManyPieces(Player::Black)
+    );
+    // Eight pawns and a second queen are one promotion too many.
+    assert_eq!(
+        fen_error("3qk3/pppppppp/8/8/8/8/8/q3K3 w - - 0 1"),
+        FenError::TooManyPieces(Player::Black)
+    );
+    assert_eq!(
+        fen_error("4k2P/8/8/8/8/8/8/4K3 w - - 0 1"),
+        FenError::PawnOnBackRank
+    );
+    assert_eq!(
+        fen_error("4k3/8/8/8/8/8/8/p3K3 w - - 0 1"),
+        FenError::PawnOnBackRank
+    );
+    // White to move cannot have left Black in check.
+    assert_eq!(
+        fen_error("4k3/8/8/8/8/8/8/K3R3 w - - 0 1"),
+        FenError::OpponentInCheck
+    );
+}
+
+#[test]
+fn castling_rights_need_the_king_and_rook() {
+    // The rook on h1 is missing.
+    assert_eq!(
+        fen_error("r3k2r/8/8/8/8/8/8/R3K3 w KQkq - 0 1"),
+        FenError::CastlingMismatch('K')
+    );
+    // The white king has left the back rank.
+    assert_eq!(
+        fen_error("r3k2r/8/8/8/8/8/4K3/R6R w KQkq - 0 1"),
+        FenError::CastlingMismatch('K')
+    );
+    assert_eq!(
+        fen_error("r3k2r/8/8/8/8/8/8/R3K2R w KQkx - 0 1"),
+        FenError::InvalidCastling("KQkx".to_string())
+    );
+    assert_eq!(
+        fen_error("r3k2r/8/8/8/8/8/8/R3K2R w KKkq - 0 1"),
+        FenError::InvalidCastling("KKkq".to_string())
+    );
+
+    let position = Position::from_fen("r3k2r/8/8/8/8/8/8/R3K2R w Kq - 0 1").unwrap();
+
+    assert_eq!(position.to_fen(), "r3k2r/8/8/8/8/8/8/R3K2R w Kq - 0 1");
+}
+
+#[test]
+fn en_passant_needs_a_pawn_that_just_moved_two_squares() {
+    assert!(
+        Position::from_fen("rnbqkbnr/pppp1ppp/8/8/4Pp2/8/PPPP2PP/RNBQKBNR b KQkq e3 0 3").is_ok()
+    );
+
+    let cases = [
+        // No pawn in front of the square.
+        "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR b KQkq e3 0 1",
+        // The square is on the wrong side for the player to move.
+        "rnbqkbnr/pppp1ppp/8/8/4Pp2/8/PPPP2PP/RNBQKBNR b KQkq e6 0 3",
+        // The pawn could not have come from its starting square, there is a pawn on it.
+        "rnbqkbnr/pppp1ppp/8/8/4Pp2/8/PPPPP1PP/RNBQKBNR b KQkq e3 0 3",
+    ];
+
+    for fen in cases {
+        assert!(
+            matches!(fen_error(fen), FenError::EnPassantMismatch(_)),
+            "{}",
+            fen
+        );
+    }
+
+    assert_eq!(
+        fen_error("4k3/8/8/8/8/8/8/4K3 w - e9 0 1"),
+        FenError::InvalidEnPassant("e9".to_string())
+    );
+}
@@ -0,0 +1,100 @@
+mod common;
+
+use chess::game::history::History;
+use chess::game::notation::from_uci;
+use chess::game::position::Position;
+use chess::game::zobrist;
+
+use common::play;
+
+/// Plays the move, takes it back and makes it again,
+/// checking that both the position before and after it come back exactly.
+fn check_undo(fen: &str, uci: &str) {
+    let mut history = History::new(Position::from_fen(fen).unwrap());
+    let before = *history.current();
+
+    play(&mut history, &[uci]);
+    let after = history.clone();
+
+    assert_eq!(history.undo(), Some(after.moves[0]));
+    assert_eq!(history.current().to_fen(), fen);
+    assert_eq!(*history.current(), before);
+    assert_eq!(history.keys, [zobrist::hash(&before)]);
+    assert!(history.notations.is_empty());
+
+    assert_eq!(history.redo(), Some(after.moves[0]));
+    assert_eq!(history.positions, after.positions);
+    assert_eq!(history.keys, after.keys);
+    assert_eq!(history.notations, after.notations);
+    assert!(history.undone.is_empty());
+}
+
+#[test]
+fn undoing_a_capture_brings_the_piece_back() {
+    check_undo(
+        "rnbqkbnr/ppp1pppp/8/3p4/4P3/8/PPPP1PPP/RNBQKBNR w KQkq d6 0 2",
+        "e4d5",
+    );
+}
+
+#[test]
+fn undoing_a_promotion_brings_the_pawn_back() {
+    check_undo("1r2k3/P7/8/8/8/8/8/4K3 w - - 3 40", "a7b8q");
+    check_undo("4k3/P7/8/8/8/8/8/4K3 w - - 0 1", "a7a8n");
+}
+
+#[test]
+fn undoing_en_passant_brings_the_pawn_back() {
+    check_undo(
+        "rnbqkbnr/ppp1p1pp/8/3pPp2/8/8/PPPP1PPP/RNBQKBNR w KQkq f6 0 3",
+        "e5f6",
+    );
+}
+
+#[test]
+fn undoing_castling_brings_the_rights_back() {
+    check_undo("r3k2r/8/8/8/8/8/8/R3K2R w KQkq - 0 1", "e1g1");
+    check_undo("r3k2r/8/8/8/8/8/8/R3K2R b KQkq - 0 1", "e8c8");
+}
+
+#[test]
+fn playing_the_undone_move_keeps_the_rest_to_redo() {
+    let mut history = History::default();
+
+    play(&mut history, &["e2e4", "e7e5", "g1f3"]);
+    history.undo();
+    history.undo();
+    history.undo();
+    assert_eq!(history.undone.len(), 3);
+
+    play(&mut history, &["e2e4"]);
+    assert_eq!(history.undone.len(), 2);
+
+    assert_eq!(history.redo(), from_uci(&history.positions[1], "e7e5"));
+    assert_eq!(history.redo(), from_uci(&history.positions[2], "g1f3"));
+    assert_eq!(history.redo(), None);
+    assert_eq!(history.notations, ["e4", "e5", "Nf3"]);
+}
+
+#[test]
+fn playing_a_different_move_drops_the_moves_to_redo() {
+    let mut history = History::default();
+
+    play(&mut history, &["e2e4", "e7e5", "g1f3"]);
+    history.undo();
+    history.undo();
+
+    play(&mut history, &["c7c5"]);
+    assert!(history.undone.is_empty());
+    assert_eq!(history.redo(), None);
+    assert_eq!(history.notations, ["e4", "c5"]);
+}
+
+#[test]
+fn nothing_to_undo_at_the_start() {
+    let mut history = History::default();
+
+    assert_eq!(history.undo(), None);
+    assert_eq!(history.redo(), None);
+    assert_eq!(history.positions, [Position::new()]);
+}
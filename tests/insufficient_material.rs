@@ -0,0 +1,52 @@
+use chess::game::check::{insufficient_material, is_tie};
+use chess::game::position::Position;
+
+#[test]
+fn insufficient_material_for_every_combination() {
+    // The FEN, and whether neither player can checkmate anymore.
+    let cases = [
+        // Kings only, or one minor piece.
+        ("4k3/8/8/8/8/8/8/4K3 w - - 0 1", true),
+        ("4k3/8/8/8/8/8/8/3NK3 w - - 0 1", true),
+        ("4k3/8/8/8/8/8/8/3BK3 w - - 0 1", true),
+        ("3nk3/8/8/8/8/8/8/4K3 w - - 0 1", true),
+        ("3bk3/8/8/8/8/8/8/4K3 w - - 0 1", true),
+        // Bishops on one color, however many and on whichever side.
+        ("2b1k3/8/8/8/8/8/8/3BK3 w - - 0 1", true),
+        ("4k3/8/8/8/8/8/8/B1B1K3 w - - 0 1", true),
+        ("1b2k3/8/8/8/8/8/8/B1B1K3 w - - 0 1", true),
+        ("b1b1k1b1/8/8/8/8/8/8/4K3 w - - 0 1", true),
+        // Bishops on both colors.
+        ("3bk3/8/8/8/8/8/8/3BK3 w - - 0 1", false),
+        ("4k3/8/8/8/8/8/8/2BBK3 w - - 0 1", false),
+        ("2bbk3/8/8/8/8/8/8/4K3 w - - 0 1", false),
+        // Knights, which can mate with help, alone or next to another minor piece.
+        ("4k3/8/8/8/8/8/8/2NNK3 w - - 0 1", false),
+        ("2nnk3/8/8/8/8/8/8/4K3 w - - 0 1", false),
+        ("3nk3/8/8/8/8/8/8/3NK3 w - - 0 1", false),
+        ("3nk3/8/8/8/8/8/8/3BK3 w - - 0 1", false),
+        ("3bk3/8/8/8/8/8/8/3NK3 w - - 0 1", false),
+        ("4k3/8/8/8/8/8/8/2NBK3 w - - 0 1", false),
+        // Pawns, rooks and queens.
+        ("4k3/8/8/8/8/8/4P3/4K3 w - - 0 1", false),
+        ("4k3/4p3/8/8/8/8/8/4K3 w - - 0 1", false),
+        ("4k3/8/8/8/8/8/8/R3K3 w - - 0 1", false),
+        ("r3k3/8/8/8/8/8/8/4K3 w - - 0 1", false),
+        ("4k3/8/8/8/8/8/8/3QK3 w - - 0 1", false),
+        ("3qk3/8/8/8/8/8/8/4K3 w - - 0 1", false),
+        ("3bk3/8/8/8/8/8/4P3/3BK3 w - - 0 1", false),
+        ("4k3/8/8/8/8/8/8/2RBK3 w - - 0 1", false),
+        // The starting position.
+        (
+            "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+            false,
+        ),
+    ];
+
+    for (fen, insufficient) in cases {
+        let position = Position::from_fen(fen).unwrap();
+
+        assert_eq!(insufficient_material(&position), insufficient, "{}", fen);
+        assert_eq!(is_tie(&position), insufficient, "{}", fen);
+    }
+}
@@ -0,0 +1,101 @@
+use chess::game::notation::{from_san, from_uci, to_san, SanError};
+use chess::game::pieces::PieceKind;
+use chess::game::position::Position;
+
+/// Checks the SAN of every move given in UCI, and that the SAN reads back as the same move.
+fn check_san(fen: &str, moves: &[(&str, &str)]) {
+    let position = Position::from_fen(fen).unwrap();
+
+    for (uci, san) in moves {
+        let mv = from_uci(&position, uci).unwrap();
+
+        assert_eq!(to_san(&position, mv), *san, "{} in {}", uci, fen);
+        assert_eq!(from_san(&position, san), Ok(mv), "{} in {}", san, fen);
+    }
+}
+
+#[test]
+fn disambiguates_by_file() {
+    check_san(
+        "4k3/8/8/8/8/5N2/8/1N2K3 w - - 0 1",
+        &[("b1d2", "Nbd2"), ("f3d2", "Nfd2"), ("f3g5", "Ng5")],
+    );
+}
+
+#[test]
+fn disambiguates_by_rank() {
+    check_san(
+        "4k3/8/8/R7/8/8/8/R3K3 w - - 0 1",
+        &[("a1a3", "R1a3"), ("a5a3", "R5a3"), ("a1b1", "Rb1")],
+    );
+}
+
+#[test]
+fn disambiguates_by_square() {
+    // The queen on a1 shares its file with a3 and its rank with c1.
+    check_san(
+        "4k3/8/8/8/8/Q7/8/Q1Q1K3 w - - 0 1",
+        &[("a1b2", "Qa1b2"), ("c1b2", "Qcb2"), ("a3b2", "Q3b2")],
+    );
+}
+
+#[test]
+fn ambiguous_moves_are_rejected() {
+    let position = Position::from_fen("4k3/8/8/8/8/Q7/8/Q1Q1K3 w - - 0 1").unwrap();
+
+    assert_eq!(from_san(&position, "Qb2"), Err(SanError::Ambiguous));
+    assert_eq!(from_san(&position, "Qab2"), Err(SanError::Ambiguous));
+    assert_eq!(from_san(&position, "Q1b2"), Err(SanError::Ambiguous));
+    assert_eq!(from_san(&position, "Nf3"), Err(SanError::Illegal));
+    assert_eq!(from_san(&position, "Zb2"), Err(SanError::Invalid));
+}
+
+#[test]
+fn castling_can_give_check() {
+    check_san("5k2/8/8/8/8/8/8/4K2R w K - 0 1", &[("e1g1", "O-O+")]);
+    check_san("3k4/8/8/8/8/8/8/R3K3 w Q - 0 1", &[("e1c1", "O-O-O+")]);
+    check_san(
+        "r3k2r/8/8/8/8/8/8/4K3 b kq - 0 1",
+        &[("e8g8", "O-O"), ("e8c8", "O-O-O")],
+    );
+
+    let position = Position::from_fen("5k2/8/8/8/8/8/8/4K2R w K - 0 1").unwrap();
+
+    assert_eq!(from_san(&position, "0-0"), from_san(&position, "O-O+"));
+}
+
+#[test]
+fn promotions_round_trip() {
+    check_san(
+        "r3k3/1P6/8/8/8/8/8/4K3 w - - 0 1",
+        &[
+            ("b7b8q", "b8=Q+"),
+            ("b7b8r", "b8=R+"),
+            ("b7b8b", "b8=B"),
+            ("b7b8n", "b8=N"),
+            ("b7a8q", "bxa8=Q+"),
+            ("b7a8n", "bxa8=N"),
+        ],
+    );
+
+    let position = Position::from_fen("r3k3/1P6/8/8/8/8/8/4K3 w - - 0 1").unwrap();
+    let mv = from_san(&position, "b8Q").unwrap();
+
+    assert_eq!(mv.promotion, Some(PieceKind::Queen));
+    assert_eq!(from_san(&position, "b8"), Err(SanError::Illegal));
+}
+
+#[test]
+fn pawn_captures_name_their_file() {
+    check_san(
+        "4k3/8/8/3pP3/8/8/8/4K3 w - d6 0 1",
+        &[("e5d6", "exd6"), ("e5e6", "e6")],
+    );
+
+    let position = Position::from_fen("4k3/8/8/3pP3/8/8/8/4K3 w - d6 0 1").unwrap();
+
+    assert_eq!(
+        from_san(&position, "exd6 e.p."),
+        from_san(&position, "exd6")
+    );
+}
@@ -0,0 +1,151 @@
+use std::process::Command;
+
+use chess::game::perft::{divide, perft};
+use chess::game::position::Position;
+
+/// Checks the perft counts of a position, from depth 1 upwards.
+fn check_perft(fen: &str, counts: &[u64]) {
+    let position = Position::from_fen(fen).unwrap();
+
+    for (depth, count) in counts.iter().enumerate() {
+        assert_eq!(
+            perft(&position, depth + 1),
+            *count,
+            "perft({}) of {}",
+            depth + 1,
+            fen
+        );
+    }
+}
+
+#[test]
+fn perft_startpos() {
+    check_perft(
+        "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+        &[20, 400, 8902, 197281, 4865609],
+    );
+}
+
+#[test]
+fn perft_kiwipete() {
+    check_perft(
+        "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+        &[48, 2039, 97862, 4085603],
+    );
+}
+
+#[test]
+fn perft_position_3() {
+    check_perft(
+        "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1",
+        &[14, 191, 2812, 43238, 674624],
+    );
+}
+
+#[test]
+fn perft_position_4() {
+    check_perft(
+        "r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 w kq - 0 1",
+        &[6, 264, 9467, 422333],
+    );
+}
+
+#[test]
+fn perft_position_4_mirrored() {
+    check_perft(
+        "r2q1rk1/pP1p2pp/Q4n2/bbp1p3/Np6/1B3NBn/pPPP1PPP/R3K2R b KQ - 0 1",
+        &[6, 264, 9467, 422333],
+    );
+}
+
+#[test]
+fn perft_position_5() {
+    check_perft(
+        "rnbq1k1r/pp1Pbppp/2p5/8/2B5/8/PPP1NnPP/RNBQK2R w KQ - 1 8",
+        &[44, 1486, 62379, 2103487],
+    );
+}
+
+#[test]
+fn perft_position_6() {
+    check_perft(
+        "r4rk1/1pp1qppp/p1np1n2/2b1p1B1/2B1P1b1/P1NP1N2/1PP1QPPP/R4RK1 w - - 0 10",
+        &[46, 2079, 89890, 3894594],
+    );
+}
+
+/// Positions with pins, discovered checks and other edge cases that move generators like to get wrong.
+#[test]
+fn perft_tricky_positions() {
+    let positions = [
+        // En passant that would expose the king.
+        ("8/8/1k6/2b5/2pP4/8/5K2/8 b - d3 0 1", 6, 1440467),
+        ("8/5bk1/8/2Pp4/8/1K6/8/8 w - d6 0 1", 6, 824064),
+        ("3k4/3p4/8/K1P4r/8/8/8/8 b - - 0 1", 6, 1134888),
+        // Discovered checks.
+        ("8/8/4k3/8/2p5/8/B2P2K1/8 w - - 0 1", 6, 1015133),
+        ("8/8/2k5/5q2/5n2/8/5K2/8 b - - 0 1", 4, 23527),
+        // Castling that gives check, loses the rights or is not allowed.
+        ("5k2/8/8/8/8/8/8/4K2R w K - 0 1", 6, 661072),
+        ("r3k2r/1b4bq/8/8/8/8/7B/R3K2R w KQkq - 0 1", 4, 1274206),
+        ("r3k2r/8/3Q4/8/8/5q2/8/R3K2R b KQkq - 0 1", 4, 1720476),
+        // Promotions, underpromotions and stalemates.
+        ("2K2r2/4P3/8/8/8/8/8/3k4 w - - 0 1", 6, 3821001),
+        ("8/8/1P2K3/8/2n5/1q6/8/5k2 b - - 0 1", 5, 1004658),
+        ("4k3/1P6/8/8/8/8/K7/8 w - - 0 1", 6, 217342),
+        ("8/P1k5/K7/8/8/8/8/8 w - - 0 1", 6, 92683),
+        ("K1k5/8/P7/8/8/8/8/8 w - - 0 1", 6, 2217),
+        ("8/k1P5/8/1K6/8/8/8/8 w - - 0 1", 7, 567584),
+    ];
+
+    for (fen, depth, count) in positions {
+        let position = Position::from_fen(fen).unwrap();
+
+        assert_eq!(
+            perft(&position, depth),
+            count,
+            "perft({}) of {}",
+            depth,
+            fen
+        );
+    }
+}
+
+#[test]
+fn en_passant_needs_the_pawn_next_to_it() {
+    // The en passant column is set, but there is no black pawn beside the white one to capture.
+    let mut position = Position::from_fen("4k3/8/8/4P3/8/8/8/4K3 w - - 0 1").unwrap();
+    position.en_passant = Some(3);
+
+    assert_eq!(perft(&position, 1), 6);
+}
+
+#[test]
+fn divide_adds_up_to_perft() {
+    let position = Position::new();
+    let total: u64 = divide(&position, 3).iter().map(|(_, nodes)| nodes).sum();
+
+    assert_eq!(divide(&position, 3).len(), 20);
+    assert_eq!(total, perft(&position, 3));
+}
+
+#[test]
+fn depth_0_is_only_the_position_itself() {
+    let position = Position::new();
+
+    assert_eq!(perft(&position, 0), 1);
+    assert!(divide(&position, 0).is_empty());
+
+    // The command lists the moves, so it wants at least one of them.
+    let perft_command = |depth: &str| {
+        let output = Command::new(env!("CARGO_BIN_EXE_chess"))
+            .args(["perft", depth])
+            .output()
+            .unwrap();
+
+        String::from_utf8(output.stdout).unwrap()
+    };
+
+    assert_eq!(perft_command("0"), "The depth has to be at least 1.\n");
+    assert!(perft_command("1").contains("Nodes searched: 20\n"));
+}
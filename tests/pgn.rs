@@ -0,0 +1,210 @@
+mod common;
+
+use std::time::Duration;
+
+use chess::game::history::History;
+use chess::game::pgn::{parse_pgn, seven_tag_roster, write_pgn};
+use chess::game::position::Position;
+
+use common::play;
+
+#[test]
+fn castling_can_be_written_with_zeros() {
+    let pgn = "1. e4 e5 2. Nf3 Nc6 3. Bc4 d6 4. 0-0 Bg4 5. d3 Qd7 6. Nc3 0-0-0!? 7.h3 *";
+    let games = parse_pgn(pgn).unwrap();
+    let game = &games[0];
+
+    assert_eq!(game.moves[6].san, "0-0");
+    assert_eq!(game.moves[11].san, "0-0-0");
+    assert_eq!(game.moves[11].nags, [5]);
+
+    let history = game.history();
+
+    assert_eq!(history.notations[6], "O-O");
+    assert_eq!(history.notations[11], "O-O-O");
+    assert_eq!(history.notations[12], "h3");
+}
+
+#[test]
+fn games_round_trip() {
+    let mut history = History::default();
+
+    play(
+        &mut history,
+        &[
+            "e2e4", "c7c5", "g1f3", "d7d6", "f1b5", "c8d7", "b5d7", "d8d7", "e1g1",
+        ],
+    );
+
+    let tags = [
+        ("White".to_string(), "Someone".to_string()),
+        ("Result".to_string(), "*".to_string()),
+    ];
+    let pgn = write_pgn(&tags, &history, &[]);
+
+    assert!(pgn.contains("[White \"Someone\"]"));
+    assert!(pgn.contains("1. e4 c5 2. Nf3 d6 3. Bb5+ Bd7 4. Bxd7+ Qxd7 5. O-O *"));
+
+    let games = parse_pgn(&pgn).unwrap();
+
+    assert_eq!(games.len(), 1);
+    assert_eq!(games[0].tag("White"), Some("Someone"));
+    assert_eq!(games[0].result, "*");
+    assert_eq!(games[0].history().current(), history.current());
+}
+
+#[test]
+fn reads_comments_variations_and_several_games() {
+    let pgn = "[Event \"First\"]\n\n1.e4 {best by test} e5 (1...c5 2.Nf3) 2.Nf3 $1 1-0\n\n\
+               [Event \"Second\"]\n\n1. d4 d5 0-1\n";
+    let games = parse_pgn(pgn).unwrap();
+
+    assert_eq!(games.len(), 2);
+    assert_eq!(games[0].tag("Event"), Some("First"));
+    assert_eq!(games[0].moves[0].comments, ["best by test"]);
+    assert_eq!(games[0].moves[1].variations[0].len(), 2);
+    assert_eq!(games[0].moves[2].nags, [1]);
+    assert_eq!(games[0].result, "1-0");
+    assert_eq!(games[1].tag("Event"), Some("Second"));
+    assert_eq!(games[1].moves.len(), 2);
+    assert_eq!(games[1].result, "0-1");
+}
+
+#[test]
+fn reads_nested_variations_with_comments_and_glyphs() {
+    let pgn = "{Opening} 1. e4 $1 (1. d4 d5 (1... Nf6 {Indian} 2. c4 $2) 2. c4!?) \
+               1... e5 ; to the end of the line\n2. Nf3 *";
+    let games = parse_pgn(pgn).unwrap();
+    let game = &games[0];
+
+    assert_eq!(game.comments, ["Opening"]);
+    assert_eq!(game.moves.len(), 3);
+    assert_eq!(game.moves[0].nags, [1]);
+    assert_eq!(game.moves[1].comments, ["to the end of the line"]);
+
+    let variation = &game.moves[0].variations[0];
+
+    assert_eq!(variation.len(), 3);
+    assert_eq!(variation[0].san, "d4");
+    assert_eq!(variation[2].nags, [5]);
+
+    let nested = &variation[1].variations[0];
+
+    assert_eq!(nested.len(), 2);
+    assert_eq!(nested[0].san, "Nf6");
+    assert_eq!(nested[0].comments, ["Indian"]);
+    assert_eq!(nested[1].nags, [2]);
+}
+
+#[test]
+fn errors_tell_the_game_ply_and_token() {
+    let pgn = "[Event \"First\"]\n\n1. e4 e5 1-0\n\n[Event \"Second\"]\n\n1. e4 e5 2. Ke3 *\n";
+    let error = parse_pgn(pgn).unwrap_err();
+
+    assert_eq!(error.game, 2);
+    assert_eq!(error.line, 7);
+    assert_eq!(error.ply, Some(3));
+    assert_eq!(error.token, "Ke3");
+
+    let error = parse_pgn("1. e4 e5 2. Nf3 ) *").unwrap_err();
+
+    assert_eq!(error.message, "unexpected end of variation");
+    assert_eq!(error.ply, Some(4));
+    assert_eq!(error.token, ")");
+
+    let error = parse_pgn("1. e4 (1. d4 d5 *").unwrap_err();
+
+    assert_eq!(error.message, "unterminated variation");
+}
+
+#[test]
+fn reading_errors_tell_the_game_they_are_in() {
+    let first = "[Event \"First\"]\n\n1. e4 e5 1-0\n\n";
+
+    let broken_comment = format!("{}[Event \"Second\"]\n\n1. d4 {{ never closed\n", first);
+    let error = parse_pgn(&broken_comment).unwrap_err();
+
+    assert_eq!(error.game, 2);
+    assert_eq!(error.line, 7);
+    assert_eq!(error.message, "unterminated comment");
+
+    let broken_tag = format!("{}{}[Event \"Third\"\n", first, first);
+    let error = parse_pgn(&broken_tag).unwrap_err();
+
+    assert_eq!(error.game, 3);
+    assert_eq!(error.line, 9);
+    assert_eq!(error.message, "unterminated tag");
+
+    // Games without tags are told apart by their results.
+    let error = parse_pgn("1. e4 e5 1-0\n1. d4 d5 0-1\n1. c4 e5 2x. Nf3 *").unwrap_err();
+
+    assert_eq!(error.game, 3);
+    assert_eq!(error.line, 3);
+    assert_eq!(error.token, "2x.");
+
+    // And by the tags of the next game, when the result is missing.
+    let error = parse_pgn("1. e4 e5\n[Event \"Second\"]\n1. d4 d5 $x *").unwrap_err();
+
+    assert_eq!(error.game, 2);
+    assert_eq!(error.token, "$x");
+}
+
+#[test]
+fn exports_the_seven_tag_roster_and_wrapped_movetext() {
+    let mut history = History::default();
+
+    for _ in 0..10 {
+        play(&mut history, &["g1f3", "g8f6", "f3g1", "f6g8"]);
+    }
+
+    let tags = seven_tag_roster("White player", "Black player", "2022.05.31", "1/2-1/2");
+    let times: Vec<Duration> = (0..history.len() as u64)
+        .map(|i| Duration::from_secs(300 - i))
+        .collect();
+    let pgn = write_pgn(&tags, &history, &times);
+
+    let (header, movetext) = pgn.split_once("\n\n").unwrap();
+    let names: Vec<&str> = header
+        .lines()
+        .map(|line| line[1..].split(' ').next().unwrap())
+        .collect();
+
+    assert_eq!(
+        names,
+        ["Event", "Site", "Date", "Round", "White", "Black", "Result"]
+    );
+    assert!(header.contains("[Result \"1/2-1/2\"]"));
+
+    // Black moves get their own number after the clock comment of White's move.
+    assert!(movetext.starts_with("1. Nf3 {[%clk 0:05:00]} 1... Nf6 {[%clk 0:04:59]} 2. Ng1"));
+    let words: Vec<&str> = movetext.split_whitespace().collect();
+    assert!(words
+        .join(" ")
+        .ends_with("20... Ng8 {[%clk 0:04:21]} 1/2-1/2"));
+
+    assert!(movetext.lines().count() > 5);
+
+    for line in movetext.lines() {
+        assert!(line.len() <= 80, "{}", line);
+        assert_eq!(line.trim(), line);
+    }
+
+    assert_eq!(parse_pgn(&pgn).unwrap()[0].history(), history);
+}
+
+#[test]
+fn exports_black_move_numbers_only_where_needed() {
+    let fen = "rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq e3 0 1";
+    let mut history = History::new(Position::from_fen(fen).unwrap());
+
+    play(&mut history, &["e7e5", "g1f3", "b8c6"]);
+
+    let tags = seven_tag_roster("?", "?", "????.??.??", "*");
+    let pgn = write_pgn(&tags, &history, &[]);
+
+    assert!(pgn.contains(&format!(
+        "[SetUp \"1\"]\n[FEN \"{}\"]\n",
+        history.start().to_fen()
+    )));
+    assert!(pgn.ends_with("\n\n1... e5 2. Nf3 Nc6 *\n\n"), "{}", pgn);
+}
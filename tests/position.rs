@@ -0,0 +1,199 @@
+use chess::game::moves::{Move, MoveKind};
+use chess::game::pieces::PieceKind;
+use chess::game::position::{IllegalMove, Position};
+
+/// Turns a square like "e4" into (row, column), row 0 being the 8th rank.
+fn square(name: &str) -> (usize, usize) {
+    let bytes = name.as_bytes();
+
+    ((b'8' - bytes[1]) as usize, (bytes[0] - b'a') as usize)
+}
+
+fn mv(from: &str, to: &str) -> Move {
+    Move::new(square(from), square(to))
+}
+
+fn castle(from: &str, rook: &str) -> Move {
+    Move {
+        kind: MoveKind::Castle,
+        ..mv(from, rook)
+    }
+}
+
+fn apply(fen: &str, mv: Move) -> Result<String, IllegalMove> {
+    Position::from_fen(fen)
+        .unwrap()
+        .apply_move(mv)
+        .map(|position| position.to_fen())
+}
+
+#[test]
+fn legal_moves_make_a_new_position() {
+    let start = Position::new();
+    let double_push = Move {
+        kind: MoveKind::DoublePush,
+        ..mv("e2", "e4")
+    };
+
+    let after = start.apply_move(double_push).unwrap();
+
+    assert_eq!(
+        after.to_fen(),
+        "rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq e3 0 1"
+    );
+    assert_eq!(start, Position::new());
+
+    let after = after.apply_move(mv("g8", "f6")).unwrap();
+
+    assert_eq!(
+        after.to_fen(),
+        "rnbqkb1r/pppppppp/5n2/8/4P3/8/PPPP1PPP/RNBQKBNR w KQkq - 1 2"
+    );
+}
+
+#[test]
+fn illegal_moves_tell_why() {
+    let start = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1";
+
+    let off_the_board = Move::new(square("e2"), (8, 4));
+
+    assert_eq!(apply(start, off_the_board), Err(IllegalMove::OutOfBounds));
+    assert_eq!(apply(start, mv("e3", "e4")), Err(IllegalMove::NoPiece));
+    assert_eq!(apply(start, mv("e7", "e5")), Err(IllegalMove::WrongTurn));
+    assert_eq!(
+        apply(start, mv("e2", "e5")),
+        Err(IllegalMove::InvalidDestination)
+    );
+    assert_eq!(
+        apply(start, mv("g1", "e2")),
+        Err(IllegalMove::InvalidDestination)
+    );
+    // Moving two squares is a double push, not a normal move.
+    assert_eq!(apply(start, mv("e2", "e4")), Err(IllegalMove::WrongKind));
+}
+
+#[test]
+fn the_king_cannot_be_left_in_check() {
+    // Moving into the rook's line.
+    let fen = "4r1k1/8/8/8/8/8/8/3K4 w - - 0 1";
+
+    assert_eq!(apply(fen, mv("d1", "e1")), Err(IllegalMove::KingInCheck));
+    assert_eq!(apply(fen, mv("d1", "e2")), Err(IllegalMove::KingInCheck));
+    assert!(apply(fen, mv("d1", "d2")).is_ok());
+
+    // The rook is pinned to the king, it can only move along the pin.
+    let fen = "4r1k1/8/8/8/8/8/4R3/4K3 w - - 0 1";
+
+    assert_eq!(apply(fen, mv("e2", "d2")), Err(IllegalMove::KingInCheck));
+    assert!(apply(fen, mv("e2", "e5")).is_ok());
+
+    // Any move has to get out of check.
+    let fen = "4r1k1/8/8/8/8/8/P7/4K3 w - - 0 1";
+
+    assert_eq!(apply(fen, mv("a2", "a3")), Err(IllegalMove::KingInCheck));
+    assert!(apply(fen, mv("e1", "d1")).is_ok());
+}
+
+#[test]
+fn castling_through_or_out_of_check_is_illegal() {
+    // The rook on f8 covers f1, which the king passes.
+    let fen = "5rk1/8/8/8/8/8/8/R3K2R w KQ - 0 1";
+
+    assert_eq!(
+        apply(fen, castle("e1", "h1")),
+        Err(IllegalMove::KingInCheck)
+    );
+    assert_eq!(
+        apply(fen, castle("e1", "a1")).unwrap(),
+        "5rk1/8/8/8/8/8/8/2KR3R b - - 1 1"
+    );
+
+    // The king may not castle out of check either.
+    let fen = "4r1k1/8/8/8/8/8/8/R3K2R w KQ - 0 1";
+
+    assert_eq!(
+        apply(fen, castle("e1", "h1")),
+        Err(IllegalMove::KingInCheck)
+    );
+    assert_eq!(
+        apply(fen, castle("e1", "a1")),
+        Err(IllegalMove::KingInCheck)
+    );
+
+    // Or into it.
+    let fen = "6rk/7p/8/8/8/8/8/R3K2R w KQ - 0 1";
+
+    assert_eq!(
+        apply(fen, castle("e1", "h1")),
+        Err(IllegalMove::KingInCheck)
+    );
+
+    let fen = "6k1/8/8/8/8/8/8/R3K2R w KQ - 0 1";
+
+    assert_eq!(
+        apply(fen, castle("e1", "h1")).unwrap(),
+        "6k1/8/8/8/8/8/8/R4RK1 b - - 1 1"
+    );
+}
+
+#[test]
+fn promotions_take_the_piece_of_the_move() {
+    let fen = "1r2k3/P7/8/8/8/8/8/4K3 w - - 0 1";
+
+    for (piece, letter) in [
+        (PieceKind::Queen, 'Q'),
+        (PieceKind::Rook, 'R'),
+        (PieceKind::Bishop, 'B'),
+        (PieceKind::Knight, 'N'),
+    ] {
+        assert_eq!(
+            apply(fen, mv("a7", "a8").with_promotion(piece)).unwrap(),
+            format!("{}r2k3/8/8/8/8/8/8/4K3 b - - 0 1", letter)
+        );
+        assert_eq!(
+            apply(fen, mv("a7", "b8").with_promotion(piece)).unwrap(),
+            format!("1{}2k3/8/8/8/8/8/8/4K3 b - - 0 1", letter)
+        );
+    }
+
+    // The knight gives check where a queen would not.
+    let position = Position::from_fen("8/3P4/4k3/8/8/8/8/4K3 w - - 0 1").unwrap();
+    let knight = position
+        .apply_move(mv("d7", "d8").with_promotion(PieceKind::Knight))
+        .unwrap();
+    let queen = position
+        .apply_move(mv("d7", "d8").with_promotion(PieceKind::Queen))
+        .unwrap();
+
+    assert_eq!(knight.piece_at(square("d8")), 'N');
+    assert!(knight.in_check());
+    assert!(!queen.in_check());
+}
+
+#[test]
+fn promotions_need_a_promotion_piece() {
+    let fen = "1r2k3/P7/8/8/8/8/8/4K3 w - - 0 1";
+
+    assert_eq!(
+        apply(fen, mv("a7", "a8")),
+        Err(IllegalMove::MissingPromotion)
+    );
+    assert_eq!(
+        apply(fen, mv("a7", "b8")),
+        Err(IllegalMove::MissingPromotion)
+    );
+    assert_eq!(
+        apply(fen, mv("a7", "a8").with_promotion(PieceKind::King)),
+        Err(IllegalMove::InvalidPromotion)
+    );
+    assert_eq!(
+        apply(fen, mv("a7", "a8").with_promotion(PieceKind::Pawn)),
+        Err(IllegalMove::InvalidPromotion)
+    );
+
+    // Only moves to the last row promote.
+    assert_eq!(
+        apply(fen, mv("e1", "e2").with_promotion(PieceKind::Queen)),
+        Err(IllegalMove::InvalidPromotion)
+    );
+}
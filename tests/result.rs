@@ -0,0 +1,170 @@
+mod common;
+
+use std::time::{Duration, SystemTime};
+
+use chess::game::check::{game_result, DrawRule};
+use chess::game::clock::{TimeBonus, TimeControl};
+use chess::game::history::History;
+use chess::game::pgn::{game_tags, write_pgn};
+use chess::game::player::Player;
+use chess::game::position::Position;
+use chess::game::result::{computer_accepts_draw, DrawOffer, GameResult};
+
+use common::play;
+
+#[test]
+fn checkmate_ends_the_game() {
+    let mut history = History::default();
+
+    play(&mut history, &["f2f3", "e7e5", "g2g4"]);
+    assert_eq!(game_result(&history), None);
+
+    play(&mut history, &["d8h4"]);
+    let result = game_result(&history).unwrap();
+
+    assert_eq!(
+        result,
+        GameResult::Checkmate {
+            winner: Player::Black
+        }
+    );
+    assert_eq!(result.tag(), "0-1");
+    assert_eq!(result.termination(), "normal");
+    assert_eq!(result.to_string(), "Black wins by checkmate");
+}
+
+#[test]
+fn results_for_the_pgn_tags() {
+    // The result, its Result tag and its Termination tag.
+    let cases = [
+        (
+            GameResult::Timeout {
+                winner: Player::White,
+            },
+            "1-0",
+            "time forfeit",
+        ),
+        (
+            GameResult::Resignation {
+                winner: Player::Black,
+            },
+            "0-1",
+            "normal",
+        ),
+        (GameResult::Draw(DrawRule::Stalemate), "1/2-1/2", "normal"),
+        (GameResult::Draw(DrawRule::Agreement), "1/2-1/2", "normal"),
+        (
+            GameResult::Draw(DrawRule::TimeoutVsInsufficientMaterial),
+            "1/2-1/2",
+            "time forfeit",
+        ),
+    ];
+
+    for (result, tag, termination) in cases {
+        assert_eq!(result.tag(), tag, "{}", result);
+        assert_eq!(result.termination(), termination, "{}", result);
+    }
+}
+
+#[test]
+fn only_results_of_the_board_are_taken_back() {
+    let winner = Player::White;
+
+    assert!(GameResult::Checkmate { winner }.is_decided_by_board());
+    assert!(GameResult::Draw(DrawRule::Stalemate).is_decided_by_board());
+    assert!(GameResult::Draw(DrawRule::ThreefoldRepetition).is_decided_by_board());
+
+    assert!(!GameResult::Timeout { winner }.is_decided_by_board());
+    assert!(!GameResult::Resignation { winner }.is_decided_by_board());
+    assert!(!GameResult::Draw(DrawRule::Agreement).is_decided_by_board());
+    assert!(!GameResult::Draw(DrawRule::TimeoutVsInsufficientMaterial).is_decided_by_board());
+}
+
+#[test]
+fn a_player_cannot_answer_their_own_draw_offer() {
+    let mut offer = DrawOffer::default();
+
+    assert!(offer.offer(Player::White));
+    assert!(!offer.offer(Player::White));
+    assert!(!offer.offer(Player::Black));
+
+    assert_eq!(offer.accept(Player::White), None);
+    assert!(!offer.decline(Player::White));
+    assert_eq!(offer.offered_by, Some(Player::White));
+
+    assert_eq!(
+        offer.accept(Player::Black),
+        Some(GameResult::Draw(DrawRule::Agreement))
+    );
+    assert_eq!(offer.offered_by, None);
+    assert_eq!(offer.accept(Player::Black), None);
+}
+
+#[test]
+fn moving_declines_the_draw_offer_of_the_other_player() {
+    let mut offer = DrawOffer::default();
+
+    offer.offer(Player::White);
+
+    // The offer is made before the move, and stands until Black answers it.
+    assert!(!offer.move_made(Player::White));
+    assert_eq!(offer.offered_by, Some(Player::White));
+
+    assert!(offer.move_made(Player::Black));
+    assert_eq!(offer.offered_by, None);
+
+    offer.offer(Player::Black);
+    assert!(offer.decline(Player::White));
+    assert_eq!(offer.offered_by, None);
+}
+
+#[test]
+fn the_computer_only_takes_a_draw_when_it_is_behind() {
+    // White is a queen up.
+    let position = Position::from_fen("4k3/8/8/8/8/8/8/3QK3 w - - 0 1").unwrap();
+
+    assert!(computer_accepts_draw(&position, Player::Black));
+    assert!(!computer_accepts_draw(&position, Player::White));
+
+    assert!(!computer_accepts_draw(&Position::new(), Player::White));
+    assert!(!computer_accepts_draw(&Position::new(), Player::Black));
+}
+
+#[test]
+fn resigning_and_agreeing_to_a_draw_end_up_in_the_pgn() {
+    let mut history = History::default();
+    play(&mut history, &["e2e4", "e7e5"]);
+
+    let started = SystemTime::UNIX_EPOCH + Duration::from_secs(1_700_000_000);
+    let control = TimeControl::parse("300+2", TimeBonus::Fischer).unwrap();
+
+    let resigned = GameResult::Resignation {
+        winner: Player::Black,
+    };
+    let tags = game_tags("Alice", "Bob", started, Some(resigned), Some(&control));
+    let pgn = write_pgn(&tags, &history, &[]);
+
+    assert!(pgn.contains("[Result \"0-1\"]\n"), "{}", pgn);
+    assert!(pgn.contains("[TimeControl \"300+2\"]\n"), "{}", pgn);
+    assert!(pgn.contains("[Termination \"normal\"]\n"), "{}", pgn);
+    assert!(pgn.ends_with("1. e4 e5 0-1\n\n"), "{}", pgn);
+
+    let mut offer = DrawOffer::default();
+    offer.offer(Player::White);
+    let agreed = offer.accept(Player::Black);
+
+    let tags = game_tags("Alice", "Bob", started, agreed, None);
+    let pgn = write_pgn(&tags, &history, &[]);
+
+    assert!(pgn.contains("[Result \"1/2-1/2\"]\n"), "{}", pgn);
+    assert!(pgn.contains("[Termination \"normal\"]\n"), "{}", pgn);
+    assert!(!pgn.contains("TimeControl"), "{}", pgn);
+    assert!(pgn.ends_with("1. e4 e5 1/2-1/2\n\n"), "{}", pgn);
+
+    let tags = game_tags("Alice", "Bob", started, None, None);
+    let pgn = write_pgn(&tags, &history, &[]);
+
+    assert!(pgn.contains("[Result \"*\"]\n"), "{}", pgn);
+    assert!(!pgn.contains("Termination"), "{}", pgn);
+    assert!(pgn.ends_with("1. e4 e5 *\n\n"), "{}", pgn);
+}
@@ -0,0 +1,90 @@
+mod common;
+
+use std::time::{Duration, SystemTime};
+
+use chess::game::check::DrawRule;
+use chess::game::clock::{Clock, TimeBonus, TimeControl};
+use chess::game::history::History;
+use chess::game::player::Player;
+use chess::game::result::GameResult;
+use chess::game::save::{load_game, save_game, SaveError, SavedGame, SavedHistory};
+
+use common::play;
+
+fn saved_game(history: &History) -> SavedGame {
+    let mut clock = Clock::new(TimeControl::parse("300+2", TimeBonus::Fischer).unwrap());
+    clock.resume(Player::White);
+    clock.tick(Duration::from_millis(1500));
+
+    SavedGame {
+        history: SavedHistory::new(history),
+        started: SystemTime::UNIX_EPOCH + Duration::from_secs(1_700_000_000),
+        clock: Some(clock),
+        clock_times: vec![Duration::from_secs(299), Duration::from_secs(301)],
+        result: None,
+        draw_offer: Some(Player::Black),
+        flipped: true,
+        white_player_name: "Alice".to_string(),
+        black_player_name: "Bob".to_string(),
+        engine_player: "None".to_string(),
+        ai_player: "Black".to_string(),
+    }
+}
+
+#[test]
+fn moves_taken_back_can_still_be_redone() {
+    let mut history = History::default();
+
+    play(&mut history, &["e2e4", "e7e5", "g1f3", "b8c6"]);
+    history.undo();
+    history.undo();
+
+    let saved = SavedHistory::new(&history);
+    assert_eq!(saved.moves, ["e2e4", "e7e5"]);
+    assert_eq!(saved.undone, ["g1f3", "b8c6"]);
+
+    let mut loaded = saved.to_history().unwrap();
+    assert_eq!(loaded, history);
+
+    loaded.redo();
+    loaded.redo();
+    assert_eq!(loaded.notations, ["e4", "e5", "Nf3", "Nc6"]);
+}
+
+#[test]
+fn games_survive_a_trip_through_the_file() {
+    let mut history = History::default();
+    play(&mut history, &["d2d4", "d7d5"]);
+
+    let mut game = saved_game(&history);
+    let path = std::env::temp_dir().join("chess_save_test.json");
+
+    save_game(&path, &game).unwrap();
+    assert_eq!(load_game(&path).unwrap(), game);
+    assert!(game.is_unfinished());
+
+    game.result = Some(GameResult::Draw(DrawRule::Agreement));
+    save_game(&path, &game).unwrap();
+    assert!(!load_game(&path).unwrap().is_unfinished());
+
+    std::fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn broken_saves_are_rejected() {
+    let mut saved = SavedHistory::new(&History::default());
+    saved.moves.push("e2e5".to_string());
+
+    assert!(matches!(
+        saved.to_history(),
+        Err(SaveError::IllegalMove(uci)) if uci == "e2e5"
+    ));
+
+    let mut saved = SavedHistory::new(&History::default());
+    saved.moves.push("e2e4".to_string());
+
+    assert!(matches!(
+        saved.to_history(),
+        Err(SaveError::PositionMismatch)
+    ));
+}
@@ -0,0 +1,117 @@
+#![cfg(unix)]
+
+use std::fs;
+use std::os::unix::fs::PermissionsExt;
+use std::path::PathBuf;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use chess::game::history::History;
+use chess::game::notation::{from_uci, to_uci};
+use chess::game::position::Position;
+use chess::game::uci::{parse_bestmove, parse_info, Score, UciEngine};
+
+/// A stand-in engine that always answers e7e5, and writes down the commands it got.
+const STAND_IN_ENGINE: &str = r#"#!/bin/sh
+while read -r line; do
+    echo "$line" >> "$0.log"
+    case "$line" in
+        uci) echo "id name Stand-in"; echo "uciok" ;;
+        isready) echo "readyok" ;;
+        go*)
+            echo "info depth 3 score cp -25 nodes 1234 pv e7e5 g1f3"
+            echo "bestmove e7e5 ponder g1f3"
+            ;;
+        quit) exit 0 ;;
+    esac
+done
+"#;
+
+fn stand_in_engine(name: &str) -> PathBuf {
+    let path = std::env::temp_dir().join(format!("{}-{}.sh", name, std::process::id()));
+
+    fs::write(&path, STAND_IN_ENGINE).unwrap();
+    fs::set_permissions(&path, fs::Permissions::from_mode(0o755)).unwrap();
+    let _ = fs::remove_file(path.with_extension("sh.log"));
+
+    path
+}
+
+#[test]
+fn plays_against_stand_in_engine() {
+    let path = stand_in_engine("stand-in-engine");
+    let mut engine = UciEngine::start(path.to_str().unwrap()).unwrap();
+
+    let start = Position::new();
+    let mv = from_uci(&start, "e2e4").unwrap();
+    let mut history = History::new(start);
+    history.push(mv, start.apply_move(mv).unwrap());
+
+    let deadline = Instant::now() + Duration::from_secs(5);
+
+    while !engine.ready {
+        assert!(engine.poll().is_none());
+        assert!(Instant::now() < deadline, "the engine never answered uciok");
+        thread::sleep(Duration::from_millis(10));
+    }
+
+    assert_eq!(engine.name, "Stand-in");
+
+    engine.go(&history, 100).unwrap();
+
+    let (position, best_move) = loop {
+        if let Some(result) = engine.poll() {
+            break result;
+        }
+        assert!(
+            Instant::now() < deadline,
+            "the engine never answered bestmove"
+        );
+        thread::sleep(Duration::from_millis(10));
+    };
+
+    assert_eq!(position, *history.current());
+    assert_eq!(best_move.as_deref(), Some("e7e5"));
+    assert_eq!(
+        from_uci(&position, "e7e5").map(to_uci).as_deref(),
+        Some("e7e5")
+    );
+    assert_eq!(engine.info.depth, Some(3));
+    assert_eq!(engine.info.score, Some(Score::Centipawns(-25)));
+    assert_eq!(engine.info.pv, vec!["e7e5", "g1f3"]);
+
+    drop(engine);
+
+    let log = fs::read_to_string(path.with_extension("sh.log")).unwrap();
+    let commands: Vec<&str> = log.lines().collect();
+
+    assert_eq!(
+        commands[..3],
+        [
+            "uci",
+            "position fen rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1 moves e2e4",
+            "go movetime 100",
+        ]
+    );
+
+    let _ = fs::remove_file(&path);
+    let _ = fs::remove_file(path.with_extension("sh.log"));
+}
+
+#[test]
+fn parses_engine_output() {
+    let info = parse_info("info depth 12 seldepth 18 score mate -3 nodes 99 nps 1000 pv a2a3 b7b6")
+        .unwrap();
+
+    assert_eq!(info.depth, Some(12));
+    assert_eq!(info.score, Some(Score::Mate(-3)));
+    assert_eq!(info.nodes, Some(99));
+    assert_eq!(info.pv, vec!["a2a3", "b7b6"]);
+
+    assert_eq!(parse_info("bestmove e2e4"), None);
+    assert_eq!(
+        parse_bestmove("bestmove e7e8q ponder a1a2").as_deref(),
+        Some("e7e8q")
+    );
+    assert_eq!(parse_bestmove("bestmove (none)"), None);
+}
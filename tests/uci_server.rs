@@ -0,0 +1,226 @@
+use std::io::{BufRead, BufReader, Write};
+use std::process::{Child, ChildStdin, Command, Stdio};
+use std::sync::mpsc::{self, Receiver};
+use std::thread;
+use std::time::Duration;
+
+/// The engine binary running in its own process, talking UCI over its stdin and stdout.
+struct Server {
+    child: Child,
+    stdin: ChildStdin,
+    lines: Receiver<String>,
+}
+
+impl Server {
+    fn start() -> Server {
+        let mut child = Command::new(env!("CARGO_BIN_EXE_uci"))
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .spawn()
+            .unwrap();
+
+        let stdin = child.stdin.take().unwrap();
+        let stdout = child.stdout.take().unwrap();
+        let (sender, lines) = mpsc::channel();
+
+        thread::spawn(move || {
+            for line in BufReader::new(stdout).lines().map_while(Result::ok) {
+                if sender.send(line).is_err() {
+                    break;
+                }
+            }
+        });
+
+        Server {
+            child,
+            stdin,
+            lines,
+        }
+    }
+
+    fn send(&mut self, command: &str) {
+        writeln!(self.stdin, "{}", command).unwrap();
+        self.stdin.flush().unwrap();
+    }
+
+    /// Reads the output up to and including the first line starting with the prefix.
+    fn read_until(&self, prefix: &str) -> Vec<String> {
+        let mut lines = Vec::new();
+
+        loop {
+            let line = self
+                .lines
+                .recv_timeout(Duration::from_secs(10))
+                .unwrap_or_else(|_| panic!("no {} after {:?}", prefix, lines));
+            let done = line.starts_with(prefix);
+
+            lines.push(line);
+
+            if done {
+                return lines;
+            }
+        }
+    }
+
+    /// Searches the position set before, returning the output and the best move.
+    fn go(&mut self, command: &str) -> (Vec<String>, String) {
+        self.send(command);
+
+        let lines = self.read_until("bestmove");
+        let best_move = lines[lines.len() - 1]["bestmove ".len()..].to_string();
+
+        (lines, best_move)
+    }
+}
+
+impl Drop for Server {
+    fn drop(&mut self) {
+        let _ = writeln!(self.stdin, "quit");
+        let _ = self.child.wait();
+    }
+}
+
+/// Gets the depths of the info lines.
+fn depths(lines: &[String]) -> Vec<u32> {
+    lines
+        .iter()
+        .filter_map(|line| line.strip_prefix("info depth "))
+        .map(|rest| rest.split(' ').next().unwrap().parse().unwrap())
+        .collect()
+}
+
+#[test]
+fn answers_uci_and_isready() {
+    let mut server = Server::start();
+
+    server.send("uci");
+    let lines = server.read_until("uciok");
+
+    assert_eq!(lines[0], "id name Chess");
+    assert!(lines
+        .iter()
+        .any(|line| line == "option name Hash type spin default 16 min 1 max 1024"));
+    assert!(lines
+        .iter()
+        .any(|line| line == "option name Skill Level type spin default 20 min 0 max 20"));
+
+    server.send("isready");
+    assert_eq!(server.read_until("readyok"), ["readyok"]);
+}
+
+#[test]
+fn plays_from_the_position_with_the_moves() {
+    let mut server = Server::start();
+
+    server.send("position startpos moves e2e4 e7e5 g1f3");
+    let (lines, best_move) = server.go("go depth 2");
+
+    assert_eq!(depths(&lines), [1, 2]);
+    // Black is on turn.
+    assert!(
+        best_move.ends_with('6') || best_move.ends_with('5'),
+        "{}",
+        best_move
+    );
+
+    server.send("position fen 6k1/5ppp/8/8/8/8/8/R5K1 w - - 0 1");
+    let (lines, best_move) = server.go("go depth 3");
+
+    assert_eq!(best_move, "a1a8");
+    assert!(lines.iter().any(|line| line.contains("score mate 1")));
+
+    server.send("position fen 6k1/5ppp/8/8/8/8/8/R5K1 w - - 0 1 moves a1a8");
+    assert_eq!(server.go("go depth 1").1, "0000");
+}
+
+#[test]
+fn stops_reading_moves_at_an_illegal_one() {
+    let mut server = Server::start();
+
+    server.send("position startpos moves e2e4 e2e4 e7e5");
+    assert_eq!(
+        server.read_until("info string"),
+        ["info string illegal move e2e4"]
+    );
+
+    // The moves up to the illegal one are played, so Black is on turn.
+    let (_, best_move) = server.go("go depth 1");
+
+    assert!(
+        best_move.ends_with('6') || best_move.ends_with('5'),
+        "{}",
+        best_move
+    );
+}
+
+#[test]
+fn searches_on_the_clock() {
+    let mut server = Server::start();
+
+    server.send("position startpos");
+    let (_, best_move) = server.go("go wtime 1000 btime 1000 winc 0 binc 0");
+    assert_eq!(best_move.len(), 4);
+
+    let (_, best_move) = server.go("go movetime 100");
+    assert_eq!(best_move.len(), 4);
+}
+
+#[test]
+fn an_infinite_search_answers_once_when_stopped() {
+    let mut server = Server::start();
+
+    server.send("position startpos");
+    server.send("go infinite");
+
+    thread::sleep(Duration::from_millis(200));
+
+    server.send("isready");
+    let lines = server.read_until("readyok");
+    assert!(!lines.iter().any(|line| line.starts_with("bestmove")));
+
+    server.send("stop");
+    server.send("isready");
+
+    let lines = server.read_until("readyok");
+    let best_moves = lines
+        .iter()
+        .filter(|line| line.starts_with("bestmove"))
+        .count();
+
+    assert_eq!(best_moves, 1, "{:?}", lines);
+
+    // Stopping again, with no search running, does not answer again.
+    server.send("stop");
+    server.send("isready");
+
+    assert_eq!(server.read_until("readyok"), ["readyok"]);
+}
+
+#[test]
+fn clamps_the_options() {
+    let mut server = Server::start();
+
+    // A skill level below 0 is the lowest one, which only searches one half-move deep.
+    server.send("setoption name Skill Level value -5");
+    server.send("position startpos");
+    let (lines, _) = server.go("go depth 3");
+    assert_eq!(depths(&lines), [1]);
+
+    server.send("setoption name Skill Level value 4294967296");
+    let (lines, _) = server.go("go depth 3");
+    assert_eq!(depths(&lines), [1, 2, 3]);
+
+    // A table of at least one megabyte is made, not one as large as it gets.
+    for hash in ["0", "-1"] {
+        server.send(&format!("setoption name Hash value {}", hash));
+        server.send("isready");
+        assert_eq!(server.read_until("readyok"), ["readyok"]);
+
+        let (lines, _) = server.go("go depth 2");
+        assert_eq!(depths(&lines), [1, 2]);
+    }
+
+    server.send("setoption name Hash value 1");
+    server.send("isready");
+    assert_eq!(server.read_until("readyok"), ["readyok"]);
+}
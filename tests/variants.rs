@@ -0,0 +1,528 @@
+mod common;
+
+use chess::game::check::{self, game_result, is_tie};
+use chess::game::engine::{Engine, SearchLimits};
+use chess::game::history::History;
+use chess::game::moves::Move;
+use chess::game::notation::{from_san, from_uci, to_san, to_uci};
+use chess::game::perft::perft;
+use chess::game::pgn::{parse_pgn, write_pgn};
+use chess::game::pieces::PieceKind;
+use chess::game::player::Player;
+use chess::game::position::{IllegalMove, Position};
+use chess::game::result::GameResult;
+use chess::game::variant::VariantKind;
+use chess::game::zobrist;
+
+use common::play;
+
+fn new_game(variant: VariantKind) -> History {
+    History::new(
+        variant
+            .rules()
+            .start_position(Position::new().with_variant(variant)),
+    )
+}
+
+#[test]
+fn parses_variant_names() {
+    for variant in VariantKind::ALL {
+        assert_eq!(VariantKind::parse(&variant.to_string()), Some(variant));
+        assert_eq!(VariantKind::parse(variant.uci_name()), Some(variant));
+    }
+
+    assert_eq!(
+        VariantKind::parse("king-of-the-hill"),
+        Some(VariantKind::KingOfTheHill)
+    );
+    assert_eq!(
+        VariantKind::parse("threecheck"),
+        Some(VariantKind::ThreeCheck)
+    );
+    assert_eq!(VariantKind::parse("Chess960"), None);
+}
+
+#[test]
+fn only_atomic_explodes_and_only_crazyhouse_has_pockets() {
+    for variant in VariantKind::ALL {
+        let rules = variant.rules();
+
+        assert_eq!(rules.explodes_on_capture(), variant == VariantKind::Atomic);
+        assert_eq!(rules.has_pockets(), variant == VariantKind::Crazyhouse);
+        assert_eq!(rules.counts_checks(), variant == VariantKind::ThreeCheck);
+    }
+}
+
+#[test]
+fn king_of_the_hill_is_won_in_the_center() {
+    let mut history = new_game(VariantKind::KingOfTheHill);
+
+    play(
+        &mut history,
+        &["e2e4", "e7e5", "e1e2", "d7d6", "e2d3", "c8g4"],
+    );
+    assert_eq!(game_result(&history), None);
+
+    play(&mut history, &["d3c4", "g4h5"]);
+    assert_eq!(game_result(&history), None);
+
+    play(&mut history, &["c4d5"]);
+    let result = game_result(&history).unwrap();
+
+    assert_eq!(
+        result,
+        GameResult::Variant {
+            winner: Player::White,
+            variant: VariantKind::KingOfTheHill
+        }
+    );
+    assert_eq!(result.to_string(), "White wins by reaching the center");
+
+    // The same moves are only a normal game in standard chess.
+    let mut standard = History::default();
+
+    play(
+        &mut standard,
+        &[
+            "e2e4", "e7e5", "e1e2", "d7d6", "e2d3", "c8g4", "d3c4", "g4h5", "c4d5",
+        ],
+    );
+    assert_eq!(game_result(&standard), None);
+}
+
+#[test]
+fn lone_kings_can_still_win_king_of_the_hill() {
+    let position = Position::from_fen("4k3/8/8/8/8/8/8/4K3 w - - 0 1").unwrap();
+
+    assert!(is_tie(&position));
+    assert!(!is_tie(&position.with_variant(VariantKind::KingOfTheHill)));
+}
+
+#[test]
+fn three_checks_win() {
+    let mut history = new_game(VariantKind::ThreeCheck);
+
+    play(&mut history, &["e2e4", "e7e5", "f1c4", "b8c6", "c4f7"]);
+    assert_eq!(history.current().checks, [1, 0]);
+
+    play(&mut history, &["e8f7", "d1h5"]);
+    assert_eq!(history.current().checks, [2, 0]);
+    assert_eq!(game_result(&history), None);
+
+    // Taking the queen back would be fine in standard chess, but the third check already won.
+    play(&mut history, &["f7e7", "h5e5"]);
+    assert_eq!(history.current().checks, [3, 0]);
+
+    assert_eq!(
+        game_result(&history),
+        Some(GameResult::Variant {
+            winner: Player::White,
+            variant: VariantKind::ThreeCheck
+        })
+    );
+}
+
+#[test]
+fn three_check_fen_has_the_checks_left() {
+    let mut history = new_game(VariantKind::ThreeCheck);
+
+    assert_eq!(
+        history.current().to_fen(),
+        "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 3+3 0 1"
+    );
+
+    play(&mut history, &["e2e4", "f7f6", "d1h5"]);
+
+    let fen = history.current().to_fen();
+    assert_eq!(
+        fen,
+        "rnbqkbnr/ppppp1pp/5p2/7Q/4P3/8/PPPP1PPP/RNB1KBNR b KQkq - 2+3 1 2"
+    );
+
+    let position = Position::from_fen(&fen).unwrap();
+    assert_eq!(position.variant, VariantKind::ThreeCheck);
+    assert_eq!(position, *history.current());
+
+    for invalid in ["4+3", "3+", "a+b"] {
+        let fen = format!("4k3/8/8/8/8/8/8/4K3 w - - {} 0 1", invalid);
+
+        assert!(Position::from_fen(&fen).is_err(), "{}", fen);
+    }
+}
+
+#[test]
+fn variant_tag_round_trips_through_pgn() {
+    let mut history = new_game(VariantKind::KingOfTheHill);
+
+    play(&mut history, &["e2e4", "e7e5"]);
+
+    let pgn = write_pgn(&[("Result".to_string(), "*".to_string())], &history, &[]);
+
+    assert!(pgn.contains("[Variant \"King of the Hill\"]"));
+    assert!(!pgn.contains("[FEN"));
+
+    let games = parse_pgn(&pgn).unwrap();
+
+    assert_eq!(games[0].start.variant, VariantKind::KingOfTheHill);
+    assert_eq!(games[0].history().current(), history.current());
+}
+
+#[test]
+fn perft_atomic() {
+    // The FEN, and the perft counts from depth 1 upwards.
+    let cases: [(&str, &[u64]); 3] = [
+        (
+            "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+            &[20, 400, 8902],
+        ),
+        (
+            "rn2kb1r/1pp1p2p/p2q1pp1/3P4/2P3b1/4PN2/PP3PPP/R2QKB1R b KQkq - 0 1",
+            &[40, 1238, 45237],
+        ),
+        (
+            "rn1qkb1r/p5pp/2p5/3p4/N3P3/5P2/PPP4P/R1BQK3 w Qkq - 0 1",
+            &[28, 833, 23353],
+        ),
+    ];
+
+    for (fen, counts) in cases {
+        let position = Position::from_fen(fen)
+            .unwrap()
+            .with_variant(VariantKind::Atomic);
+
+        for (depth, count) in counts.iter().enumerate() {
+            assert_eq!(perft(&position, depth + 1), *count, "{}", fen);
+        }
+    }
+}
+
+#[test]
+fn captures_explode() {
+    let position = Position::from_fen("4k3/8/2n5/1pqr4/2B5/8/8/4K3 w - - 0 1")
+        .unwrap()
+        .with_variant(VariantKind::Atomic);
+
+    let mv = from_uci(&position, "c4b5").unwrap();
+    let next = position.apply_move(mv).unwrap();
+
+    // The bishop and everything around b5 is gone, but pawns only blow up when they are taken.
+    assert_eq!(next.to_fen(), "4k3/8/8/3r4/8/8/8/4K3 b - - 0 1");
+}
+
+#[test]
+fn kings_next_to_each_other_cannot_be_checked() {
+    let position = Position::from_fen("8/8/8/4k3/8/4K3/8/4r3 w - - 0 1").unwrap();
+    let atomic = position.with_variant(VariantKind::Atomic);
+
+    assert!(atomic.in_check());
+
+    // Next to the other king the rook does not give check anymore, which standard chess does not allow.
+    let mv = Move::new((5, 4), (4, 4));
+
+    assert_eq!(position.apply_move(mv), Err(IllegalMove::KingInCheck));
+
+    let next = atomic.apply_move(mv).unwrap();
+
+    assert!(check::check(next.board, Player::White));
+    assert!(!check::atomic_check(next.board, Player::White));
+}
+
+#[test]
+fn castling_next_to_the_other_king_is_safe() {
+    // The rook on e8, the queen and the rook on g8 cover every square the white king crosses.
+    let mut history = History::new(
+        Position::from_fen("4r1r1/8/q7/8/8/4k3/8/4K2R b K - 0 1")
+            .unwrap()
+            .with_variant(VariantKind::Atomic),
+    );
+
+    // With the black king next to them none of them gives check.
+    play(&mut history, &["e3f2"]);
+    assert!(check::check(history.current().board, Player::White));
+    assert!(!history.current().in_check());
+
+    play(&mut history, &["e1g1"]);
+    assert_eq!(
+        history.current().to_fen(),
+        "4r1r1/8/q7/8/8/8/5k2/5RK1 b - - 2 2"
+    );
+}
+
+#[test]
+fn kings_cannot_capture() {
+    let position = Position::from_fen("8/8/8/4k3/8/3nK3/8/8 w - - 0 1")
+        .unwrap()
+        .with_variant(VariantKind::Atomic);
+
+    assert_eq!(
+        position.apply_move(Move::new((5, 4), (5, 3))),
+        Err(IllegalMove::VariantRule)
+    );
+}
+
+#[test]
+fn exploding_the_king_wins() {
+    let mut history = History::new(
+        Position::from_fen("rnbqkbnr/pppp1ppp/8/4p3/8/5N2/PPPPPPPP/RNBQKB1R w KQkq - 0 2")
+            .unwrap()
+            .with_variant(VariantKind::Atomic),
+    );
+
+    // Taking on f7 blows up the king on e8.
+    play(&mut history, &["f3g5", "d7d6", "g5f7"]);
+
+    assert_eq!(
+        game_result(&history),
+        Some(GameResult::Variant {
+            winner: Player::White,
+            variant: VariantKind::Atomic
+        })
+    );
+    assert_eq!(history.notations[2], "Nxf7#");
+}
+
+#[test]
+fn perft_crazyhouse() {
+    let position = Position::new().with_variant(VariantKind::Crazyhouse);
+
+    // Nothing can be captured and dropped back before the fourth half-move.
+    for (depth, count) in [20, 400, 8902, 197281].into_iter().enumerate() {
+        assert_eq!(perft(&position, depth + 1), count);
+    }
+}
+
+#[test]
+fn captured_pieces_go_into_the_pocket() {
+    let mut history = new_game(VariantKind::Crazyhouse);
+
+    play(&mut history, &["e2e4", "d7d5", "e4d5", "d8d5"]);
+
+    assert_eq!(history.current().pocket(Player::White), [1, 0, 0, 0, 0]);
+    assert_eq!(history.current().pocket(Player::Black), [1, 0, 0, 0, 0]);
+
+    play(&mut history, &["P@e6", "f7e6"]);
+
+    assert_eq!(history.current().pocket(Player::White), [0, 0, 0, 0, 0]);
+    assert_eq!(history.current().pocket(Player::Black), [2, 0, 0, 0, 0]);
+    assert_eq!(history.notations[4], "P@e6");
+}
+
+#[test]
+fn promoted_pieces_go_back_to_pawns() {
+    let position = Position::from_fen("r3k3/1P6/8/8/8/8/8/4K3[] w - - 0 1").unwrap();
+
+    assert_eq!(position.variant, VariantKind::Crazyhouse);
+
+    let position = position
+        .apply_move(from_uci(&position, "b7b8q").unwrap())
+        .unwrap();
+
+    assert_eq!(position.to_fen(), "rQ~2k3/8/8/8/8/8/8/4K3[] b - - 0 1");
+
+    let position = position
+        .apply_move(from_uci(&position, "a8b8").unwrap())
+        .unwrap();
+
+    assert_eq!(position.pocket(Player::Black), [1, 0, 0, 0, 0]);
+    assert_eq!(position.to_fen(), "1r2k3/8/8/8/8/8/8/4K3[p] w - - 0 2");
+}
+
+#[test]
+fn drops_follow_the_rules() {
+    let position = Position::from_fen("4k3/8/8/8/8/8/8/R3K3[Pn] w - - 0 1").unwrap();
+
+    // Pawns cannot go on the first or last rank.
+    assert!(from_uci(&position, "P@d8").is_none());
+    assert!(from_uci(&position, "P@a2").is_some());
+    assert_eq!(
+        position.apply_move(Move::drop(PieceKind::Pawn, (7, 1))),
+        Err(IllegalMove::InvalidDrop)
+    );
+
+    // Only pieces in the own pocket can be dropped, and only on empty squares.
+    assert!(from_uci(&position, "N@c3").is_none());
+    assert!(from_uci(&position, "P@a1").is_none());
+
+    // In check, a piece can only be dropped in between.
+    let position = Position::from_fen("4k2R/8/8/8/8/8/8/4K3[n] b - - 0 1").unwrap();
+    let drops: Vec<(usize, usize)> = position
+        .legal_drops(PieceKind::Knight)
+        .into_iter()
+        .map(|mv| mv.to)
+        .collect();
+
+    assert_eq!(drops, [(0, 5), (0, 6)]);
+}
+
+#[test]
+fn drops_are_written_with_an_at_sign() {
+    let position = Position::from_fen("4k3/8/8/8/8/8/8/4K3[N] w - - 0 1").unwrap();
+    let mv = from_san(&position, "N@d6").unwrap();
+
+    assert_eq!(mv, Move::drop(PieceKind::Knight, (2, 3)));
+    assert_eq!(to_san(&position, mv), "N@d6+");
+    assert_eq!(to_uci(mv), "N@d6");
+    assert_eq!(from_uci(&position, "n@d6"), Some(mv));
+
+    let position = Position::from_fen("4k3/8/8/8/8/8/8/4K3[P] w - - 0 1").unwrap();
+
+    assert_eq!(from_san(&position, "@e4"), from_san(&position, "P@e4"));
+    assert_eq!(
+        to_san(&position, from_san(&position, "@e4").unwrap()),
+        "P@e4"
+    );
+}
+
+#[test]
+fn crazyhouse_fen_has_the_pockets() {
+    let fen = "r1bqk2r/pppp1ppp/2n5/4p3/1b2P3/2N2N2/PPPP1PPP/R1BQK2R~[Bn] b KQkq - 0 5";
+    let position = Position::from_fen(fen).unwrap();
+
+    assert_eq!(position.variant, VariantKind::Crazyhouse);
+    assert_eq!(position.pocket(Player::White), [0, 0, 1, 0, 0]);
+    assert_eq!(position.pocket(Player::Black), [0, 1, 0, 0, 0]);
+    assert_eq!(position.to_fen(), fen);
+
+    // Pieces change sides, so more of them than in a real game are fine.
+    assert!(Position::from_fen("4k3/8/8/8/8/8/NNNNNNNN/NNNNKNNN[] w - - 0 1").is_ok());
+    assert!(Position::from_fen("4k3/8/8/8/8/8/NNNNNNNN/NNNNKNNN w - - 0 1").is_err());
+
+    for invalid in ["[K]", "[Q", "[x]"] {
+        let fen = format!("4k3/8/8/8/8/8/8/4K3{} w - - 0 1", invalid);
+
+        assert!(Position::from_fen(&fen).is_err(), "{}", fen);
+    }
+}
+
+fn antichess(fen: &str) -> Position {
+    Position::from_variant_fen(fen, VariantKind::Antichess).unwrap()
+}
+
+#[test]
+fn perft_antichess() {
+    let position = new_game(VariantKind::Antichess).current().to_owned();
+
+    for (depth, count) in [20, 400, 8067, 153299].into_iter().enumerate() {
+        assert_eq!(perft(&position, depth + 1), count);
+    }
+}
+
+#[test]
+fn captures_are_forced() {
+    let mut history = new_game(VariantKind::Antichess);
+
+    play(&mut history, &["e2e3", "b7b5"]);
+
+    let position = history.current();
+
+    assert_eq!(position.legal_moves(), [Move::new((7, 5), (3, 1))]);
+    assert_eq!(position.legal_moves_from((6, 3)), []);
+    assert_eq!(
+        position.apply_move(Move::new((6, 3), (5, 3))),
+        Err(IllegalMove::VariantRule)
+    );
+}
+
+#[test]
+fn kings_can_be_taken_and_promoted_to() {
+    let position = antichess("4k3/1P6/8/8/8/8/8/8 w - - 0 1");
+
+    // Without a king of its own, White has nothing to be checked with.
+    assert!(Position::from_fen("4k3/1P6/8/8/8/8/8/8 w - - 0 1").is_err());
+
+    let mv = from_san(&position, "b8=K").unwrap();
+
+    assert_eq!(
+        mv,
+        Move::new((1, 1), (0, 1)).with_promotion(PieceKind::King)
+    );
+    assert_eq!(to_san(&position, mv), "b8=K");
+    assert_eq!(position.apply_move(mv).unwrap().piece_at((0, 1)), 'K');
+
+    let standard = Position::from_fen("4k3/1P6/8/8/8/8/8/4K3 w - - 0 1").unwrap();
+
+    assert_eq!(standard.apply_move(mv), Err(IllegalMove::InvalidPromotion));
+
+    // Kings can walk into attacks and be taken like any other piece.
+    let position = antichess("8/8/8/8/8/8/1r6/K7 w - - 0 1");
+
+    assert!(!position.in_check());
+    assert_eq!(
+        position.legal_moves(),
+        [Move::new((7, 0), (6, 1))],
+        "taking the rook is forced"
+    );
+}
+
+#[test]
+fn losing_all_pieces_wins() {
+    let mut history = History::new(antichess("8/8/8/8/8/8/1p6/R7 b - - 0 1"));
+
+    play(&mut history, &["b2a1q"]);
+
+    let result = game_result(&history).unwrap();
+
+    assert_eq!(
+        result,
+        GameResult::Variant {
+            winner: Player::White,
+            variant: VariantKind::Antichess
+        }
+    );
+    assert_eq!(result.to_string(), "White wins by having no moves left");
+    assert_eq!(history.notations[0], "bxa1=Q#");
+}
+
+#[test]
+fn being_stalemated_wins() {
+    let position = antichess("8/8/8/8/8/p7/P7/8 w - - 0 1");
+
+    assert_eq!(check::variant_winner(&position), Some(Player::White));
+    assert!(!is_tie(&position));
+}
+
+#[test]
+fn bishops_on_different_colours_are_a_draw() {
+    assert!(is_tie(&antichess("8/8/8/8/8/8/8/1B4b1 w - - 0 1")));
+    assert!(!is_tie(&antichess("8/8/8/8/8/8/8/B5b1 w - - 0 1")));
+}
+
+#[test]
+fn promoted_pieces_change_the_hash() {
+    let promoted = Position::from_fen("4k3/8/8/8/8/8/8/Q~3K3[] w - - 0 1").unwrap();
+    let original = Position::from_fen("4k3/8/8/8/8/8/8/Q3K3[] w - - 0 1").unwrap();
+
+    assert_ne!(zobrist::hash(&promoted), zobrist::hash(&original));
+}
+
+#[test]
+fn engine_searches_forced_captures() {
+    // Giving away the rook forces g7xh6, which the engine only sees if it does not stand pat.
+    let history = History::new(antichess("8/6p1/8/8/8/8/8/1N5R w - - 0 1"));
+    let limits = SearchLimits {
+        depth: Some(1),
+        movetime: None,
+        randomness: 0,
+    };
+
+    let result = Engine::new(1).search(&history, limits, |_| ());
+
+    assert_eq!(result.best_move.map(to_uci).as_deref(), Some("h1h6"));
+    assert!(result.info.score > -400, "{}", result.info.score);
+}
+
+#[test]
+fn antichess_has_no_castling_rights() {
+    let position = antichess("r3k2r/8/8/8/8/8/8/R3K2R w KQkq - 0 1");
+
+    assert_eq!(position.castling, [None; 4]);
+    assert_eq!(position.to_fen(), "r3k2r/8/8/8/8/8/8/R3K2R w - - 0 1");
+    assert_eq!(
+        zobrist::hash(&position),
+        zobrist::hash(&antichess("r3k2r/8/8/8/8/8/8/R3K2R w - - 0 1"))
+    );
+    assert_eq!(
+        new_game(VariantKind::Antichess).current().to_fen(),
+        "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w - - 0 1"
+    );
+}